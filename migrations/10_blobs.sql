@@ -0,0 +1,20 @@
+CREATE TABLE blobs(
+    content_hash CHAR(64) PRIMARY KEY,
+    ref_count INTEGER NOT NULL DEFAULT 0 CHECK (ref_count >= 0),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+-- snapshot contents were already stored by hash, each file holds a reference
+INSERT INTO blobs (content_hash, ref_count)
+SELECT content_hash, COUNT(*)
+FROM snapshot_files
+GROUP BY content_hash;
+
+ALTER TABLE snapshot_files
+ADD CONSTRAINT snapshot_files_content_hash_key
+FOREIGN KEY (content_hash)
+REFERENCES blobs (content_hash);
+
+-- resources without a hash still live under their project directory
+ALTER TABLE resources
+ADD COLUMN content_hash CHAR(64) REFERENCES blobs(content_hash);
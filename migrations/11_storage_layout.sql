@@ -0,0 +1,4 @@
+CREATE TABLE storage_migrations(
+    name TEXT PRIMARY KEY,
+    applied_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
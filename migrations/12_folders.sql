@@ -0,0 +1,30 @@
+CREATE TABLE folders(
+    folder_id SERIAL PRIMARY KEY,
+    project_id INTEGER REFERENCES projects(project_id) NOT NULL,
+    parent_id INTEGER REFERENCES folders(folder_id),
+    name VARCHAR(128) NOT NULL
+);
+
+-- names are unique within their folder, the project root has no folder
+CREATE UNIQUE INDEX folders_root_name_unique
+ON folders (project_id, name) WHERE parent_id IS NULL;
+CREATE UNIQUE INDEX folders_parent_name_unique
+ON folders (parent_id, name) WHERE parent_id IS NOT NULL;
+
+ALTER TABLE documents
+ADD COLUMN folder_id INTEGER REFERENCES folders(folder_id);
+
+ALTER TABLE resources
+ADD COLUMN folder_id INTEGER REFERENCES folders(folder_id);
+
+ALTER TABLE resources
+DROP CONSTRAINT project_id_name_unique;
+
+CREATE UNIQUE INDEX resources_root_name_unique
+ON resources (project_id, name) WHERE folder_id IS NULL;
+CREATE UNIQUE INDEX resources_folder_name_unique
+ON resources (folder_id, name) WHERE folder_id IS NOT NULL;
+
+-- snapshot files are recorded by their path inside the project
+ALTER TABLE snapshot_files
+ALTER COLUMN name TYPE TEXT;
@@ -0,0 +1,19 @@
+-- sizes of files written before this migration are measured on startup
+ALTER TABLE documents
+ADD COLUMN size BIGINT NOT NULL DEFAULT 0;
+
+ALTER TABLE resources
+ADD COLUMN size BIGINT NOT NULL DEFAULT 0;
+
+ALTER TABLE snapshot_files
+ADD COLUMN size BIGINT NOT NULL DEFAULT 0;
+
+CREATE VIEW project_usage AS
+SELECT
+    p.project_id,
+    p.owner_id,
+    (
+        COALESCE((SELECT SUM(d.size) FROM documents AS d WHERE d.project_id = p.project_id), 0)
+        + COALESCE((SELECT SUM(r.size) FROM resources AS r WHERE r.project_id = p.project_id), 0)
+    )::BIGINT AS used_bytes
+FROM projects AS p;
@@ -0,0 +1,12 @@
+CREATE TABLE data_keys(
+    key_id SERIAL PRIMARY KEY,
+    project_id INTEGER REFERENCES projects(project_id),
+    wrapped_key BYTEA NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+-- each project has one key, content-addressed objects share the single key without a project
+CREATE UNIQUE INDEX data_keys_project_unique
+ON data_keys (project_id) WHERE project_id IS NOT NULL;
+CREATE UNIQUE INDEX data_keys_shared_unique
+ON data_keys ((project_id IS NULL)) WHERE project_id IS NULL;
@@ -0,0 +1,2 @@
+-- expired sessions are purged periodically
+CREATE INDEX sessions_expires ON sessions (expires);
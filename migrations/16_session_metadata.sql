@@ -0,0 +1,7 @@
+ALTER TABLE sessions
+ADD COLUMN created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+ADD COLUMN last_seen TIMESTAMP NOT NULL DEFAULT NOW(),
+ADD COLUMN ip VARCHAR(45),
+ADD COLUMN user_agent TEXT;
+
+CREATE INDEX sessions_user_id ON sessions (user_id);
@@ -0,0 +1,9 @@
+-- only a hash of each token is kept, the token itself is sent to the user
+CREATE TABLE password_resets(
+    token_hash CHAR(64) PRIMARY KEY,
+    user_id INTEGER REFERENCES users(user_id) NOT NULL,
+    expires_at TIMESTAMP NOT NULL,
+    used_at TIMESTAMP
+);
+
+CREATE INDEX password_resets_user_id ON password_resets (user_id);
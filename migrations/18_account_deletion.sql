@@ -0,0 +1,3 @@
+-- deleted users stay as anonymous rows, so the entries they authored in other projects remain
+ALTER TABLE users
+ADD COLUMN deleted_at TIMESTAMP;
@@ -0,0 +1,6 @@
+ALTER TABLE users
+ADD COLUMN display_name VARCHAR(128),
+ADD COLUMN affiliation VARCHAR(256),
+ADD COLUMN orcid CHAR(19),
+-- the avatar itself is stored under users/<id>/avatar, this is only set while one exists
+ADD COLUMN avatar_content_type VARCHAR(128);
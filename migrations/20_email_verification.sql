@@ -0,0 +1,15 @@
+ALTER TABLE users
+ADD COLUMN verified_at TIMESTAMP;
+
+-- accounts created before verification existed are trusted as they are
+UPDATE users SET verified_at = created_at WHERE deleted_at IS NULL;
+
+-- only a hash of each token is kept, the token itself is sent to the user
+CREATE TABLE email_verifications(
+    token_hash CHAR(64) PRIMARY KEY,
+    user_id INTEGER REFERENCES users(user_id) NOT NULL,
+    expires_at TIMESTAMP NOT NULL,
+    used_at TIMESTAMP
+);
+
+CREATE INDEX email_verifications_user_id ON email_verifications (user_id);
@@ -0,0 +1,15 @@
+-- only a hash of each token is kept, the token itself is shown once on creation
+CREATE TABLE api_tokens(
+    token_id SERIAL PRIMARY KEY,
+    token_hash CHAR(64) NOT NULL UNIQUE,
+    user_id INTEGER REFERENCES users(user_id) NOT NULL,
+    name VARCHAR(128) NOT NULL,
+    scope TEXT NOT NULL,
+    -- the token only works for this project when set
+    project_id INTEGER REFERENCES projects(project_id),
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMP NOT NULL,
+    last_used_at TIMESTAMP
+);
+
+CREATE INDEX api_tokens_user_id ON api_tokens (user_id);
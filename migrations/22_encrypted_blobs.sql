@@ -0,0 +1,5 @@
+-- the data key each stored blob was encrypted with, blobs without a row are stored as they are
+CREATE TABLE encrypted_blobs(
+    blob_key TEXT PRIMARY KEY,
+    key_id INTEGER REFERENCES data_keys(key_id) NOT NULL
+);
@@ -0,0 +1,26 @@
+CREATE TABLE comment_threads(
+    thread_id SERIAL PRIMARY KEY,
+    project_id INTEGER REFERENCES projects(project_id) NOT NULL,
+    document_id INTEGER REFERENCES documents(document_id) NOT NULL,
+    author_id INTEGER REFERENCES users(user_id) NOT NULL,
+    anchor_start INTEGER NOT NULL,
+    anchor_end INTEGER NOT NULL,
+    resolved BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE comments(
+    comment_id SERIAL PRIMARY KEY,
+    thread_id INTEGER REFERENCES comment_threads(thread_id) NOT NULL,
+    author_id INTEGER REFERENCES users(user_id) NOT NULL,
+    content TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE comment_mentions(
+    comment_id INTEGER REFERENCES comments(comment_id) NOT NULL,
+    user_id INTEGER REFERENCES users(user_id) NOT NULL,
+    PRIMARY KEY (comment_id, user_id)
+);
+
+CREATE INDEX comment_threads_document_id ON comment_threads (document_id);
@@ -0,0 +1,16 @@
+ALTER TABLE projects
+ADD COLUMN track_changes BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE TABLE suggestions(
+    suggestion_id SERIAL PRIMARY KEY,
+    project_id INTEGER REFERENCES projects(project_id) NOT NULL,
+    document_id INTEGER REFERENCES documents(document_id) NOT NULL,
+    author_id INTEGER REFERENCES users(user_id) NOT NULL,
+    kind TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    content TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending',
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX suggestions_document_id_status ON suggestions (document_id, status);
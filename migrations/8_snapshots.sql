@@ -0,0 +1,15 @@
+CREATE TABLE snapshots(
+    snapshot_id SERIAL PRIMARY KEY,
+    project_id INTEGER REFERENCES projects(project_id) NOT NULL,
+    author_id INTEGER REFERENCES users(user_id) NOT NULL,
+    name VARCHAR(128) NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE snapshot_files(
+    snapshot_id INTEGER REFERENCES snapshots(snapshot_id) NOT NULL,
+    kind TEXT NOT NULL,
+    name VARCHAR(128) NOT NULL,
+    content_hash CHAR(64) NOT NULL,
+    PRIMARY KEY (snapshot_id, kind, name)
+);
@@ -0,0 +1,10 @@
+CREATE TABLE project_events(
+    event_id SERIAL PRIMARY KEY,
+    project_id INTEGER REFERENCES projects(project_id) NOT NULL,
+    actor_id INTEGER REFERENCES users(user_id) NOT NULL,
+    event_type TEXT NOT NULL,
+    target_id INTEGER,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX project_events_project_id_event_id ON project_events (project_id, event_id DESC);
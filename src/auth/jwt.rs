@@ -0,0 +1,112 @@
+use std::fmt::{self, Debug};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::Sha256;
+
+#[derive(Debug, PartialEq)]
+pub enum JwtError {
+    Malformed,
+    /// Only HS256 is accepted, anything else including `none` is refused
+    Algorithm,
+    Signature,
+    Expired,
+    Issuer,
+}
+
+#[derive(Deserialize)]
+struct JwtHeader {
+    alg: String,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Subject {
+    Id(i32),
+    Text(String),
+}
+
+#[derive(Deserialize)]
+struct Claims {
+    sub: Subject,
+    exp: i64,
+    #[serde(default)]
+    nbf: Option<i64>,
+    #[serde(default)]
+    iss: Option<String>,
+}
+
+/// Checks tokens signed with a key shared with the issuer, the subject is the user id
+#[derive(Clone)]
+pub struct JwtVerifier {
+    key: Vec<u8>,
+    issuer: Option<String>,
+}
+
+impl Debug for JwtVerifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JwtVerifier")
+            .field("issuer", &self.issuer)
+            .finish_non_exhaustive()
+    }
+}
+
+fn decode_part<T: for<'de> Deserialize<'de>>(part: &str) -> Result<T, JwtError> {
+    let bytes = URL_SAFE_NO_PAD
+        .decode(part)
+        .map_err(|_| JwtError::Malformed)?;
+    serde_json::from_slice(&bytes).map_err(|_| JwtError::Malformed)
+}
+
+impl JwtVerifier {
+    pub fn new(key: &[u8], issuer: Option<String>) -> Self {
+        Self {
+            key: key.to_vec(),
+            issuer,
+        }
+    }
+
+    fn mac(&self) -> Hmac<Sha256> {
+        // HMAC takes keys of any length
+        Hmac::<Sha256>::new_from_slice(&self.key).unwrap()
+    }
+
+    /// Whether the token looks like a JWT at all, session ids and access tokens do not
+    pub fn is_jwt(token: &str) -> bool {
+        token.split('.').count() == 3
+    }
+
+    /// Returns the user id of a valid token, `now` is in seconds since the epoch
+    pub fn verify(&self, token: &str, now: i64) -> Result<i32, JwtError> {
+        let [header, claims, signature] = token.split('.').collect::<Vec<_>>()[..] else {
+            return Err(JwtError::Malformed);
+        };
+
+        let JwtHeader { alg } = decode_part(header)?;
+        if alg != "HS256" {
+            return Err(JwtError::Algorithm);
+        }
+
+        let signature = URL_SAFE_NO_PAD
+            .decode(signature)
+            .map_err(|_| JwtError::Malformed)?;
+        let mut mac = self.mac();
+        mac.update(format!("{}.{}", header, claims).as_bytes());
+        mac.verify_slice(&signature)
+            .map_err(|_| JwtError::Signature)?;
+
+        let claims: Claims = decode_part(claims)?;
+        if claims.exp <= now || claims.nbf.is_some_and(|nbf| nbf > now) {
+            return Err(JwtError::Expired);
+        }
+        if self.issuer.is_some() && claims.iss != self.issuer {
+            return Err(JwtError::Issuer);
+        }
+
+        match claims.sub {
+            Subject::Id(user_id) => Ok(user_id),
+            Subject::Text(user_id) => user_id.parse().map_err(|_| JwtError::Malformed),
+        }
+    }
+}
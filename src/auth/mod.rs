@@ -0,0 +1,353 @@
+pub mod jwt;
+
+use std::{
+    fs,
+    net::{IpAddr, SocketAddr},
+    str::FromStr,
+    sync::Arc,
+};
+
+use anyhow::anyhow;
+use axum::{
+    extract::{ConnectInfo, State},
+    headers::{authorization::Bearer, Authorization, HeaderMapExt},
+    http::{header, HeaderMap, Method, Request},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use axum_extra::extract::CookieJar;
+use http::StatusCode;
+use sha2::{Digest, Sha256};
+use sqlx::types::chrono::Utc;
+use tracing::{info, warn};
+
+use crate::{
+    constants::{
+        AUTH_MODE, JWT_ISSUER, JWT_SECRET, JWT_SECRET_FILE, PROXY_SECRET, SESSION_COOKIE_NAME,
+        TRUSTED_PROXIES, XPROXYSECRET_HEADER_NAME, XUSERID_HEADER_NAME,
+    },
+    domain::api_tokens::{ApiToken, ApiTokenScope},
+    extractors::headers::XUserId,
+    repository::{
+        api_tokens::{ApiTokenGetError, ApiTokenRepository, API_TOKEN_PREFIX},
+        sessions::{SessionGetError, SessionRepository},
+    },
+};
+
+use self::jwt::JwtVerifier;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AuthMode {
+    /// The user id header set by a gateway in front of the service
+    TrustedHeader,
+    /// The session cookie, looked up in the database
+    SessionCookie,
+    /// A `Bearer` token signed with the configured key
+    Jwt,
+}
+
+impl FromStr for AuthMode {
+    type Err = String;
+
+    fn from_str(mode: &str) -> Result<Self, Self::Err> {
+        match mode {
+            "trusted-header" => Ok(Self::TrustedHeader),
+            "session-cookie" => Ok(Self::SessionCookie),
+            "jwt" => Ok(Self::Jwt),
+            _ => Err(format!("Unknown authentication mode {}", mode)),
+        }
+    }
+}
+
+/// Decides which user a request acts for, handlers only ever see the resulting user id header
+#[derive(Debug, Clone)]
+pub struct Authenticator {
+    pub mode: AuthMode,
+    /// Peers allowed to send the user id header, any peer when empty
+    pub trusted_proxies: Vec<IpAddr>,
+    pub proxy_secret: Option<String>,
+    pub jwt: Option<JwtVerifier>,
+}
+
+impl Authenticator {
+    pub fn from_config() -> anyhow::Result<Self> {
+        let mode = *AUTH_MODE;
+        let trusted_proxies = TRUSTED_PROXIES
+            .split(',')
+            .map(str::trim)
+            .filter(|proxy| !proxy.is_empty())
+            .map(|proxy| {
+                proxy
+                    .parse()
+                    .map_err(|_| anyhow!("Invalid trusted proxy address {}", proxy))
+            })
+            .collect::<anyhow::Result<_>>()?;
+        let proxy_secret = (!PROXY_SECRET.is_empty()).then(|| PROXY_SECRET.clone());
+
+        let jwt_secret = match (JWT_SECRET.is_empty(), JWT_SECRET_FILE.is_empty()) {
+            (false, _) => Some(JWT_SECRET.clone()),
+            (true, false) => Some(
+                fs::read_to_string(JWT_SECRET_FILE.as_str())?
+                    .trim()
+                    .to_string(),
+            ),
+            (true, true) => None,
+        };
+        let issuer = (!JWT_ISSUER.is_empty()).then(|| JWT_ISSUER.clone());
+        let jwt = jwt_secret.map(|secret| JwtVerifier::new(secret.as_bytes(), issuer));
+        if mode == AuthMode::Jwt && jwt.is_none() {
+            return Err(anyhow!(
+                "JWT authentication needs JWT_SECRET or JWT_SECRET_FILE"
+            ));
+        }
+
+        info!(?mode, "Authenticating requests");
+        Ok(Self {
+            mode,
+            trusted_proxies,
+            proxy_secret,
+            jwt,
+        })
+    }
+
+    /// Whether the user id header of the request was set by a trusted gateway
+    pub fn trusts(&self, headers: &HeaderMap, peer: Option<IpAddr>) -> bool {
+        let trusted_peer = self.trusted_proxies.is_empty()
+            || peer.is_some_and(|peer| self.trusted_proxies.contains(&peer));
+        // digests are compared so the time taken tells nothing about the secret
+        let trusted_secret = match &self.proxy_secret {
+            Some(secret) => headers.get(&XPROXYSECRET_HEADER_NAME).is_some_and(|sent| {
+                Sha256::digest(sent.as_bytes()) == Sha256::digest(secret.as_bytes())
+            }),
+            None => true,
+        };
+
+        trusted_peer && trusted_secret
+    }
+
+    /// Whether the request comes from the gateway that logs users in. Outside of the trusted
+    /// header mode the service may be exposed directly, so the gateway has to be configured.
+    pub fn trusts_gateway(&self, headers: &HeaderMap, peer: Option<IpAddr>) -> bool {
+        let configured = self.mode == AuthMode::TrustedHeader
+            || !self.trusted_proxies.is_empty()
+            || self.proxy_secret.is_some();
+
+        configured && self.trusts(headers, peer)
+    }
+
+    /// The session a request was made with, JWTs and access tokens belong to none
+    pub fn session_id(&self, headers: &HeaderMap) -> Option<String> {
+        if api_token_secret(headers).is_some() {
+            return None;
+        }
+
+        match self.mode {
+            // the gateway passes the session id along as `Bearer`
+            AuthMode::TrustedHeader => headers
+                .typed_get::<Authorization<Bearer>>()
+                .map(|Authorization(bearer)| bearer.token().to_string())
+                .filter(|token| !JwtVerifier::is_jwt(token)),
+            AuthMode::SessionCookie => CookieJar::from_headers(headers)
+                .get(&SESSION_COOKIE_NAME)
+                .map(|cookie| cookie.value().to_string()),
+            AuthMode::Jwt => None,
+        }
+    }
+}
+
+/// The session of the authenticated request, set by [`authenticate`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct CurrentSession(pub Option<String>);
+
+/// Returns the secret of an access token sent as `Bearer`, session ids are left alone
+pub fn api_token_secret(headers: &HeaderMap) -> Option<&str> {
+    let secret = headers
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")?;
+
+    secret.starts_with(API_TOKEN_PREFIX).then_some(secret)
+}
+
+/// Access tokens only reach the project routes, account management needs a session.
+/// Tokens limited to a project cannot copy, as copies may land in other projects.
+/// Read tokens are limited to requests that change nothing.
+pub fn allows(token: &ApiToken, method: &Method, path: &str) -> bool {
+    let segments: Vec<&str> = path.trim_start_matches('/').split('/').collect();
+    let in_scope = match (segments.as_slice(), token.project_id) {
+        (["projects", ..], None) => true,
+        // copies name their target in the body, where the project check cannot see it
+        (["projects", _, "resources" | "snapshots", _, "copy"], Some(_)) => false,
+        (["projects", project_id, ..], Some(allowed)) => project_id.parse() == Ok(allowed),
+        _ => false,
+    };
+    let permitted = match token.scope {
+        ApiTokenScope::Read => matches!(*method, Method::GET | Method::HEAD),
+        ApiTokenScope::Write => true,
+    };
+
+    in_scope && permitted
+}
+
+/// Routes that work without an identity: sign up and recovery, public profiles,
+/// reading and ending sessions (which carry their own session id) and the admin routes.
+pub fn is_public(method: &Method, path: &str) -> bool {
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    match (method, segments.as_slice()) {
+        (_, ["admin", ..]) => true,
+        (&Method::GET | &Method::DELETE, ["sessions", ..]) => true,
+        (&Method::POST, ["users"])
+        | (&Method::POST, ["users", "verify"])
+        | (&Method::POST, ["users", "password-reset"])
+        | (&Method::POST, ["users", "password-reset", "confirm"])
+        | (&Method::POST, ["users", "verification", "confirm"]) => true,
+        (&Method::GET, ["users", email] | ["users", email, "avatar"]) => *email != "me",
+        (&Method::GET, ["projects", _, "metadata"]) => true,
+        _ => false,
+    }
+}
+
+/// Sessions are created for whichever user the body names, so only the gateway may do it
+pub fn creates_session(method: &Method, path: &str) -> bool {
+    *method == Method::POST && path.trim_matches('/') == "sessions"
+}
+
+/// Returns the id of the user the token acts for, when it may make the request
+pub async fn resolve_api_token<T: ApiTokenRepository>(
+    repository: &T,
+    secret: &str,
+    method: &Method,
+    path: &str,
+) -> Result<i32, StatusCode> {
+    let token = match repository.authenticate(secret).await {
+        Ok(token) => token,
+        Err(ApiTokenGetError::Missing) => return Err(StatusCode::UNAUTHORIZED),
+        Err(ApiTokenGetError::Unknown) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+
+    if !allows(&token, method, path) {
+        warn!(
+            token_id = token.token_id,
+            %method, path, "Rejected request outside of the token scope"
+        );
+        return Err(StatusCode::FORBIDDEN);
+    }
+    Ok(token.user_id)
+}
+
+#[derive(Clone)]
+pub struct AuthState<S, T> {
+    pub authenticator: Arc<Authenticator>,
+    pub sessions: S,
+    pub api_tokens: T,
+}
+
+impl<S: SessionRepository, T: ApiTokenRepository> AuthState<S, T> {
+    /// Returns the user the request acts for, or `None` for an anonymous request.
+    /// Access tokens are accepted in every mode.
+    pub async fn identify(
+        &self,
+        headers: &HeaderMap,
+        peer: Option<IpAddr>,
+        method: &Method,
+        path: &str,
+    ) -> Result<Option<i32>, StatusCode> {
+        if let Some(secret) = api_token_secret(headers) {
+            return resolve_api_token(&self.api_tokens, secret, method, path)
+                .await
+                .map(Some);
+        }
+
+        match self.authenticator.mode {
+            AuthMode::TrustedHeader => match headers.typed_get::<XUserId>() {
+                Some(XUserId(user_id)) if self.authenticator.trusts(headers, peer) => {
+                    Ok(Some(user_id))
+                }
+                Some(_) => {
+                    warn!(?peer, "Rejected user id header from an untrusted source");
+                    Err(StatusCode::UNAUTHORIZED)
+                }
+                None => Ok(None),
+            },
+            AuthMode::SessionCookie => {
+                let jar = CookieJar::from_headers(headers);
+                let Some(cookie) = jar.get(&SESSION_COOKIE_NAME) else {
+                    return Ok(None);
+                };
+                match self.sessions.get(cookie.value()).await {
+                    Ok(session) => Ok(Some(session.user.id)),
+                    Err(SessionGetError::Missing) => Err(StatusCode::UNAUTHORIZED),
+                    Err(SessionGetError::Unknown) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+                }
+            }
+            AuthMode::Jwt => {
+                let (Some(verifier), Some(Authorization(bearer))) = (
+                    &self.authenticator.jwt,
+                    headers.typed_get::<Authorization<Bearer>>(),
+                ) else {
+                    return Ok(None);
+                };
+                // session ids are sent as bearer too, on the session routes
+                if !JwtVerifier::is_jwt(bearer.token()) {
+                    return Ok(None);
+                }
+                verifier
+                    .verify(bearer.token(), Utc::now().timestamp())
+                    .map(Some)
+                    .map_err(|err| {
+                        warn!(?err, "Rejected token");
+                        StatusCode::UNAUTHORIZED
+                    })
+            }
+        }
+    }
+}
+
+/// Replaces the user id header with the identity the configured mode establishes
+pub async fn authenticate<S, T, B>(
+    State(state): State<AuthState<S, T>>,
+    mut request: Request<B>,
+    next: Next<B>,
+) -> Response
+where
+    S: SessionRepository + Send + Sync,
+    T: ApiTokenRepository + Send + Sync,
+{
+    let peer = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(address)| address.ip());
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+
+    let creates_session = creates_session(&method, &path);
+    if creates_session && !state.authenticator.trusts_gateway(request.headers(), peer) {
+        warn!(?peer, "Rejected session creation from an untrusted source");
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+
+    match state
+        .identify(request.headers(), peer, &method, &path)
+        .await
+    {
+        Ok(None) if !creates_session && !is_public(&method, &path) => {
+            warn!(%method, path, "Rejected anonymous request to a protected route");
+            StatusCode::UNAUTHORIZED.into_response()
+        }
+        Ok(user_id) => {
+            let session_id =
+                user_id.and_then(|_| state.authenticator.session_id(request.headers()));
+            request.headers_mut().remove(&XUSERID_HEADER_NAME);
+            if let Some(user_id) = user_id {
+                request.headers_mut().typed_insert(XUserId(user_id));
+            }
+            request.extensions_mut().insert(CurrentSession(session_id));
+            next.run(request).await
+        }
+        Err(status) => status.into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,468 @@
+use std::net::Ipv4Addr;
+
+use axum::{body::Body, middleware, routing, Router};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use hmac::{Hmac, Mac};
+use http::HeaderValue;
+use mockall::predicate;
+use sqlx::{types::chrono::NaiveDateTime, PgPool};
+use tower::ServiceExt;
+
+use crate::{
+    domain::{sessions::Session, users::User},
+    repository::{
+        api_tokens::{MockApiTokenRepository, PgApiTokenRepository},
+        sessions::{MockSessionRepository, PgSessionRepository},
+    },
+};
+
+use super::{jwt::JwtError, *};
+
+const JWT_KEY: &[u8] = b"secret";
+const PROXY: IpAddr = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+
+fn mock_authenticator(mode: AuthMode) -> Authenticator {
+    Authenticator {
+        mode,
+        trusted_proxies: Vec::new(),
+        proxy_secret: None,
+        jwt: Some(JwtVerifier::new(JWT_KEY, None)),
+    }
+}
+
+fn mock_state(
+    authenticator: Authenticator,
+    sessions: MockSessionRepository,
+) -> AuthState<MockSessionRepository, MockApiTokenRepository> {
+    AuthState {
+        authenticator: Arc::new(authenticator),
+        sessions,
+        api_tokens: MockApiTokenRepository::new(),
+    }
+}
+
+fn sign(header: &str, claims: serde_json::Value) -> String {
+    let header = URL_SAFE_NO_PAD.encode(header);
+    let claims = URL_SAFE_NO_PAD.encode(claims.to_string());
+
+    let mut mac = Hmac::<Sha256>::new_from_slice(JWT_KEY).unwrap();
+    mac.update(format!("{}.{}", header, claims).as_bytes());
+    let signature = URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes());
+    format!("{}.{}.{}", header, claims, signature)
+}
+
+fn mock_jwt(claims: serde_json::Value) -> String {
+    sign(r#"{"alg":"HS256","typ":"JWT"}"#, claims)
+}
+
+fn user_id_header(user_id: &str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        &XUSERID_HEADER_NAME,
+        HeaderValue::from_str(user_id).unwrap(),
+    );
+    headers
+}
+
+fn mock_token(scope: ApiTokenScope, project_id: Option<i32>) -> ApiToken {
+    ApiToken {
+        token_id: 1,
+        user_id: 7,
+        name: String::from("ci"),
+        scope,
+        project_id,
+        created_at: NaiveDateTime::default(),
+        expires_at: NaiveDateTime::default(),
+        last_used_at: None,
+    }
+}
+
+fn bearer(token: &str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        header::AUTHORIZATION,
+        HeaderValue::from_str(&format!("Bearer {}", token)).unwrap(),
+    );
+    headers
+}
+
+#[test]
+fn api_token_secret_ignores_session_ids() {
+    assert_eq!(Some("pat_abc"), api_token_secret(&bearer("pat_abc")));
+    assert_eq!(None, api_token_secret(&bearer(&"1".repeat(64))));
+    assert_eq!(None, api_token_secret(&HeaderMap::new()));
+}
+
+#[test]
+fn session_id_follows_mode() {
+    let mut cookie = HeaderMap::new();
+    cookie.insert(
+        header::COOKIE,
+        HeaderValue::from_str(&format!("{}=abc", *SESSION_COOKIE_NAME)).unwrap(),
+    );
+    let jwt = bearer(&mock_jwt(serde_json::json!({ "sub": "4" })));
+
+    let trusted = mock_authenticator(AuthMode::TrustedHeader);
+    assert_eq!(
+        Some(String::from("abc")),
+        trusted.session_id(&bearer("abc"))
+    );
+    assert_eq!(None, trusted.session_id(&bearer("pat_abc")));
+    assert_eq!(None, trusted.session_id(&jwt));
+
+    let session_cookie = mock_authenticator(AuthMode::SessionCookie);
+    assert_eq!(
+        Some(String::from("abc")),
+        session_cookie.session_id(&cookie)
+    );
+    assert_eq!(None, session_cookie.session_id(&bearer("abc")));
+
+    assert_eq!(None, mock_authenticator(AuthMode::Jwt).session_id(&jwt));
+}
+
+#[test]
+fn allows_enforces_scope() {
+    let read = mock_token(ApiTokenScope::Read, None);
+    assert!(allows(&read, &Method::GET, "/projects/3/documents"));
+    assert!(!allows(&read, &Method::PUT, "/projects/3/documents"));
+
+    let write = mock_token(ApiTokenScope::Write, None);
+    assert!(allows(&write, &Method::PUT, "/projects/3/resources/1"));
+    assert!(allows(&write, &Method::POST, "/projects"));
+    // account management needs a session
+    assert!(!allows(&write, &Method::GET, "/users/me/tokens"));
+    assert!(!allows(&write, &Method::PUT, "/users/me/password"));
+}
+
+#[test]
+fn allows_enforces_project() {
+    let token = mock_token(ApiTokenScope::Write, Some(3));
+
+    assert!(allows(&token, &Method::PUT, "/projects/3/resources/1"));
+    assert!(!allows(&token, &Method::PUT, "/projects/4/resources/1"));
+    assert!(!allows(&token, &Method::GET, "/projects"));
+    assert!(!allows(&token, &Method::POST, "/projects/sharing/token"));
+    // the target project of a copy is in the body
+    assert!(!allows(
+        &token,
+        &Method::POST,
+        "/projects/3/resources/1/copy"
+    ));
+    assert!(!allows(
+        &token,
+        &Method::POST,
+        "/projects/3/snapshots/2/copy"
+    ));
+
+    let unrestricted = mock_token(ApiTokenScope::Write, None);
+    assert!(allows(
+        &unrestricted,
+        &Method::POST,
+        "/projects/3/resources/1/copy"
+    ));
+}
+
+#[test]
+fn is_public_lets_anonymous_routes_through() {
+    assert!(is_public(&Method::POST, "/users"));
+    assert!(is_public(&Method::POST, "/users/password-reset/confirm"));
+    assert!(is_public(&Method::GET, "/users/john@example.com/avatar"));
+    assert!(is_public(&Method::GET, "/sessions"));
+    assert!(is_public(&Method::DELETE, "/sessions/all"));
+    assert!(is_public(&Method::POST, "/admin/gc"));
+
+    assert!(!is_public(&Method::POST, "/sessions"));
+    assert!(!is_public(&Method::GET, "/users/me"));
+    assert!(!is_public(&Method::GET, "/users/me/avatar"));
+    assert!(!is_public(&Method::GET, "/projects"));
+    assert!(!is_public(&Method::PUT, "/projects/3/metadata"));
+}
+
+#[test]
+fn trusts_gateway_needs_configuration_outside_trusted_header_mode() {
+    let mut secret = HeaderMap::new();
+    secret.insert(
+        &XPROXYSECRET_HEADER_NAME,
+        HeaderValue::from_static("s3cret"),
+    );
+
+    assert!(mock_authenticator(AuthMode::TrustedHeader).trusts_gateway(&HeaderMap::new(), None));
+    assert!(!mock_authenticator(AuthMode::SessionCookie).trusts_gateway(&HeaderMap::new(), None));
+    assert!(!mock_authenticator(AuthMode::Jwt).trusts_gateway(&secret, None));
+
+    let mut configured = mock_authenticator(AuthMode::SessionCookie);
+    configured.proxy_secret = Some(String::from("s3cret"));
+    assert!(configured.trusts_gateway(&secret, None));
+    assert!(!configured.trusts_gateway(&HeaderMap::new(), None));
+}
+
+/// Runs the middleware in front of handlers that always succeed
+fn mock_app(authenticator: Authenticator) -> Router {
+    // the repositories are never reached by anonymous requests
+    let pool = PgPool::connect_lazy("postgres://localhost/agartex").unwrap();
+    let state = AuthState {
+        authenticator: Arc::new(authenticator),
+        sessions: PgSessionRepository::new(&pool),
+        api_tokens: PgApiTokenRepository::new(&pool),
+    };
+    Router::new()
+        .route("/projects", routing::get(|| async { StatusCode::OK }))
+        .route("/users", routing::post(|| async { StatusCode::OK }))
+        .route("/sessions", routing::post(|| async { StatusCode::CREATED }))
+        .layer(middleware::from_fn_with_state(
+            state,
+            authenticate::<PgSessionRepository, PgApiTokenRepository, _>,
+        ))
+}
+
+#[tokio::test]
+async fn authenticate_anonymous_protected_route_unauthorized() {
+    let app = mock_app(mock_authenticator(AuthMode::SessionCookie));
+
+    let request = Request::get("/projects").body(Body::empty()).unwrap();
+    let res = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(StatusCode::UNAUTHORIZED, res.status());
+
+    let request = Request::post("/users").body(Body::empty()).unwrap();
+    let res = app.oneshot(request).await.unwrap();
+    assert_eq!(StatusCode::OK, res.status());
+}
+
+#[tokio::test]
+async fn authenticate_anonymous_session_creation_unauthorized() {
+    for mode in [AuthMode::SessionCookie, AuthMode::Jwt] {
+        let request = Request::post("/sessions").body(Body::empty()).unwrap();
+        let res = mock_app(mock_authenticator(mode))
+            .oneshot(request)
+            .await
+            .unwrap();
+        assert_eq!(StatusCode::UNAUTHORIZED, res.status());
+    }
+
+    let mut gateway = mock_authenticator(AuthMode::SessionCookie);
+    gateway.proxy_secret = Some(String::from("s3cret"));
+    let request = Request::post("/sessions")
+        .header(&XPROXYSECRET_HEADER_NAME, "s3cret")
+        .body(Body::empty())
+        .unwrap();
+    let res = mock_app(gateway).oneshot(request).await.unwrap();
+    assert_eq!(StatusCode::CREATED, res.status());
+}
+
+#[tokio::test]
+async fn resolve_api_token_returns_user() {
+    let mut repository = MockApiTokenRepository::new();
+
+    repository
+        .expect_authenticate()
+        .with(predicate::eq("pat_abc"))
+        .times(1)
+        .returning(|_| Ok(mock_token(ApiTokenScope::Read, None)));
+
+    let res = resolve_api_token(&repository, "pat_abc", &Method::GET, "/projects/3").await;
+
+    assert_eq!(Ok(7), res);
+}
+
+#[tokio::test]
+async fn resolve_api_token_out_of_scope_error() {
+    let mut repository = MockApiTokenRepository::new();
+
+    repository
+        .expect_authenticate()
+        .times(1)
+        .returning(|_| Ok(mock_token(ApiTokenScope::Read, None)));
+
+    let res = resolve_api_token(&repository, "pat_abc", &Method::PUT, "/projects/3").await;
+
+    assert_eq!(Err(StatusCode::FORBIDDEN), res);
+}
+
+#[tokio::test]
+async fn resolve_api_token_unknown_token_error() {
+    let mut repository = MockApiTokenRepository::new();
+
+    repository
+        .expect_authenticate()
+        .times(1)
+        .returning(|_| Err(ApiTokenGetError::Missing));
+
+    let res = resolve_api_token(&repository, "pat_abc", &Method::GET, "/projects/3").await;
+
+    assert_eq!(Err(StatusCode::UNAUTHORIZED), res);
+}
+
+#[test]
+fn auth_mode_parses() {
+    assert_eq!(Ok(AuthMode::TrustedHeader), "trusted-header".parse());
+    assert_eq!(Ok(AuthMode::SessionCookie), "session-cookie".parse());
+    assert_eq!(Ok(AuthMode::Jwt), "jwt".parse());
+    assert!("basic".parse::<AuthMode>().is_err());
+}
+
+#[test]
+fn trusts_checks_proxies_and_secret() {
+    let mut authenticator = mock_authenticator(AuthMode::TrustedHeader);
+    assert!(authenticator.trusts(&HeaderMap::new(), None));
+
+    authenticator.trusted_proxies = vec![PROXY];
+    assert!(authenticator.trusts(&HeaderMap::new(), Some(PROXY)));
+    assert!(!authenticator.trusts(&HeaderMap::new(), Some("10.0.0.2".parse().unwrap())));
+    assert!(!authenticator.trusts(&HeaderMap::new(), None));
+
+    authenticator.proxy_secret = Some(String::from("shared"));
+    let mut headers = HeaderMap::new();
+    assert!(!authenticator.trusts(&headers, Some(PROXY)));
+    headers.insert(&XPROXYSECRET_HEADER_NAME, HeaderValue::from_static("wrong"));
+    assert!(!authenticator.trusts(&headers, Some(PROXY)));
+    headers.insert(
+        &XPROXYSECRET_HEADER_NAME,
+        HeaderValue::from_static("shared"),
+    );
+    assert!(authenticator.trusts(&headers, Some(PROXY)));
+}
+
+#[tokio::test]
+async fn identify_trusted_header() {
+    let mut authenticator = mock_authenticator(AuthMode::TrustedHeader);
+    authenticator.trusted_proxies = vec![PROXY];
+    let state = mock_state(authenticator, MockSessionRepository::new());
+
+    let res = state
+        .identify(&user_id_header("5"), Some(PROXY), &Method::GET, "/projects")
+        .await;
+    assert_eq!(Ok(Some(5)), res);
+
+    let res = state
+        .identify(&user_id_header("5"), None, &Method::GET, "/projects")
+        .await;
+    assert_eq!(Err(StatusCode::UNAUTHORIZED), res);
+
+    let res = state
+        .identify(&HeaderMap::new(), None, &Method::POST, "/users")
+        .await;
+    assert_eq!(Ok(None), res);
+}
+
+#[tokio::test]
+async fn identify_session_cookie_ignores_header() {
+    let mut sessions = MockSessionRepository::new();
+
+    sessions
+        .expect_get()
+        .with(predicate::eq("abc"))
+        .times(1)
+        .returning(|_| {
+            Ok(Session {
+                id: String::from("abc"),
+                user: User {
+                    id: 3,
+                    email: String::from("john@email.com"),
+                    display_name: None,
+                    password_hash: String::new(),
+                    verified_at: None,
+                },
+                expires: 0,
+            })
+        });
+
+    let state = mock_state(mock_authenticator(AuthMode::SessionCookie), sessions);
+    let mut headers = user_id_header("5");
+    headers.insert(
+        header::COOKIE,
+        HeaderValue::from_str(&format!("{}=abc", *SESSION_COOKIE_NAME)).unwrap(),
+    );
+
+    assert_eq!(
+        Ok(Some(3)),
+        state
+            .identify(&headers, None, &Method::GET, "/projects")
+            .await
+    );
+    assert_eq!(
+        Ok(None),
+        state
+            .identify(&user_id_header("5"), None, &Method::GET, "/projects")
+            .await
+    );
+}
+
+#[tokio::test]
+async fn identify_expired_session_unauthorized() {
+    let mut sessions = MockSessionRepository::new();
+
+    // expired sessions are reported as missing by the repository
+    sessions
+        .expect_get()
+        .with(predicate::eq("abc"))
+        .times(1)
+        .returning(|_| Err(SessionGetError::Missing));
+
+    let state = mock_state(mock_authenticator(AuthMode::SessionCookie), sessions);
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        header::COOKIE,
+        HeaderValue::from_str(&format!("{}=abc", *SESSION_COOKIE_NAME)).unwrap(),
+    );
+
+    assert_eq!(
+        Err(StatusCode::UNAUTHORIZED),
+        state
+            .identify(&headers, None, &Method::GET, "/projects")
+            .await
+    );
+}
+
+#[tokio::test]
+async fn identify_jwt() {
+    let state = mock_state(
+        mock_authenticator(AuthMode::Jwt),
+        MockSessionRepository::new(),
+    );
+    let exp = Utc::now().timestamp() + 60;
+
+    let res = state
+        .identify(
+            &bearer(&mock_jwt(serde_json::json!({ "sub": "4", "exp": exp }))),
+            None,
+            &Method::GET,
+            "/projects",
+        )
+        .await;
+    assert_eq!(Ok(Some(4)), res);
+
+    // session ids are left to the session routes
+    let res = state
+        .identify(&bearer(&"1".repeat(64)), None, &Method::GET, "/sessions")
+        .await;
+    assert_eq!(Ok(None), res);
+}
+
+#[test]
+fn jwt_verify_rejects_invalid_tokens() {
+    let verifier = JwtVerifier::new(JWT_KEY, Some(String::from("gateway")));
+    let claims = serde_json::json!({ "sub": 4, "exp": 100, "iss": "gateway" });
+
+    assert_eq!(Ok(4), verifier.verify(&mock_jwt(claims.clone()), 50));
+    assert_eq!(
+        Err(JwtError::Expired),
+        verifier.verify(&mock_jwt(claims.clone()), 100)
+    );
+    assert_eq!(
+        Err(JwtError::Issuer),
+        verifier.verify(
+            &mock_jwt(serde_json::json!({ "sub": 4, "exp": 100, "iss": "other" })),
+            50
+        )
+    );
+    assert_eq!(
+        Err(JwtError::Algorithm),
+        verifier.verify(&sign(r#"{"alg":"none"}"#, claims.clone()), 50)
+    );
+
+    let other_key = JwtVerifier::new(b"other", Some(String::from("gateway")));
+    assert_eq!(
+        Err(JwtError::Signature),
+        other_key.verify(&mock_jwt(claims), 50)
+    );
+}
@@ -10,6 +10,12 @@ use http::HeaderName;
 use lazy_static::lazy_static;
 use regex::Regex;
 
+use crate::{
+    auth::AuthMode,
+    notify::NotifierKind,
+    storage::{codec::Compression, StorageBackend},
+};
+
 fn load_env_or_default<T>(var: &str, default: T) -> T
 where
     T: FromStr,
@@ -29,17 +35,90 @@ where
 // - PGPASSWORD
 pub const FALLBACK_DB_URL: &str = "postgres://localhost:5432/agartex-db";
 pub static XUSERID_HEADER_NAME: HeaderName = HeaderName::from_static("x-user-id");
+pub static XADMINTOKEN_HEADER_NAME: HeaderName = HeaderName::from_static("x-admin-token");
+pub static XPROXYSECRET_HEADER_NAME: HeaderName = HeaderName::from_static("x-proxy-secret");
 
 lazy_static! {
     pub static ref SERVER_URL: SocketAddr = load_env_or_default(
         "SERVER_URL",
         SocketAddr::new(IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)), 3200)
     );
+    // how requests are tied to a user, see the README
+    pub static ref AUTH_MODE: AuthMode = load_env_or_default("AUTH_MODE", AuthMode::TrustedHeader);
+    // comma-separated addresses allowed to send the user id header, any address when empty
+    pub static ref TRUSTED_PROXIES: String = load_env_or_default("TRUSTED_PROXIES", String::new());
+    // the gateway must send this in the proxy secret header when set
+    pub static ref PROXY_SECRET: String = load_env_or_default("PROXY_SECRET", String::new());
+    // key for HS256 signed tokens in jwt mode, either directly or in a file
+    pub static ref JWT_SECRET: String = load_env_or_default("JWT_SECRET", String::new());
+    pub static ref JWT_SECRET_FILE: String = load_env_or_default("JWT_SECRET_FILE", String::new());
+    // the iss claim is checked when set
+    pub static ref JWT_ISSUER: String = load_env_or_default("JWT_ISSUER", String::new());
     pub static ref SESSION_COOKIE_NAME: String =
         load_env_or_default("SESSION_COOKIE_NAME", String::from("RSESSID"));
+    // sessions used within the renewal window of their expiry are extended to a full lifetime
+    pub static ref SESSION_LIFETIME_IN_SECONDS: i64 =
+        load_env_or_default("SESSION_LIFETIME", 7 * 24 * 60 * 60);
+    pub static ref SESSION_RENEWAL_WINDOW_IN_SECONDS: i64 =
+        load_env_or_default("SESSION_RENEWAL_WINDOW", 24 * 60 * 60);
+    pub static ref SESSION_PURGE_INTERVAL_IN_SECONDS: u64 =
+        load_env_or_default("SESSION_PURGE_INTERVAL", 60 * 60);
+    // stored hashes with another cost are rehashed on the next successful verification
+    pub static ref BCRYPT_COST: u32 = load_env_or_default("BCRYPT_COST", bcrypt::DEFAULT_COST);
+    pub static ref NOTIFIER: NotifierKind = load_env_or_default("NOTIFIER", NotifierKind::Log);
+    pub static ref NOTIFIER_FILE_PATH: PathBuf =
+        load_env_or_default("NOTIFIER_FILE_PATH", PathBuf::from(r"notifications.log"));
+    pub static ref PASSWORD_RESET_TOKEN_LIFETIME_IN_SECONDS: i64 =
+        load_env_or_default("PASSWORD_RESET_TOKEN_LIFETIME", 60 * 60);
+    // the reset token is appended to this link in the notification
+    pub static ref PASSWORD_RESET_URL: String = load_env_or_default(
+        "PASSWORD_RESET_URL",
+        String::from("http://localhost:3000/reset-password?token=")
+    );
+    pub static ref EMAIL_VERIFICATION_TOKEN_LIFETIME_IN_SECONDS: i64 =
+        load_env_or_default("EMAIL_VERIFICATION_TOKEN_LIFETIME", 2 * 24 * 60 * 60);
+    // the verification token is appended to this link in the notification
+    pub static ref EMAIL_VERIFICATION_URL: String = load_env_or_default(
+        "EMAIL_VERIFICATION_URL",
+        String::from("http://localhost:3000/verify-email?token=")
+    );
+    pub static ref STORAGE_BACKEND: StorageBackend =
+        load_env_or_default("STORAGE_BACKEND", StorageBackend::Local);
     pub static ref FILE_DIR_PATH: PathBuf =
         load_env_or_default("FILE_DIR_PATH", PathBuf::from(r"blobs"));
+    pub static ref S3_BUCKET: String = load_env_or_default("S3_BUCKET", String::from("agartex"));
+    pub static ref S3_PREFIX: String = load_env_or_default("S3_PREFIX", String::from("blobs"));
+    pub static ref S3_ENDPOINT: String = load_env_or_default("S3_ENDPOINT", String::new());
+    pub static ref S3_REGION: String = load_env_or_default("S3_REGION", String::from("us-east-1"));
+    pub static ref S3_ACCESS_KEY_ID: String = load_env_or_default("S3_ACCESS_KEY_ID", String::new());
+    pub static ref S3_SECRET_ACCESS_KEY: String =
+        load_env_or_default("S3_SECRET_ACCESS_KEY", String::new());
+    // presigned downloads are disabled when zero
+    pub static ref S3_PRESIGN_EXPIRY_IN_SECONDS: u64 =
+        load_env_or_default("S3_PRESIGN_EXPIRY", 0);
+    pub static ref DOCUMENT_COMPRESSION: Compression =
+        load_env_or_default("DOCUMENT_COMPRESSION", Compression::None);
+    // stored files are encrypted when either is set, the key is 32 bytes in hex
+    pub static ref MASTER_KEY: String = load_env_or_default("MASTER_KEY", String::new());
+    pub static ref MASTER_KEY_FILE: String = load_env_or_default("MASTER_KEY_FILE", String::new());
     pub static ref RESOURCE_SIZE_LIMIT_IN_BYTES: usize =
         load_env_or_default("RESOURCE_SIZE_LIMIT", 10 * 1024 * 1024);
+    // quotas are disabled when zero
+    pub static ref PROJECT_QUOTA_IN_BYTES: u64 =
+        load_env_or_default("PROJECT_QUOTA", 100 * 1024 * 1024);
+    pub static ref USER_QUOTA_IN_BYTES: u64 = load_env_or_default("USER_QUOTA", 1024 * 1024 * 1024);
+    pub static ref GC_INTERVAL_IN_SECONDS: u64 = load_env_or_default("GC_INTERVAL", 60 * 60);
+    // unreferenced blobs younger than this may still belong to an uncommitted write
+    pub static ref GC_GRACE_PERIOD_IN_SECONDS: u64 =
+        load_env_or_default("GC_GRACE_PERIOD", 24 * 60 * 60);
+    // admin endpoints are disabled when empty
+    pub static ref ADMIN_TOKEN: String = load_env_or_default("ADMIN_TOKEN", String::new());
+    pub static ref PRESENCE_IDLE_TIMEOUT_IN_SECONDS: i64 =
+        load_env_or_default("PRESENCE_IDLE_TIMEOUT", 5 * 60);
+    pub static ref PRESENCE_SWEEP_INTERVAL_IN_SECONDS: u64 =
+        load_env_or_default("PRESENCE_SWEEP_INTERVAL", 30);
+    pub static ref AVATAR_SIZE_LIMIT_IN_BYTES: usize =
+        load_env_or_default("AVATAR_SIZE_LIMIT", 1024 * 1024);
     pub static ref NAME_REGEX: Regex = Regex::from_str(r"^[\w\s.-]+$").unwrap();
+    pub static ref ORCID_REGEX: Regex = Regex::from_str(r"^\d{4}-\d{4}-\d{4}-\d{3}[\dX]$").unwrap();
 }
@@ -0,0 +1,37 @@
+use axum::{
+    response::{IntoResponse, Response},
+    Extension, Json, TypedHeader,
+};
+use http::StatusCode;
+use tracing::{info, warn};
+
+use crate::{
+    control::usage::quota_exceeded_response,
+    domain::accounts::{AccountDeletion, AccountDeletionReport},
+    extractors::headers::XUserId,
+    repository::accounts::{AccountDeleteError, AccountRepository},
+};
+
+/// Deletes the account of the user, or reports what deletion would affect on a dry run
+#[tracing::instrument(skip(account_repository))]
+pub async fn post_users_me_deletion<A: AccountRepository>(
+    Extension(account_repository): Extension<A>,
+    TypedHeader(XUserId(user_id)): TypedHeader<XUserId>,
+    Json(deletion): Json<AccountDeletion>,
+) -> Result<Json<AccountDeletionReport>, Response> {
+    info!("Received account deletion attempt");
+
+    match account_repository.delete(user_id, &deletion).await {
+        Ok(report) => Ok(Json(report)),
+        Err(AccountDeleteError::Missing) => Err(StatusCode::NOT_FOUND.into_response()),
+        Err(AccountDeleteError::InvalidTransfer(project_id)) => {
+            warn!(project_id, "Project cannot be transferred");
+            Err(StatusCode::CONFLICT.into_response())
+        }
+        Err(AccountDeleteError::QuotaExceeded(exceeded)) => Err(quota_exceeded_response(exceeded)),
+        Err(AccountDeleteError::Unknown) => Err(StatusCode::INTERNAL_SERVER_ERROR.into_response()),
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,100 @@
+use axum::Extension;
+use http::StatusCode;
+use mockall::predicate;
+
+use crate::{
+    domain::{
+        accounts::ProjectTransfer,
+        usage::{QuotaExceeded, QuotaScope},
+    },
+    repository::accounts::MockAccountRepository,
+};
+
+use super::*;
+
+fn mock_deletion(dry_run: bool) -> AccountDeletion {
+    AccountDeletion {
+        dry_run,
+        transfers: vec![ProjectTransfer {
+            project_id: 1,
+            new_owner_email: String::from("jane@email.com"),
+        }],
+        remove_activity: false,
+    }
+}
+
+#[tokio::test]
+async fn post_users_me_deletion_dry_run() {
+    let mut account_repository = MockAccountRepository::new();
+
+    let report = AccountDeletionReport {
+        dry_run: true,
+        transferred_projects: vec![1],
+        deleted_projects: vec![2, 3],
+        left_projects: vec![4],
+        ended_sessions: 2,
+        removed_events: 0,
+        anonymized_entries: 7,
+    };
+    let report_cpy = report.clone();
+
+    account_repository
+        .expect_delete()
+        .with(predicate::eq(5), predicate::eq(mock_deletion(true)))
+        .times(1)
+        .return_once(|_, _| Ok(report_cpy));
+
+    let res = post_users_me_deletion(
+        Extension(account_repository),
+        TypedHeader(XUserId(5)),
+        Json(mock_deletion(true)),
+    )
+    .await;
+
+    assert_eq!(report, res.unwrap().0);
+}
+
+#[tokio::test]
+async fn post_users_me_deletion_invalid_transfer_error() {
+    let mut account_repository = MockAccountRepository::new();
+
+    account_repository
+        .expect_delete()
+        .times(1)
+        .returning(|_, _| Err(AccountDeleteError::InvalidTransfer(1)));
+
+    let res = post_users_me_deletion(
+        Extension(account_repository),
+        TypedHeader(XUserId(5)),
+        Json(mock_deletion(false)),
+    )
+    .await;
+
+    assert_eq!(StatusCode::CONFLICT, res.unwrap_err().status());
+}
+
+#[tokio::test]
+async fn post_users_me_deletion_quota_exceeded_error() {
+    let mut account_repository = MockAccountRepository::new();
+
+    account_repository
+        .expect_delete()
+        .times(1)
+        .returning(|_, _| {
+            Err(AccountDeleteError::QuotaExceeded(QuotaExceeded {
+                scope: QuotaScope::Owner,
+                quota_bytes: 100,
+                used_bytes: 150,
+                requested_bytes: 0,
+            }))
+        });
+
+    let res = post_users_me_deletion(
+        Extension(account_repository),
+        TypedHeader(XUserId(5)),
+        Json(mock_deletion(false)),
+    )
+    .await;
+
+    assert_eq!(StatusCode::PAYLOAD_TOO_LARGE, res.unwrap_err().status());
+}
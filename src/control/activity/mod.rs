@@ -0,0 +1,74 @@
+use axum::{
+    extract::{Path, Query},
+    Extension, Json, TypedHeader,
+};
+use http::StatusCode;
+use tracing::{info, warn};
+
+use crate::{
+    domain::events::{ActivityFilter, ActivityPage, ProjectEventType},
+    extractors::headers::XUserId,
+    repository::{
+        events::{EventGetError, EventRepository},
+        projects::{ProjectGetError, ProjectRepository},
+    },
+};
+
+const DEFAULT_PAGE_SIZE: i64 = 50;
+const MAX_PAGE_SIZE: i64 = 200;
+
+/// Appends an entry to the project activity log
+pub async fn record_event<E: EventRepository>(
+    event_repository: &E,
+    project_id: i32,
+    actor_id: i32,
+    event_type: ProjectEventType,
+    target_id: Option<i32>,
+) {
+    // the operation already happened, so a failure here must not fail the request
+    if event_repository
+        .insert(project_id, actor_id, event_type, target_id)
+        .await
+        .is_err()
+    {
+        warn!(?event_type, "Could not record project event");
+    }
+}
+
+#[tracing::instrument(skip(project_repository, event_repository))]
+pub async fn get_projects_activity<P: ProjectRepository, E: EventRepository>(
+    Extension(project_repository): Extension<P>,
+    Extension(event_repository): Extension<E>,
+    TypedHeader(XUserId(user_id)): TypedHeader<XUserId>,
+    Path(project_id): Path<i32>,
+    Query(filter): Query<ActivityFilter>,
+) -> Result<Json<ActivityPage>, StatusCode> {
+    info!("Received attempt to get project activity");
+
+    match project_repository.has_access(project_id, user_id).await {
+        Ok(true) => (),
+        Ok(false) => return Err(StatusCode::FORBIDDEN),
+        Err(ProjectGetError::Missing) => return Err(StatusCode::NOT_FOUND),
+        Err(ProjectGetError::Unknown) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+
+    let limit = filter
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+
+    let events = match event_repository.get(project_id, &filter, limit).await {
+        Ok(events) => events,
+        Err(EventGetError::Unknown) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+
+    let next = match events.last() {
+        Some(event) if events.len() as i64 == limit => Some(event.event_id),
+        _ => None,
+    };
+
+    Ok(Json(ActivityPage { events, next }))
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,118 @@
+use axum::Extension;
+use http::StatusCode;
+use mockall::predicate;
+use sqlx::types::chrono::Utc;
+
+use crate::{
+    domain::events::ProjectEvent,
+    repository::{events::MockEventRepository, projects::MockProjectRepository},
+};
+
+use super::*;
+
+fn mock_filter(limit: Option<i64>) -> ActivityFilter {
+    ActivityFilter {
+        actor_id: Some(2),
+        event_type: Some(ProjectEventType::DocumentUpdate),
+        before: None,
+        limit,
+    }
+}
+
+fn mock_event(event_id: i32) -> ProjectEvent {
+    ProjectEvent {
+        event_id,
+        project_id: 1,
+        actor_id: 2,
+        actor_email: String::from("email"),
+        actor_display_name: None,
+        event_type: ProjectEventType::DocumentUpdate,
+        target_id: Some(1),
+        created_at: Utc::now().naive_utc(),
+    }
+}
+
+fn mock_access(access: bool) -> MockProjectRepository {
+    let mut project_repository = MockProjectRepository::new();
+
+    project_repository
+        .expect_has_access()
+        .with(predicate::eq(1), predicate::eq(2))
+        .times(1)
+        .returning(move |_, _| Ok(access));
+
+    project_repository
+}
+
+#[tokio::test]
+async fn get_projects_activity_full_page() {
+    let mut event_repository = MockEventRepository::new();
+
+    event_repository
+        .expect_get()
+        .with(
+            predicate::eq(1),
+            predicate::eq(mock_filter(Some(2))),
+            predicate::eq(2),
+        )
+        .times(1)
+        .returning(|_, _, _| Ok(vec![mock_event(5), mock_event(4)]));
+
+    let Json(page) = get_projects_activity(
+        Extension(mock_access(true)),
+        Extension(event_repository),
+        TypedHeader(XUserId(2)),
+        Path(1),
+        Query(mock_filter(Some(2))),
+    )
+    .await
+    .unwrap_or_else(|status| panic!("unexpected status {}", status));
+
+    assert_eq!(2, page.events.len());
+    assert_eq!(Some(4), page.next);
+}
+
+#[tokio::test]
+async fn get_projects_activity_last_page() {
+    let mut event_repository = MockEventRepository::new();
+
+    event_repository
+        .expect_get()
+        .with(
+            predicate::eq(1),
+            predicate::always(),
+            predicate::eq(MAX_PAGE_SIZE),
+        )
+        .times(1)
+        .returning(|_, _, _| Ok(vec![mock_event(1)]));
+
+    let Json(page) = get_projects_activity(
+        Extension(mock_access(true)),
+        Extension(event_repository),
+        TypedHeader(XUserId(2)),
+        Path(1),
+        Query(mock_filter(Some(10_000))),
+    )
+    .await
+    .unwrap_or_else(|status| panic!("unexpected status {}", status));
+
+    assert_eq!(None, page.next);
+}
+
+#[tokio::test]
+async fn get_projects_activity_forbidden_error() {
+    let mut event_repository = MockEventRepository::new();
+
+    event_repository.expect_get().times(0);
+
+    let result = get_projects_activity(
+        Extension(mock_access(false)),
+        Extension(event_repository),
+        TypedHeader(XUserId(2)),
+        Path(1),
+        Query(mock_filter(None)),
+    )
+    .await;
+
+    assert_eq!(Some(StatusCode::FORBIDDEN), result.err());
+}
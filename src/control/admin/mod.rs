@@ -0,0 +1,57 @@
+use axum::{Extension, Json, TypedHeader};
+use http::StatusCode;
+use sha2::{Digest, Sha256};
+use tracing::{error, info, warn};
+
+use crate::{
+    constants::ADMIN_TOKEN, domain::gc::GcReport, extractors::headers::XAdminToken,
+    gc::GarbageCollector,
+};
+
+fn check_admin_token(token: &str) -> Result<(), StatusCode> {
+    // digests are compared so the time taken tells nothing about the token
+    if ADMIN_TOKEN.is_empty()
+        || Sha256::digest(token.as_bytes()) != Sha256::digest(ADMIN_TOKEN.as_bytes())
+    {
+        warn!("Rejected admin request");
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    Ok(())
+}
+
+#[tracing::instrument(skip(collector, token))]
+pub async fn get_admin_gc(
+    Extension(collector): Extension<GarbageCollector>,
+    TypedHeader(XAdminToken(token)): TypedHeader<XAdminToken>,
+) -> Result<Json<GcReport>, StatusCode> {
+    info!("Received attempt to get the last garbage collection report");
+
+    check_admin_token(&token)?;
+
+    match collector.last_report().await {
+        Some(report) => Ok(Json(report)),
+        None => Err(StatusCode::NOT_FOUND),
+    }
+}
+
+#[tracing::instrument(skip(collector, token))]
+pub async fn post_admin_gc(
+    Extension(collector): Extension<GarbageCollector>,
+    TypedHeader(XAdminToken(token)): TypedHeader<XAdminToken>,
+) -> Result<Json<GcReport>, StatusCode> {
+    info!("Received garbage collection attempt");
+
+    check_admin_token(&token)?;
+
+    match collector.collect().await {
+        Ok(report) => Ok(Json(report)),
+        Err(err) => {
+            error!(%err);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
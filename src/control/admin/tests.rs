@@ -0,0 +1,10 @@
+use http::StatusCode;
+
+use super::*;
+
+#[test]
+fn check_admin_token_disabled_without_configured_token() {
+    // ADMIN_TOKEN is not set in the test environment
+    assert_eq!(Err(StatusCode::FORBIDDEN), check_admin_token(""));
+    assert_eq!(Err(StatusCode::FORBIDDEN), check_admin_token("guess"));
+}
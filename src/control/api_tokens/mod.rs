@@ -0,0 +1,72 @@
+use axum::{extract::Path, Extension, Json, TypedHeader};
+use http::StatusCode;
+use tracing::info;
+
+use crate::{
+    domain::api_tokens::{ApiToken, ApiTokenData, CreatedApiToken},
+    extractors::headers::XUserId,
+    repository::{
+        api_tokens::{
+            ApiTokenDeleteError, ApiTokenGetError, ApiTokenInsertError, ApiTokenRepository,
+        },
+        projects::{ProjectGetError, ProjectRepository},
+    },
+    validation::ValidatedJson,
+};
+
+#[tracing::instrument(skip(repository))]
+pub async fn get_users_me_tokens<T: ApiTokenRepository>(
+    Extension(repository): Extension<T>,
+    TypedHeader(XUserId(user_id)): TypedHeader<XUserId>,
+) -> Result<Json<Vec<ApiToken>>, StatusCode> {
+    info!("Received attempt to get access tokens");
+
+    match repository.get_by_user(user_id).await {
+        Ok(tokens) => Ok(Json(tokens)),
+        Err(ApiTokenGetError::Missing) | Err(ApiTokenGetError::Unknown) => {
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+#[tracing::instrument(skip(project_repository, repository))]
+pub async fn post_users_me_tokens<P: ProjectRepository, T: ApiTokenRepository>(
+    Extension(project_repository): Extension<P>,
+    Extension(repository): Extension<T>,
+    TypedHeader(XUserId(user_id)): TypedHeader<XUserId>,
+    ValidatedJson(data): ValidatedJson<ApiTokenData>,
+) -> Result<(StatusCode, Json<CreatedApiToken>), StatusCode> {
+    info!("Received access token creation attempt");
+
+    if let Some(project_id) = data.project_id {
+        match project_repository.has_access(project_id, user_id).await {
+            Ok(true) => (),
+            Ok(false) => return Err(StatusCode::FORBIDDEN),
+            Err(ProjectGetError::Missing) => return Err(StatusCode::NOT_FOUND),
+            Err(ProjectGetError::Unknown) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+        }
+    }
+
+    match repository.insert(user_id, &data).await {
+        Ok(token) => Ok((StatusCode::CREATED, Json(token))),
+        Err(ApiTokenInsertError::Unknown) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+#[tracing::instrument(skip(repository))]
+pub async fn delete_users_me_tokens<T: ApiTokenRepository>(
+    Extension(repository): Extension<T>,
+    TypedHeader(XUserId(user_id)): TypedHeader<XUserId>,
+    Path(token_id): Path<i32>,
+) -> StatusCode {
+    info!("Received access token revocation attempt");
+
+    match repository.delete(user_id, token_id).await {
+        Ok(()) => StatusCode::NO_CONTENT,
+        Err(ApiTokenDeleteError::Missing) => StatusCode::NOT_FOUND,
+        Err(ApiTokenDeleteError::Unknown) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+#[cfg(test)]
+mod tests;
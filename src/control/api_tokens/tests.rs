@@ -0,0 +1,102 @@
+use axum::Extension;
+use http::StatusCode;
+use mockall::predicate;
+use sqlx::types::chrono::NaiveDateTime;
+
+use crate::{
+    domain::api_tokens::ApiTokenScope,
+    repository::{api_tokens::MockApiTokenRepository, projects::MockProjectRepository},
+};
+
+use super::*;
+
+fn mock_token_data(project_id: Option<i32>) -> ApiTokenData {
+    ApiTokenData {
+        name: String::from("ci"),
+        scope: ApiTokenScope::Write,
+        project_id,
+        expires_in_days: 30,
+    }
+}
+
+fn mock_created_token() -> CreatedApiToken {
+    CreatedApiToken {
+        token: ApiToken {
+            token_id: 1,
+            user_id: 1,
+            name: String::from("ci"),
+            scope: ApiTokenScope::Write,
+            project_id: Some(3),
+            created_at: NaiveDateTime::default(),
+            expires_at: NaiveDateTime::default(),
+            last_used_at: None,
+        },
+        secret: String::from("pat_secret"),
+    }
+}
+
+#[tokio::test]
+async fn post_users_me_tokens_success() {
+    let mut project_repository = MockProjectRepository::new();
+    let mut repository = MockApiTokenRepository::new();
+
+    project_repository
+        .expect_has_access()
+        .with(predicate::eq(3), predicate::eq(1))
+        .times(1)
+        .returning(|_, _| Ok(true));
+    repository
+        .expect_insert()
+        .with(predicate::eq(1), predicate::eq(mock_token_data(Some(3))))
+        .times(1)
+        .returning(|_, _| Ok(mock_created_token()));
+
+    let res = post_users_me_tokens(
+        Extension(project_repository),
+        Extension(repository),
+        TypedHeader(XUserId(1)),
+        ValidatedJson(mock_token_data(Some(3))),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(StatusCode::CREATED, res.0);
+    assert_eq!(mock_created_token(), res.1 .0);
+}
+
+#[tokio::test]
+async fn post_users_me_tokens_foreign_project_error() {
+    let mut project_repository = MockProjectRepository::new();
+    let mut repository = MockApiTokenRepository::new();
+
+    project_repository
+        .expect_has_access()
+        .times(1)
+        .returning(|_, _| Ok(false));
+    repository.expect_insert().never();
+
+    let res = post_users_me_tokens(
+        Extension(project_repository),
+        Extension(repository),
+        TypedHeader(XUserId(1)),
+        ValidatedJson(mock_token_data(Some(3))),
+    )
+    .await;
+
+    assert_eq!(StatusCode::FORBIDDEN, res.unwrap_err());
+}
+
+#[tokio::test]
+async fn delete_users_me_tokens_missing_error() {
+    let mut repository = MockApiTokenRepository::new();
+
+    repository
+        .expect_delete()
+        .with(predicate::eq(1), predicate::eq(9))
+        .times(1)
+        .returning(|_, _| Err(ApiTokenDeleteError::Missing));
+
+    let res = delete_users_me_tokens(Extension(repository), TypedHeader(XUserId(1)), Path(9)).await;
+
+    assert_eq!(StatusCode::NOT_FOUND, res);
+}
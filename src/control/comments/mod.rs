@@ -0,0 +1,169 @@
+use axum::{extract::Path, Extension, Json, TypedHeader};
+use http::StatusCode;
+use tracing::info;
+
+use crate::{
+    control::activity::record_event,
+    domain::{
+        comments::{Comment, CommentData, Thread, ThreadData, ThreadStatus},
+        events::ProjectEventType,
+    },
+    extractors::headers::XUserId,
+    repository::{
+        comments::{CommentGetError, CommentInsertError, CommentRepository, CommentUpdateError},
+        events::EventRepository,
+        projects::{ProjectGetError, ProjectRepository},
+    },
+    validation::ValidatedJson,
+};
+
+#[tracing::instrument(skip(project_repository, comment_repository))]
+pub async fn get_projects_comments<P: ProjectRepository, C: CommentRepository>(
+    Extension(project_repository): Extension<P>,
+    Extension(comment_repository): Extension<C>,
+    TypedHeader(XUserId(user_id)): TypedHeader<XUserId>,
+    Path(project_id): Path<i32>,
+) -> Result<Json<Vec<Thread>>, StatusCode> {
+    info!("Received attempt to get project comments");
+
+    match project_repository.has_access(project_id, user_id).await {
+        Ok(true) => (),
+        Ok(false) => return Err(StatusCode::FORBIDDEN),
+        Err(ProjectGetError::Missing) => return Err(StatusCode::NOT_FOUND),
+        Err(ProjectGetError::Unknown) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+
+    match comment_repository.get(project_id).await {
+        Ok(threads) => Ok(Json(threads)),
+        Err(CommentGetError::Unknown) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+#[tracing::instrument(skip(project_repository, comment_repository, event_repository, data))]
+pub async fn post_projects_comments<
+    P: ProjectRepository,
+    C: CommentRepository,
+    E: EventRepository,
+>(
+    Extension(project_repository): Extension<P>,
+    Extension(comment_repository): Extension<C>,
+    Extension(event_repository): Extension<E>,
+    TypedHeader(XUserId(user_id)): TypedHeader<XUserId>,
+    Path(project_id): Path<i32>,
+    ValidatedJson(data): ValidatedJson<ThreadData>,
+) -> Result<(StatusCode, Json<Thread>), StatusCode> {
+    info!("Received comment thread creation attempt");
+
+    match project_repository.has_access(project_id, user_id).await {
+        Ok(true) => (),
+        Ok(false) => return Err(StatusCode::FORBIDDEN),
+        Err(ProjectGetError::Missing) => return Err(StatusCode::NOT_FOUND),
+        Err(ProjectGetError::Unknown) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+
+    match comment_repository
+        .insert_thread(project_id, user_id, &data)
+        .await
+    {
+        Ok(thread) => {
+            record_event(
+                &event_repository,
+                project_id,
+                user_id,
+                ProjectEventType::CommentCreate,
+                Some(thread.thread.thread_id),
+            )
+            .await;
+            Ok((StatusCode::CREATED, Json(thread)))
+        }
+        Err(CommentInsertError::Missing) => Err(StatusCode::NOT_FOUND),
+        Err(CommentInsertError::Unknown) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+#[tracing::instrument(skip(project_repository, comment_repository, event_repository, data))]
+pub async fn post_projects_comments_replies<
+    P: ProjectRepository,
+    C: CommentRepository,
+    E: EventRepository,
+>(
+    Extension(project_repository): Extension<P>,
+    Extension(comment_repository): Extension<C>,
+    Extension(event_repository): Extension<E>,
+    TypedHeader(XUserId(user_id)): TypedHeader<XUserId>,
+    Path((project_id, thread_id)): Path<(i32, i32)>,
+    ValidatedJson(data): ValidatedJson<CommentData>,
+) -> Result<(StatusCode, Json<Comment>), StatusCode> {
+    info!("Received comment reply attempt");
+
+    match project_repository.has_access(project_id, user_id).await {
+        Ok(true) => (),
+        Ok(false) => return Err(StatusCode::FORBIDDEN),
+        Err(ProjectGetError::Missing) => return Err(StatusCode::NOT_FOUND),
+        Err(ProjectGetError::Unknown) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+
+    match comment_repository
+        .insert_reply(project_id, thread_id, user_id, &data)
+        .await
+    {
+        Ok(comment) => {
+            record_event(
+                &event_repository,
+                project_id,
+                user_id,
+                ProjectEventType::CommentReply,
+                Some(thread_id),
+            )
+            .await;
+            Ok((StatusCode::CREATED, Json(comment)))
+        }
+        Err(CommentInsertError::Missing) => Err(StatusCode::NOT_FOUND),
+        Err(CommentInsertError::Unknown) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+#[tracing::instrument(skip(project_repository, comment_repository, event_repository))]
+pub async fn put_projects_comments_status<
+    P: ProjectRepository,
+    C: CommentRepository,
+    E: EventRepository,
+>(
+    Extension(project_repository): Extension<P>,
+    Extension(comment_repository): Extension<C>,
+    Extension(event_repository): Extension<E>,
+    TypedHeader(XUserId(user_id)): TypedHeader<XUserId>,
+    Path((project_id, thread_id)): Path<(i32, i32)>,
+    Json(data): Json<ThreadStatus>,
+) -> StatusCode {
+    info!("Received comment thread status update attempt");
+
+    match project_repository.has_access(project_id, user_id).await {
+        Ok(true) => (),
+        Ok(false) => return StatusCode::FORBIDDEN,
+        Err(ProjectGetError::Missing) => return StatusCode::NOT_FOUND,
+        Err(ProjectGetError::Unknown) => return StatusCode::INTERNAL_SERVER_ERROR,
+    }
+
+    match comment_repository
+        .update_status(project_id, thread_id, data.resolved)
+        .await
+    {
+        Ok(()) => {
+            record_event(
+                &event_repository,
+                project_id,
+                user_id,
+                ProjectEventType::CommentStatusUpdate,
+                Some(thread_id),
+            )
+            .await;
+            StatusCode::NO_CONTENT
+        }
+        Err(CommentUpdateError::Missing) => StatusCode::NOT_FOUND,
+        Err(CommentUpdateError::Unknown) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+#[cfg(test)]
+mod tests;
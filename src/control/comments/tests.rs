@@ -0,0 +1,281 @@
+use axum::Extension;
+use http::StatusCode;
+use mockall::predicate;
+use sqlx::types::chrono::Utc;
+
+use crate::{
+    domain::comments::{Comment, CommentData, CommentThread, Thread, ThreadData, ThreadStatus},
+    repository::{
+        comments::MockCommentRepository, events::MockEventRepository,
+        projects::MockProjectRepository,
+    },
+};
+
+use super::*;
+
+fn mock_project_id() -> i32 {
+    1
+}
+
+fn mock_user_id() -> i32 {
+    2
+}
+
+fn mock_comment_data() -> CommentData {
+    CommentData {
+        content: String::from("Cite the original paper here"),
+        mentions: vec![3],
+    }
+}
+
+fn mock_thread_data() -> ThreadData {
+    ThreadData {
+        document_id: 1,
+        anchor_start: 10,
+        anchor_end: 20,
+        comment: mock_comment_data(),
+    }
+}
+
+fn mock_comment() -> Comment {
+    Comment {
+        comment_id: 1,
+        thread_id: 1,
+        author_id: mock_user_id(),
+        author_email: String::from("email"),
+        author_display_name: None,
+        content: mock_comment_data().content,
+        mentions: vec![3],
+        created_at: Utc::now().naive_utc(),
+    }
+}
+
+fn mock_thread() -> Thread {
+    Thread {
+        thread: CommentThread {
+            thread_id: 1,
+            project_id: mock_project_id(),
+            document_id: 1,
+            author_id: mock_user_id(),
+            anchor_start: 10,
+            anchor_end: 20,
+            resolved: false,
+            created_at: Utc::now().naive_utc(),
+        },
+        comments: vec![mock_comment()],
+    }
+}
+
+fn mock_access(access: bool) -> MockProjectRepository {
+    let mut project_repository = MockProjectRepository::new();
+
+    project_repository
+        .expect_has_access()
+        .with(
+            predicate::eq(mock_project_id()),
+            predicate::eq(mock_user_id()),
+        )
+        .times(1)
+        .returning(move |_, _| Ok(access));
+
+    project_repository
+}
+
+fn mock_event_repository(event_type: Option<ProjectEventType>) -> MockEventRepository {
+    let mut event_repository = MockEventRepository::new();
+
+    match event_type {
+        Some(event_type) => event_repository
+            .expect_insert()
+            .with(
+                predicate::eq(mock_project_id()),
+                predicate::eq(mock_user_id()),
+                predicate::eq(event_type),
+                predicate::always(),
+            )
+            .times(1)
+            .returning(|_, _, _, _| Ok(())),
+        None => event_repository.expect_insert().times(0),
+    };
+
+    event_repository
+}
+
+#[tokio::test]
+async fn get_projects_comments_normal() {
+    let mut comment_repository = MockCommentRepository::new();
+    let threads = vec![mock_thread()];
+    let threads_cpy = threads.clone();
+
+    comment_repository
+        .expect_get()
+        .with(predicate::eq(mock_project_id()))
+        .times(1)
+        .return_once(|_| Ok(threads_cpy));
+
+    let res = get_projects_comments(
+        Extension(mock_access(true)),
+        Extension(comment_repository),
+        TypedHeader(XUserId(mock_user_id())),
+        Path(mock_project_id()),
+    )
+    .await;
+
+    assert!(res.is_ok());
+    assert_eq!(threads, res.unwrap().0)
+}
+
+#[tokio::test]
+async fn get_projects_comments_forbidden_error() {
+    let mut comment_repository = MockCommentRepository::new();
+
+    comment_repository.expect_get().times(0);
+
+    let res = get_projects_comments(
+        Extension(mock_access(false)),
+        Extension(comment_repository),
+        TypedHeader(XUserId(mock_user_id())),
+        Path(mock_project_id()),
+    )
+    .await;
+
+    assert!(res.is_err());
+    assert_eq!(StatusCode::FORBIDDEN, res.unwrap_err())
+}
+
+#[tokio::test]
+async fn post_projects_comments_normal() {
+    let mut comment_repository = MockCommentRepository::new();
+    let thread = mock_thread();
+    let thread_cpy = thread.clone();
+
+    comment_repository
+        .expect_insert_thread()
+        .with(
+            predicate::eq(mock_project_id()),
+            predicate::eq(mock_user_id()),
+            predicate::eq(mock_thread_data()),
+        )
+        .times(1)
+        .return_once(|_, _, _| Ok(thread_cpy));
+
+    let res = post_projects_comments(
+        Extension(mock_access(true)),
+        Extension(comment_repository),
+        Extension(mock_event_repository(Some(ProjectEventType::CommentCreate))),
+        TypedHeader(XUserId(mock_user_id())),
+        Path(mock_project_id()),
+        ValidatedJson(mock_thread_data()),
+    )
+    .await;
+
+    assert!(res.is_ok());
+    let (status, Json(body)) = res.unwrap();
+    assert_eq!(StatusCode::CREATED, status);
+    assert_eq!(thread, body)
+}
+
+#[tokio::test]
+async fn post_projects_comments_missing_document_error() {
+    let mut comment_repository = MockCommentRepository::new();
+
+    comment_repository
+        .expect_insert_thread()
+        .times(1)
+        .returning(|_, _, _| Err(CommentInsertError::Missing));
+
+    let res = post_projects_comments(
+        Extension(mock_access(true)),
+        Extension(comment_repository),
+        Extension(mock_event_repository(None)),
+        TypedHeader(XUserId(mock_user_id())),
+        Path(mock_project_id()),
+        ValidatedJson(mock_thread_data()),
+    )
+    .await;
+
+    assert!(res.is_err());
+    assert_eq!(StatusCode::NOT_FOUND, res.unwrap_err())
+}
+
+#[tokio::test]
+async fn post_projects_comments_replies_unknown_error() {
+    let mut comment_repository = MockCommentRepository::new();
+
+    comment_repository
+        .expect_insert_reply()
+        .with(
+            predicate::eq(mock_project_id()),
+            predicate::eq(1),
+            predicate::eq(mock_user_id()),
+            predicate::eq(mock_comment_data()),
+        )
+        .times(1)
+        .returning(|_, _, _, _| Err(CommentInsertError::Unknown));
+
+    let res = post_projects_comments_replies(
+        Extension(mock_access(true)),
+        Extension(comment_repository),
+        Extension(mock_event_repository(None)),
+        TypedHeader(XUserId(mock_user_id())),
+        Path((mock_project_id(), 1)),
+        ValidatedJson(mock_comment_data()),
+    )
+    .await;
+
+    assert!(res.is_err());
+    assert_eq!(StatusCode::INTERNAL_SERVER_ERROR, res.unwrap_err())
+}
+
+#[tokio::test]
+async fn put_projects_comments_status_normal() {
+    let mut comment_repository = MockCommentRepository::new();
+
+    comment_repository
+        .expect_update_status()
+        .with(
+            predicate::eq(mock_project_id()),
+            predicate::eq(1),
+            predicate::eq(true),
+        )
+        .times(1)
+        .returning(|_, _, _| Ok(()));
+
+    assert_eq!(
+        StatusCode::NO_CONTENT,
+        put_projects_comments_status(
+            Extension(mock_access(true)),
+            Extension(comment_repository),
+            Extension(mock_event_repository(Some(
+                ProjectEventType::CommentStatusUpdate
+            ))),
+            TypedHeader(XUserId(mock_user_id())),
+            Path((mock_project_id(), 1)),
+            Json(ThreadStatus { resolved: true }),
+        )
+        .await
+    )
+}
+
+#[tokio::test]
+async fn put_projects_comments_status_missing_error() {
+    let mut comment_repository = MockCommentRepository::new();
+
+    comment_repository
+        .expect_update_status()
+        .times(1)
+        .returning(|_, _, _| Err(CommentUpdateError::Missing));
+
+    assert_eq!(
+        StatusCode::NOT_FOUND,
+        put_projects_comments_status(
+            Extension(mock_access(true)),
+            Extension(comment_repository),
+            Extension(mock_event_repository(None)),
+            TypedHeader(XUserId(mock_user_id())),
+            Path((mock_project_id(), 1)),
+            Json(ThreadStatus { resolved: false }),
+        )
+        .await
+    )
+}
@@ -1,15 +1,28 @@
-use axum::{extract::Path, Extension, Json, TypedHeader};
+use axum::{
+    extract::Path,
+    response::{IntoResponse, Response},
+    Extension, Json, TypedHeader,
+};
 use http::StatusCode;
-use tracing::info;
+use tracing::{info, warn};
 
 use crate::{
-    domain::documents::{Document, DocumentData},
+    control::{activity::record_event, usage::quota_exceeded_response},
+    diff::TextEdit,
+    domain::{
+        documents::{Document, DocumentData},
+        events::ProjectEventType,
+        suggestions::SuggestionData,
+    },
     extractors::headers::XUserId,
     repository::{
+        comments::CommentRepository,
         documents::{
             DocumentGetError, DocumentInsertError, DocumentRepository, DocumentUpdateError,
         },
+        events::EventRepository,
         projects::{ProjectGetError, ProjectRepository},
+        suggestions::SuggestionRepository,
     },
 };
 
@@ -50,44 +63,161 @@ pub async fn put_documents_metadata<T: DocumentRepository>(
     Path(document_id): Path<i32>,
     TypedHeader(XUserId(_user_id)): TypedHeader<XUserId>,
     Json(data): Json<DocumentData>,
-) -> StatusCode {
+) -> Response {
     info!("Received document update attempt");
 
     match repository.update(document_id, &data).await {
-        Ok(()) => StatusCode::CREATED,
-        Err(DocumentUpdateError::Missing) => StatusCode::NOT_FOUND,
-        Err(DocumentUpdateError::Unknown) => StatusCode::INTERNAL_SERVER_ERROR,
+        Ok(()) => StatusCode::CREATED.into_response(),
+        Err(DocumentUpdateError::Missing) => StatusCode::NOT_FOUND.into_response(),
+        Err(DocumentUpdateError::NoSpace) => StatusCode::INSUFFICIENT_STORAGE.into_response(),
+        Err(DocumentUpdateError::QuotaExceeded(exceeded)) => quota_exceeded_response(exceeded),
+        Err(DocumentUpdateError::Unknown) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
     }
 }
 
-#[tracing::instrument(skip(project_repository, document_repository, content))]
-pub async fn put_projects_documents<P: ProjectRepository, D: DocumentRepository>(
+/// Moves comment anchors and pending suggestions over an edit that was written to the document
+pub async fn propagate_edit<C: CommentRepository, S: SuggestionRepository>(
+    comment_repository: &C,
+    suggestion_repository: &S,
+    document_id: i32,
+    edit: &TextEdit,
+) {
+    // a failure here must not fail the write itself
+    if comment_repository
+        .reanchor(document_id, edit)
+        .await
+        .is_err()
+    {
+        warn!("Could not re-anchor comments");
+    }
+    if suggestion_repository
+        .rebase(document_id, edit)
+        .await
+        .is_err()
+    {
+        warn!("Could not rebase suggestions");
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+#[tracing::instrument(skip(
+    project_repository,
+    document_repository,
+    comment_repository,
+    suggestion_repository,
+    event_repository,
+    content
+))]
+pub async fn put_projects_documents<
+    P: ProjectRepository,
+    D: DocumentRepository,
+    C: CommentRepository,
+    S: SuggestionRepository,
+    E: EventRepository,
+>(
     Extension(project_repository): Extension<P>,
     Extension(document_repository): Extension<D>,
+    Extension(comment_repository): Extension<C>,
+    Extension(suggestion_repository): Extension<S>,
+    Extension(event_repository): Extension<E>,
     TypedHeader(XUserId(user_id)): TypedHeader<XUserId>,
     Path(project_id): Path<i32>,
     content: String,
-) -> StatusCode {
+) -> Response {
     info!("Received attempt to update document text");
 
-    let document_id = match project_repository.get_meta(project_id).await {
-        Ok(project) => project.main_document_id,
-        Err(ProjectGetError::Missing) => return StatusCode::NOT_FOUND,
-        Err(ProjectGetError::Unknown) => return StatusCode::INTERNAL_SERVER_ERROR,
+    match project_repository.has_access(project_id, user_id).await {
+        Ok(true) => (),
+        Ok(false) => return StatusCode::FORBIDDEN.into_response(),
+        Err(ProjectGetError::Missing) => return StatusCode::NOT_FOUND.into_response(),
+        Err(ProjectGetError::Unknown) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    }
+
+    let project = match project_repository.get_meta(project_id).await {
+        Ok(project) => project,
+        Err(ProjectGetError::Missing) => return StatusCode::NOT_FOUND.into_response(),
+        Err(ProjectGetError::Unknown) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
     };
+    let document_id = project.main_document_id;
 
     info!("Retrieved document id: {}", document_id);
 
     let document = match document_repository.get_meta(project_id, document_id).await {
         Ok(document) => document,
-        Err(DocumentGetError::Missing) => return StatusCode::NOT_FOUND,
-        Err(DocumentGetError::Unknown) => return StatusCode::INTERNAL_SERVER_ERROR,
+        Err(DocumentGetError::Missing) => return StatusCode::NOT_FOUND.into_response(),
+        Err(DocumentGetError::Unknown) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
     };
 
+    let previous = document_repository.read_file(&document).await;
+
+    if project.track_changes && project.owner_id != user_id {
+        info!("Storing write as suggestions");
+
+        let previous = match previous {
+            Ok(previous) => previous,
+            Err(DocumentGetError::Missing) => return StatusCode::NOT_FOUND.into_response(),
+            Err(DocumentGetError::Unknown) => {
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            }
+        };
+        // a save back to the stored text withdraws the pending suggestions of the author
+        let suggestions = TextEdit::between(&previous, &content)
+            .map(|edit| SuggestionData::from_edit(&previous, &edit))
+            .unwrap_or_default();
+        return match suggestion_repository
+            .replace_pending(project_id, document_id, user_id, &suggestions)
+            .await
+        {
+            Ok(()) if suggestions.is_empty() => StatusCode::NO_CONTENT.into_response(),
+            Ok(()) => {
+                record_event(
+                    &event_repository,
+                    project_id,
+                    user_id,
+                    ProjectEventType::SuggestionCreate,
+                    Some(document_id),
+                )
+                .await;
+                StatusCode::ACCEPTED.into_response()
+            }
+            Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+        };
+    }
+
     match document_repository.write_file(&document, &content).await {
-        Ok(_) => StatusCode::NO_CONTENT,
-        Err(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        Ok(()) => (),
+        Err(DocumentUpdateError::NoSpace) => {
+            return StatusCode::INSUFFICIENT_STORAGE.into_response()
+        }
+        Err(DocumentUpdateError::QuotaExceeded(exceeded)) => {
+            return quota_exceeded_response(exceeded)
+        }
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
     }
+
+    record_event(
+        &event_repository,
+        project_id,
+        user_id,
+        ProjectEventType::DocumentUpdate,
+        Some(document_id),
+    )
+    .await;
+
+    if let Some(edit) = previous
+        .ok()
+        .and_then(|previous| TextEdit::between(&previous, &content))
+    {
+        propagate_edit(
+            &comment_repository,
+            &suggestion_repository,
+            document_id,
+            &edit,
+        )
+        .await;
+    }
+
+    StatusCode::NO_CONTENT.into_response()
 }
 
 #[tracing::instrument(skip(project_repository, document_repository))]
@@ -123,3 +253,6 @@ pub async fn get_projects_documents<P: ProjectRepository, D: DocumentRepository>
 
     Ok(content)
 }
+
+#[cfg(test)]
+mod tests;
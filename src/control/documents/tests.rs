@@ -0,0 +1,188 @@
+use axum::Extension;
+use http::StatusCode;
+use mockall::predicate;
+use sqlx::types::chrono::Utc;
+
+use crate::{
+    domain::{documents::Document, projects::Project, suggestions::SuggestionKind},
+    repository::{
+        comments::MockCommentRepository, documents::MockDocumentRepository,
+        events::MockEventRepository, projects::MockProjectRepository,
+        suggestions::MockSuggestionRepository,
+    },
+};
+
+use super::*;
+
+fn mock_owner_id() -> i32 {
+    1
+}
+
+fn mock_collaborator_id() -> i32 {
+    2
+}
+
+fn mock_stranger_id() -> i32 {
+    3
+}
+
+fn mock_project() -> Project {
+    Project {
+        project_id: 1,
+        main_document_id: 1,
+        owner_id: mock_owner_id(),
+        owner_email: String::from("email"),
+        owner_display_name: None,
+        project_name: String::from("project"),
+        created_at: Utc::now().naive_utc(),
+        last_modified: Utc::now().naive_utc(),
+        track_changes: true,
+        used_bytes: 0,
+    }
+}
+
+fn mock_document() -> Document {
+    Document {
+        document_id: 1,
+        project_id: 1,
+        name: String::from("main.tex"),
+        folder_id: None,
+    }
+}
+
+fn mock_project_repository() -> MockProjectRepository {
+    let mut project_repository = MockProjectRepository::new();
+
+    project_repository
+        .expect_has_access()
+        .returning(|_, user_id| Ok(user_id != mock_stranger_id()));
+    project_repository
+        .expect_get_meta()
+        .with(predicate::eq(1))
+        .returning(|_| Ok(mock_project()));
+
+    project_repository
+}
+
+fn mock_document_repository(text: &'static str) -> MockDocumentRepository {
+    let mut document_repository = MockDocumentRepository::new();
+
+    document_repository
+        .expect_get_meta()
+        .with(predicate::eq(1), predicate::eq(1))
+        .returning(|_, _| Ok(mock_document()));
+    document_repository
+        .expect_read_file()
+        .returning(move |_| Ok(String::from(text)));
+
+    document_repository
+}
+
+fn mock_event_repository(times: usize) -> MockEventRepository {
+    let mut event_repository = MockEventRepository::new();
+
+    event_repository
+        .expect_insert()
+        .times(times)
+        .returning(|_, _, _, _| Ok(()));
+
+    event_repository
+}
+
+fn insertion(content: &str) -> Vec<SuggestionData> {
+    vec![SuggestionData {
+        kind: SuggestionKind::Insertion,
+        position: 6,
+        content: String::from(content),
+    }]
+}
+
+async fn save(suggestion_repository: MockSuggestionRepository, content: &str) -> StatusCode {
+    put_projects_documents(
+        Extension(mock_project_repository()),
+        Extension(mock_document_repository("Hello world")),
+        Extension(MockCommentRepository::new()),
+        Extension(suggestion_repository),
+        Extension(mock_event_repository(1)),
+        TypedHeader(XUserId(mock_collaborator_id())),
+        Path(1),
+        String::from(content),
+    )
+    .await
+    .status()
+}
+
+#[tokio::test]
+async fn put_projects_documents_consecutive_saves_replace_suggestions() {
+    // the stored text stays the same while suggestions are pending
+    for (content, suggested) in [
+        ("Hello foo world", "foo "),
+        ("Hello foobar world", "foobar "),
+    ] {
+        let mut suggestion_repository = MockSuggestionRepository::new();
+        suggestion_repository
+            .expect_replace_pending()
+            .with(
+                predicate::eq(1),
+                predicate::eq(1),
+                predicate::eq(mock_collaborator_id()),
+                predicate::eq(insertion(suggested)),
+            )
+            .times(1)
+            .returning(|_, _, _, _| Ok(()));
+
+        assert_eq!(
+            StatusCode::ACCEPTED,
+            save(suggestion_repository, content).await
+        );
+    }
+}
+
+#[tokio::test]
+async fn put_projects_documents_save_back_withdraws_suggestions() {
+    let mut suggestion_repository = MockSuggestionRepository::new();
+    suggestion_repository
+        .expect_replace_pending()
+        .withf(|_, _, _, data| data.is_empty())
+        .times(1)
+        .returning(|_, _, _, _| Ok(()));
+
+    let status = put_projects_documents(
+        Extension(mock_project_repository()),
+        Extension(mock_document_repository("Hello world")),
+        Extension(MockCommentRepository::new()),
+        Extension(suggestion_repository),
+        Extension(mock_event_repository(0)),
+        TypedHeader(XUserId(mock_collaborator_id())),
+        Path(1),
+        String::from("Hello world"),
+    )
+    .await
+    .status();
+
+    assert_eq!(StatusCode::NO_CONTENT, status);
+}
+
+#[tokio::test]
+async fn put_projects_documents_stranger_forbidden_error() {
+    let mut document_repository = MockDocumentRepository::new();
+    let mut suggestion_repository = MockSuggestionRepository::new();
+
+    document_repository.expect_write_file().times(0);
+    suggestion_repository.expect_replace_pending().times(0);
+
+    let status = put_projects_documents(
+        Extension(mock_project_repository()),
+        Extension(document_repository),
+        Extension(MockCommentRepository::new()),
+        Extension(suggestion_repository),
+        Extension(mock_event_repository(0)),
+        TypedHeader(XUserId(mock_stranger_id())),
+        Path(1),
+        String::from("Hello foo world"),
+    )
+    .await
+    .status();
+
+    assert_eq!(StatusCode::FORBIDDEN, status);
+}
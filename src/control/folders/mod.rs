@@ -0,0 +1,334 @@
+use std::collections::HashMap;
+
+use axum::{extract::Path, Extension, Json, TypedHeader};
+use http::StatusCode;
+use tracing::info;
+
+use crate::{
+    control::activity::record_event,
+    domain::{
+        documents::Document,
+        events::ProjectEventType,
+        folders::{Folder, FolderData, FolderPlacement, FolderTree},
+        resources::Resource,
+    },
+    extractors::headers::XUserId,
+    repository::{
+        documents::{DocumentGetError, DocumentRepository},
+        events::EventRepository,
+        folders::{
+            FolderDeleteError, FolderGetError, FolderInsertError, FolderMoveError,
+            FolderRepository, FolderUpdateError,
+        },
+        projects::{ProjectGetError, ProjectRepository},
+        resources::{ResourceGetError, ResourceRepository},
+    },
+    validation::ValidatedJson,
+};
+
+async fn check_access<P: ProjectRepository>(
+    project_repository: &P,
+    project_id: i32,
+    user_id: i32,
+) -> Result<(), StatusCode> {
+    match project_repository.has_access(project_id, user_id).await {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(StatusCode::FORBIDDEN),
+        Err(ProjectGetError::Missing) => Err(StatusCode::NOT_FOUND),
+        Err(ProjectGetError::Unknown) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+fn build_subtree(
+    folder_id: Option<i32>,
+    name: String,
+    children: &mut HashMap<Option<i32>, Vec<Folder>>,
+    documents: &mut HashMap<Option<i32>, Vec<Document>>,
+    resources: &mut HashMap<Option<i32>, Vec<Resource>>,
+) -> FolderTree {
+    let folders = children
+        .remove(&folder_id)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|folder| {
+            build_subtree(
+                Some(folder.folder_id),
+                folder.name,
+                children,
+                documents,
+                resources,
+            )
+        })
+        .collect();
+
+    FolderTree {
+        folder_id,
+        name,
+        folders,
+        documents: documents.remove(&folder_id).unwrap_or_default(),
+        resources: resources.remove(&folder_id).unwrap_or_default(),
+    }
+}
+
+/// Nests the flat folder and file lists below the project root
+pub fn build_tree(
+    folders: Vec<Folder>,
+    documents: Vec<Document>,
+    resources: Vec<Resource>,
+) -> FolderTree {
+    let mut children: HashMap<Option<i32>, Vec<Folder>> = HashMap::new();
+    for folder in folders {
+        children.entry(folder.parent_id).or_default().push(folder);
+    }
+    let mut documents_by_folder: HashMap<Option<i32>, Vec<Document>> = HashMap::new();
+    for document in documents {
+        documents_by_folder
+            .entry(document.folder_id)
+            .or_default()
+            .push(document);
+    }
+    let mut resources_by_folder: HashMap<Option<i32>, Vec<Resource>> = HashMap::new();
+    for resource in resources {
+        resources_by_folder
+            .entry(resource.folder_id)
+            .or_default()
+            .push(resource);
+    }
+
+    build_subtree(
+        None,
+        String::new(),
+        &mut children,
+        &mut documents_by_folder,
+        &mut resources_by_folder,
+    )
+}
+
+#[tracing::instrument(skip(
+    project_repository,
+    folder_repository,
+    document_repository,
+    resource_repository
+))]
+pub async fn get_projects_tree<
+    P: ProjectRepository,
+    F: FolderRepository,
+    D: DocumentRepository,
+    R: ResourceRepository,
+>(
+    Extension(project_repository): Extension<P>,
+    Extension(folder_repository): Extension<F>,
+    Extension(document_repository): Extension<D>,
+    Extension(resource_repository): Extension<R>,
+    TypedHeader(XUserId(user_id)): TypedHeader<XUserId>,
+    Path(project_id): Path<i32>,
+) -> Result<Json<FolderTree>, StatusCode> {
+    info!("Received attempt to get project tree");
+
+    check_access(&project_repository, project_id, user_id).await?;
+
+    let folders = match folder_repository.get(project_id).await {
+        Ok(folders) => folders,
+        Err(FolderGetError::Unknown) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+    let documents = match document_repository.get(project_id).await {
+        Ok(documents) => documents,
+        Err(DocumentGetError::Missing) => return Err(StatusCode::NOT_FOUND),
+        Err(DocumentGetError::Unknown) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+    let resources = match resource_repository.get(project_id).await {
+        Ok(resources) => resources,
+        Err(ResourceGetError::Missing) => return Err(StatusCode::NOT_FOUND),
+        Err(ResourceGetError::Unknown) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+
+    Ok(Json(build_tree(folders, documents, resources)))
+}
+
+#[tracing::instrument(skip(project_repository, folder_repository, event_repository))]
+pub async fn post_projects_folders<
+    P: ProjectRepository,
+    F: FolderRepository,
+    E: EventRepository,
+>(
+    Extension(project_repository): Extension<P>,
+    Extension(folder_repository): Extension<F>,
+    Extension(event_repository): Extension<E>,
+    TypedHeader(XUserId(user_id)): TypedHeader<XUserId>,
+    Path(project_id): Path<i32>,
+    ValidatedJson(data): ValidatedJson<FolderData>,
+) -> Result<(StatusCode, Json<Folder>), StatusCode> {
+    info!("Received folder creation attempt");
+
+    check_access(&project_repository, project_id, user_id).await?;
+
+    match folder_repository.insert(project_id, &data).await {
+        Ok(folder) => {
+            record_event(
+                &event_repository,
+                project_id,
+                user_id,
+                ProjectEventType::FolderCreate,
+                Some(folder.folder_id),
+            )
+            .await;
+            Ok((StatusCode::CREATED, Json(folder)))
+        }
+        Err(FolderInsertError::Missing) => Err(StatusCode::NOT_FOUND),
+        Err(FolderInsertError::Duplicate) => Err(StatusCode::CONFLICT),
+        Err(FolderInsertError::Unknown) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+#[tracing::instrument(skip(project_repository, folder_repository, event_repository))]
+pub async fn put_projects_folders<P: ProjectRepository, F: FolderRepository, E: EventRepository>(
+    Extension(project_repository): Extension<P>,
+    Extension(folder_repository): Extension<F>,
+    Extension(event_repository): Extension<E>,
+    TypedHeader(XUserId(user_id)): TypedHeader<XUserId>,
+    Path((project_id, folder_id)): Path<(i32, i32)>,
+    ValidatedJson(data): ValidatedJson<FolderData>,
+) -> Result<Json<Folder>, StatusCode> {
+    info!("Received folder update attempt");
+
+    check_access(&project_repository, project_id, user_id).await?;
+
+    match folder_repository.update(project_id, folder_id, &data).await {
+        Ok(folder) => {
+            record_event(
+                &event_repository,
+                project_id,
+                user_id,
+                ProjectEventType::FolderUpdate,
+                Some(folder_id),
+            )
+            .await;
+            Ok(Json(folder))
+        }
+        Err(FolderUpdateError::Missing) => Err(StatusCode::NOT_FOUND),
+        Err(FolderUpdateError::Duplicate | FolderUpdateError::Cycle) => Err(StatusCode::CONFLICT),
+        Err(FolderUpdateError::Unknown) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+#[tracing::instrument(skip(project_repository, folder_repository, event_repository))]
+pub async fn delete_projects_folders<
+    P: ProjectRepository,
+    F: FolderRepository,
+    E: EventRepository,
+>(
+    Extension(project_repository): Extension<P>,
+    Extension(folder_repository): Extension<F>,
+    Extension(event_repository): Extension<E>,
+    TypedHeader(XUserId(user_id)): TypedHeader<XUserId>,
+    Path((project_id, folder_id)): Path<(i32, i32)>,
+) -> StatusCode {
+    info!("Received folder deletion attempt");
+
+    if let Err(status) = check_access(&project_repository, project_id, user_id).await {
+        return status;
+    }
+
+    match folder_repository.delete(project_id, folder_id).await {
+        Ok(()) => {
+            record_event(
+                &event_repository,
+                project_id,
+                user_id,
+                ProjectEventType::FolderDelete,
+                Some(folder_id),
+            )
+            .await;
+            StatusCode::NO_CONTENT
+        }
+        Err(FolderDeleteError::Missing) => StatusCode::NOT_FOUND,
+        Err(FolderDeleteError::NotEmpty) => StatusCode::CONFLICT,
+        Err(FolderDeleteError::Unknown) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+fn move_status(result: Result<(), FolderMoveError>) -> Result<(), StatusCode> {
+    match result {
+        Ok(()) => Ok(()),
+        Err(FolderMoveError::Missing) => Err(StatusCode::NOT_FOUND),
+        Err(FolderMoveError::Duplicate) => Err(StatusCode::CONFLICT),
+        Err(FolderMoveError::Unknown) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+#[tracing::instrument(skip(project_repository, folder_repository, event_repository))]
+pub async fn put_projects_folders_documents<
+    P: ProjectRepository,
+    F: FolderRepository,
+    E: EventRepository,
+>(
+    Extension(project_repository): Extension<P>,
+    Extension(folder_repository): Extension<F>,
+    Extension(event_repository): Extension<E>,
+    TypedHeader(XUserId(user_id)): TypedHeader<XUserId>,
+    Path((project_id, document_id)): Path<(i32, i32)>,
+    Json(placement): Json<FolderPlacement>,
+) -> StatusCode {
+    info!("Received document move attempt");
+
+    if let Err(status) = check_access(&project_repository, project_id, user_id).await {
+        return status;
+    }
+
+    let result = folder_repository
+        .move_document(project_id, document_id, placement.folder_id)
+        .await;
+    if let Err(status) = move_status(result) {
+        return status;
+    }
+
+    record_event(
+        &event_repository,
+        project_id,
+        user_id,
+        ProjectEventType::DocumentUpdate,
+        Some(document_id),
+    )
+    .await;
+    StatusCode::NO_CONTENT
+}
+
+#[tracing::instrument(skip(project_repository, folder_repository, event_repository))]
+pub async fn put_projects_folders_resources<
+    P: ProjectRepository,
+    F: FolderRepository,
+    E: EventRepository,
+>(
+    Extension(project_repository): Extension<P>,
+    Extension(folder_repository): Extension<F>,
+    Extension(event_repository): Extension<E>,
+    TypedHeader(XUserId(user_id)): TypedHeader<XUserId>,
+    Path((project_id, resource_id)): Path<(i32, i32)>,
+    Json(placement): Json<FolderPlacement>,
+) -> StatusCode {
+    info!("Received resource move attempt");
+
+    if let Err(status) = check_access(&project_repository, project_id, user_id).await {
+        return status;
+    }
+
+    let result = folder_repository
+        .move_resource(project_id, resource_id, placement.folder_id)
+        .await;
+    if let Err(status) = move_status(result) {
+        return status;
+    }
+
+    record_event(
+        &event_repository,
+        project_id,
+        user_id,
+        ProjectEventType::ResourceUpdate,
+        Some(resource_id),
+    )
+    .await;
+    StatusCode::NO_CONTENT
+}
+
+#[cfg(test)]
+mod tests;
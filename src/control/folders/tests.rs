@@ -0,0 +1,217 @@
+use axum::Extension;
+use http::StatusCode;
+use mockall::predicate;
+
+use crate::repository::{
+    events::MockEventRepository, folders::MockFolderRepository, projects::MockProjectRepository,
+};
+
+use super::*;
+
+fn mock_folder(folder_id: i32, parent_id: Option<i32>, name: &str) -> Folder {
+    Folder {
+        folder_id,
+        project_id: 1,
+        parent_id,
+        name: String::from(name),
+    }
+}
+
+#[test]
+fn build_tree_nests_folders_and_files() {
+    let folders = vec![
+        mock_folder(1, None, "chapters"),
+        mock_folder(2, Some(1), "appendix"),
+        mock_folder(3, None, "figures"),
+    ];
+    let documents = vec![Document {
+        document_id: 1,
+        project_id: 1,
+        name: String::from("main.tex"),
+        folder_id: None,
+    }];
+    let resources = vec![
+        Resource {
+            resource_id: 1,
+            project_id: 1,
+            name: String::from("plot.png"),
+            folder_id: Some(3),
+            content_hash: None,
+        },
+        Resource {
+            resource_id: 2,
+            project_id: 1,
+            name: String::from("data.csv"),
+            folder_id: Some(2),
+            content_hash: None,
+        },
+    ];
+
+    let tree = build_tree(folders, documents, resources);
+
+    assert_eq!(None, tree.folder_id);
+    assert_eq!("main.tex", tree.documents[0].name);
+    assert!(tree.resources.is_empty());
+
+    let names: Vec<&str> = tree.folders.iter().map(|f| f.name.as_str()).collect();
+    assert_eq!(vec!["chapters", "figures"], names);
+    assert_eq!("appendix", tree.folders[0].folders[0].name);
+    assert_eq!("data.csv", tree.folders[0].folders[0].resources[0].name);
+    assert_eq!("plot.png", tree.folders[1].resources[0].name);
+}
+
+#[tokio::test]
+async fn put_projects_folders_cycle_conflict_error() {
+    let mut project_repository = MockProjectRepository::new();
+    let mut folder_repository = MockFolderRepository::new();
+    let mut event_repository = MockEventRepository::new();
+
+    project_repository
+        .expect_has_access()
+        .with(predicate::eq(1), predicate::eq(2))
+        .returning(|_, _| Ok(true));
+    folder_repository
+        .expect_update()
+        .withf(|project_id, folder_id, data| {
+            *project_id == 1 && *folder_id == 1 && data.parent_id == Some(2)
+        })
+        .times(1)
+        .returning(|_, _, _| Err(FolderUpdateError::Cycle));
+    event_repository.expect_insert().times(0);
+
+    let result = put_projects_folders(
+        Extension(project_repository),
+        Extension(folder_repository),
+        Extension(event_repository),
+        TypedHeader(XUserId(2)),
+        Path((1, 1)),
+        ValidatedJson(FolderData {
+            name: String::from("chapters"),
+            parent_id: Some(2),
+        }),
+    )
+    .await;
+
+    assert_eq!(Err(StatusCode::CONFLICT), result.map(|_| ()));
+}
+
+#[tokio::test]
+async fn delete_projects_folders_not_empty_error() {
+    let mut project_repository = MockProjectRepository::new();
+    let mut folder_repository = MockFolderRepository::new();
+
+    project_repository
+        .expect_has_access()
+        .with(predicate::eq(1), predicate::eq(2))
+        .returning(|_, _| Ok(true));
+    folder_repository
+        .expect_delete()
+        .with(predicate::eq(1), predicate::eq(3))
+        .times(1)
+        .returning(|_, _| Err(FolderDeleteError::NotEmpty));
+
+    assert_eq!(
+        StatusCode::CONFLICT,
+        delete_projects_folders(
+            Extension(project_repository),
+            Extension(folder_repository),
+            Extension(MockEventRepository::new()),
+            TypedHeader(XUserId(2)),
+            Path((1, 3)),
+        )
+        .await
+    );
+}
+
+#[tokio::test]
+async fn put_projects_folders_documents_moves_document() {
+    let mut project_repository = MockProjectRepository::new();
+    let mut folder_repository = MockFolderRepository::new();
+    let mut event_repository = MockEventRepository::new();
+
+    project_repository
+        .expect_has_access()
+        .with(predicate::eq(1), predicate::eq(2))
+        .returning(|_, _| Ok(true));
+    folder_repository
+        .expect_move_document()
+        .with(predicate::eq(1), predicate::eq(4), predicate::eq(Some(3)))
+        .times(1)
+        .returning(|_, _, _| Ok(()));
+    event_repository
+        .expect_insert()
+        .withf(|_, _, event_type, target_id| {
+            *event_type == ProjectEventType::DocumentUpdate && *target_id == Some(4)
+        })
+        .times(1)
+        .returning(|_, _, _, _| Ok(()));
+
+    assert_eq!(
+        StatusCode::NO_CONTENT,
+        put_projects_folders_documents(
+            Extension(project_repository),
+            Extension(folder_repository),
+            Extension(event_repository),
+            TypedHeader(XUserId(2)),
+            Path((1, 4)),
+            Json(FolderPlacement { folder_id: Some(3) }),
+        )
+        .await
+    );
+}
+
+#[tokio::test]
+async fn put_projects_folders_resources_foreign_folder_error() {
+    let mut project_repository = MockProjectRepository::new();
+    let mut folder_repository = MockFolderRepository::new();
+    let mut event_repository = MockEventRepository::new();
+
+    project_repository
+        .expect_has_access()
+        .with(predicate::eq(1), predicate::eq(2))
+        .returning(|_, _| Ok(true));
+    folder_repository
+        .expect_move_resource()
+        .with(predicate::eq(1), predicate::eq(5), predicate::eq(Some(9)))
+        .times(1)
+        .returning(|_, _, _| Err(FolderMoveError::Missing));
+    event_repository.expect_insert().times(0);
+
+    assert_eq!(
+        StatusCode::NOT_FOUND,
+        put_projects_folders_resources(
+            Extension(project_repository),
+            Extension(folder_repository),
+            Extension(event_repository),
+            TypedHeader(XUserId(2)),
+            Path((1, 5)),
+            Json(FolderPlacement { folder_id: Some(9) }),
+        )
+        .await
+    );
+}
+
+#[tokio::test]
+async fn put_projects_folders_resources_stranger_forbidden_error() {
+    let mut project_repository = MockProjectRepository::new();
+    let mut folder_repository = MockFolderRepository::new();
+
+    project_repository
+        .expect_has_access()
+        .with(predicate::eq(1), predicate::eq(2))
+        .returning(|_, _| Ok(false));
+    folder_repository.expect_move_resource().times(0);
+
+    assert_eq!(
+        StatusCode::FORBIDDEN,
+        put_projects_folders_resources(
+            Extension(project_repository),
+            Extension(folder_repository),
+            Extension(MockEventRepository::new()),
+            TypedHeader(XUserId(2)),
+            Path((1, 5)),
+            Json(FolderPlacement { folder_id: None }),
+        )
+        .await
+    );
+}
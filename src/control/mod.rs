@@ -1,6 +1,19 @@
+pub mod accounts;
+pub mod activity;
+pub mod admin;
+pub mod api_tokens;
+pub mod comments;
 pub mod documents;
+pub mod folders;
+pub mod passwords;
+pub mod presence;
+pub mod profiles;
 pub mod projects;
 pub mod resources;
 pub mod sessions;
 pub mod sharing;
+pub mod snapshots;
+pub mod suggestions;
+pub mod usage;
 pub mod users;
+pub mod verifications;
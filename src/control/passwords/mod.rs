@@ -0,0 +1,191 @@
+use axum::{Extension, Json, TypedHeader};
+use bcrypt::HashParts;
+use http::StatusCode;
+use tracing::{error, info, warn};
+
+use crate::{
+    auth::CurrentSession,
+    constants::{BCRYPT_COST, PASSWORD_RESET_URL},
+    domain::users::{Credentials, PasswordChange, PasswordReset, PasswordResetRequest, User},
+    extractors::headers::XUserId,
+    notify::{Notification, SharedNotifier},
+    repository::{
+        password_resets::{
+            PasswordResetInsertError, PasswordResetRedeemError, PasswordResetRepository,
+        },
+        users::{UserGetError, UserRepository, UserUpdateError},
+    },
+    validation::ValidatedJson,
+};
+
+/// Runs bcrypt off the async runtime, it is slow by design
+async fn run_bcrypt<T, F>(f: F) -> Result<T, StatusCode>
+where
+    T: Send + 'static,
+    F: FnOnce() -> bcrypt::BcryptResult<T> + Send + 'static,
+{
+    match tokio::task::spawn_blocking(f).await {
+        Ok(Ok(result)) => Ok(result),
+        Ok(Err(err)) => {
+            error!(%err);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+        Err(err) => {
+            error!(%err);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+pub async fn hash_password(password: String) -> Result<String, StatusCode> {
+    run_bcrypt(move || bcrypt::hash(password, *BCRYPT_COST)).await
+}
+
+pub async fn verify_password(password: String, password_hash: String) -> Result<bool, StatusCode> {
+    run_bcrypt(move || bcrypt::verify(password, &password_hash)).await
+}
+
+/// Whether the hash was made with another cost than the configured one
+pub fn needs_rehash(password_hash: &str) -> bool {
+    password_hash
+        .parse::<HashParts>()
+        .map_or(true, |parts| parts.get_cost() != *BCRYPT_COST)
+}
+
+/// Returns the user when the password matches, upgrading the stored hash if needed
+#[tracing::instrument(skip_all, fields(email = credentials.email))]
+pub async fn post_users_verify<U: UserRepository>(
+    Extension(user_repository): Extension<U>,
+    Json(credentials): Json<Credentials>,
+) -> Result<Json<User>, StatusCode> {
+    info!("Received credential verification attempt");
+
+    let user = match user_repository.get_by_email(&credentials.email).await {
+        Ok(user) => user,
+        Err(UserGetError::Missing) => return Err(StatusCode::UNAUTHORIZED),
+        Err(UserGetError::Unknown) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+    if !verify_password(credentials.password.clone(), user.password_hash.clone()).await? {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    if needs_rehash(&user.password_hash) {
+        // the login succeeds even when the upgrade does not, it is retried next time
+        match hash_password(credentials.password).await {
+            Ok(password_hash) => {
+                let result = user_repository
+                    .update_password_hash(user.id, &user.password_hash, &password_hash)
+                    .await;
+                match result {
+                    Ok(()) => info!(user_id = user.id, "Rehashed password"),
+                    Err(_) => warn!(user_id = user.id, "Could not rehash password"),
+                }
+            }
+            Err(_) => warn!(user_id = user.id, "Could not rehash password"),
+        }
+    }
+
+    Ok(Json(user))
+}
+
+/// Changes the password of the user, every other session of theirs is ended
+#[tracing::instrument(skip(user_repository, session_id, change))]
+pub async fn put_users_me_password<U: UserRepository>(
+    Extension(user_repository): Extension<U>,
+    TypedHeader(XUserId(user_id)): TypedHeader<XUserId>,
+    Extension(CurrentSession(session_id)): Extension<CurrentSession>,
+    ValidatedJson(change): ValidatedJson<PasswordChange>,
+) -> StatusCode {
+    info!("Received password change attempt");
+
+    let user = match user_repository.get_by_id(user_id).await {
+        Ok(user) => user,
+        Err(UserGetError::Missing) => return StatusCode::NOT_FOUND,
+        Err(UserGetError::Unknown) => return StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    match verify_password(change.old_password, user.password_hash).await {
+        Ok(true) => (),
+        Ok(false) => return StatusCode::FORBIDDEN,
+        Err(status) => return status,
+    }
+
+    let password_hash = match hash_password(change.new_password).await {
+        Ok(password_hash) => password_hash,
+        Err(status) => return status,
+    };
+    match user_repository
+        .update_password(user_id, &password_hash, session_id)
+        .await
+    {
+        Ok(()) => StatusCode::NO_CONTENT,
+        Err(UserUpdateError::Missing) => StatusCode::NOT_FOUND,
+        Err(UserUpdateError::Unknown) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+/// Sends a reset link when the address belongs to a user, the response is the same either way
+#[tracing::instrument(skip_all)]
+pub async fn post_users_password_reset<U: UserRepository, R: PasswordResetRepository>(
+    Extension(user_repository): Extension<U>,
+    Extension(password_reset_repository): Extension<R>,
+    Extension(notifier): Extension<SharedNotifier>,
+    Json(request): Json<PasswordResetRequest>,
+) -> StatusCode {
+    info!("Received password reset request");
+
+    let user = match user_repository.get_by_email(&request.email).await {
+        Ok(user) => user,
+        Err(UserGetError::Missing) => {
+            info!("No user to reset the password of");
+            return StatusCode::ACCEPTED;
+        }
+        Err(UserGetError::Unknown) => return StatusCode::INTERNAL_SERVER_ERROR,
+    };
+
+    let token = match password_reset_repository.insert(user.id).await {
+        Ok(token) => token,
+        Err(PasswordResetInsertError::Unknown) => return StatusCode::INTERNAL_SERVER_ERROR,
+    };
+
+    let notification = Notification {
+        to: user.email,
+        subject: String::from("Reset your password"),
+        body: format!(
+            "Follow this link to choose a new password:\n{}{}\n\nIf you did not ask for it, ignore this message.",
+            *PASSWORD_RESET_URL, token
+        ),
+    };
+    // a failed delivery is not reported, it would tell the address belongs to a user
+    if notifier.send(&notification).await.is_err() {
+        error!(user_id = user.id, "Could not send password reset");
+    }
+
+    StatusCode::ACCEPTED
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn post_users_password_reset_confirm<R: PasswordResetRepository>(
+    Extension(password_reset_repository): Extension<R>,
+    ValidatedJson(reset): ValidatedJson<PasswordReset>,
+) -> StatusCode {
+    info!("Received password reset attempt");
+
+    let password_hash = match hash_password(reset.new_password).await {
+        Ok(password_hash) => password_hash,
+        Err(status) => return status,
+    };
+    match password_reset_repository
+        .redeem(&reset.token, &password_hash)
+        .await
+    {
+        Ok(user_id) => {
+            info!(user_id, "Password reset");
+            StatusCode::NO_CONTENT
+        }
+        Err(PasswordResetRedeemError::Invalid) => StatusCode::NOT_FOUND,
+        Err(PasswordResetRedeemError::Unknown) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+#[cfg(test)]
+mod tests;
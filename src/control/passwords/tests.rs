@@ -0,0 +1,258 @@
+use std::sync::Arc;
+
+use axum::Extension;
+use http::StatusCode;
+use mockall::predicate;
+use validator::Validate;
+
+use crate::{
+    domain::users::User,
+    notify::MockNotifier,
+    repository::{password_resets::MockPasswordResetRepository, users::MockUserRepository},
+};
+
+use super::*;
+
+fn mock_user() -> User {
+    User {
+        id: 1,
+        email: String::from("john@email.com"),
+        display_name: None,
+        // cost is kept low so tests stay fast
+        password_hash: bcrypt::hash("old", 4).unwrap(),
+        verified_at: None,
+    }
+}
+
+fn mock_change(old_password: &str) -> PasswordChange {
+    PasswordChange {
+        old_password: String::from(old_password),
+        new_password: String::from("correct horse"),
+    }
+}
+
+#[test]
+fn password_change_checks_length() {
+    let mut change = mock_change("old");
+    assert!(change.validate().is_ok());
+
+    change.new_password = String::from("short");
+    assert!(change.validate().is_err());
+}
+
+#[test]
+fn password_reset_checks_length() {
+    let mut reset = PasswordReset {
+        token: String::from("token"),
+        new_password: String::from("correct horse"),
+    };
+    assert!(reset.validate().is_ok());
+
+    reset.new_password = String::new();
+    assert!(reset.validate().is_err());
+}
+
+#[tokio::test]
+async fn put_users_me_password_keeps_current_session() {
+    let mut user_repository = MockUserRepository::new();
+
+    user_repository
+        .expect_get_by_id()
+        .with(predicate::eq(1))
+        .times(1)
+        .returning(|_| Ok(mock_user()));
+    user_repository
+        .expect_update_password()
+        .withf(|id, password_hash, keep_session_id| {
+            *id == 1
+                && bcrypt::verify("correct horse", password_hash).unwrap()
+                && keep_session_id.as_deref() == Some("abc")
+        })
+        .times(1)
+        .returning(|_, _, _| Ok(()));
+
+    assert_eq!(
+        StatusCode::NO_CONTENT,
+        put_users_me_password(
+            Extension(user_repository),
+            TypedHeader(XUserId(1)),
+            Extension(CurrentSession(Some(String::from("abc")))),
+            ValidatedJson(mock_change("old")),
+        )
+        .await
+    );
+}
+
+#[tokio::test]
+async fn put_users_me_password_wrong_password_error() {
+    let mut user_repository = MockUserRepository::new();
+
+    user_repository
+        .expect_get_by_id()
+        .with(predicate::eq(1))
+        .times(1)
+        .returning(|_| Ok(mock_user()));
+    user_repository.expect_update_password().times(0);
+
+    assert_eq!(
+        StatusCode::FORBIDDEN,
+        put_users_me_password(
+            Extension(user_repository),
+            TypedHeader(XUserId(1)),
+            Extension(CurrentSession(None)),
+            ValidatedJson(mock_change("wrong")),
+        )
+        .await
+    );
+}
+
+#[tokio::test]
+async fn post_users_password_reset_sends_token() {
+    let mut user_repository = MockUserRepository::new();
+    let mut password_reset_repository = MockPasswordResetRepository::new();
+    let mut notifier = MockNotifier::new();
+
+    user_repository
+        .expect_get_by_email()
+        .with(predicate::eq("john@email.com"))
+        .times(1)
+        .returning(|_| Ok(mock_user()));
+    password_reset_repository
+        .expect_insert()
+        .with(predicate::eq(1))
+        .times(1)
+        .returning(|_| Ok(String::from("token")));
+    notifier
+        .expect_send()
+        .withf(|notification| {
+            notification.to == "john@email.com" && notification.body.contains("token")
+        })
+        .times(1)
+        .returning(|_| Ok(()));
+
+    assert_eq!(
+        StatusCode::ACCEPTED,
+        post_users_password_reset(
+            Extension(user_repository),
+            Extension(password_reset_repository),
+            Extension(Arc::new(notifier) as SharedNotifier),
+            Json(PasswordResetRequest {
+                email: String::from("john@email.com"),
+            }),
+        )
+        .await
+    );
+}
+
+#[tokio::test]
+async fn post_users_password_reset_unknown_email() {
+    let mut user_repository = MockUserRepository::new();
+    let mut password_reset_repository = MockPasswordResetRepository::new();
+    let mut notifier = MockNotifier::new();
+
+    user_repository
+        .expect_get_by_email()
+        .times(1)
+        .returning(|_| Err(UserGetError::Missing));
+    password_reset_repository.expect_insert().times(0);
+    notifier.expect_send().times(0);
+
+    assert_eq!(
+        StatusCode::ACCEPTED,
+        post_users_password_reset(
+            Extension(user_repository),
+            Extension(password_reset_repository),
+            Extension(Arc::new(notifier) as SharedNotifier),
+            Json(PasswordResetRequest {
+                email: String::from("nobody@email.com"),
+            }),
+        )
+        .await
+    );
+}
+
+#[tokio::test]
+async fn post_users_password_reset_confirm_invalid_token_error() {
+    let mut password_reset_repository = MockPasswordResetRepository::new();
+
+    password_reset_repository
+        .expect_redeem()
+        .withf(|token, password_hash| {
+            token == "used" && bcrypt::verify("correct horse", password_hash).unwrap()
+        })
+        .times(1)
+        .returning(|_, _| Err(PasswordResetRedeemError::Invalid));
+
+    assert_eq!(
+        StatusCode::NOT_FOUND,
+        post_users_password_reset_confirm(
+            Extension(password_reset_repository),
+            ValidatedJson(PasswordReset {
+                token: String::from("used"),
+                new_password: String::from("correct horse"),
+            }),
+        )
+        .await
+    );
+}
+
+fn mock_credentials(password: &str) -> Credentials {
+    Credentials {
+        email: String::from("john@email.com"),
+        password: String::from(password),
+    }
+}
+
+#[tokio::test]
+async fn post_users_verify_rehashes_outdated_cost() {
+    let mut user_repository = MockUserRepository::new();
+
+    user_repository
+        .expect_get_by_email()
+        .with(predicate::eq("john@email.com"))
+        .times(1)
+        .returning(|_| Ok(mock_user()));
+    user_repository
+        .expect_update_password_hash()
+        .withf(|id, old_password_hash, new_password_hash| {
+            *id == 1
+                && old_password_hash.starts_with("$2b$04$")
+                && !needs_rehash(new_password_hash)
+                && bcrypt::verify("old", new_password_hash).unwrap()
+        })
+        .times(1)
+        .returning(|_, _, _| Ok(()));
+
+    let res = post_users_verify(Extension(user_repository), Json(mock_credentials("old"))).await;
+
+    assert_eq!(1, res.unwrap().0.id);
+}
+
+#[tokio::test]
+async fn post_users_verify_wrong_password_error() {
+    let mut user_repository = MockUserRepository::new();
+
+    user_repository
+        .expect_get_by_email()
+        .times(1)
+        .returning(|_| Ok(mock_user()));
+    user_repository.expect_update_password_hash().times(0);
+
+    let res = post_users_verify(Extension(user_repository), Json(mock_credentials("wrong"))).await;
+
+    assert_eq!(StatusCode::UNAUTHORIZED, res.unwrap_err());
+}
+
+#[tokio::test]
+async fn post_users_verify_unknown_user_error() {
+    let mut user_repository = MockUserRepository::new();
+
+    user_repository
+        .expect_get_by_email()
+        .times(1)
+        .returning(|_| Err(UserGetError::Missing));
+
+    let res = post_users_verify(Extension(user_repository), Json(mock_credentials("old"))).await;
+
+    assert_eq!(StatusCode::UNAUTHORIZED, res.unwrap_err());
+}
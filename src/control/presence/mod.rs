@@ -0,0 +1,106 @@
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path,
+    },
+    response::Response,
+    Extension, Json, TypedHeader,
+};
+use http::StatusCode;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{info, warn};
+
+use crate::{
+    domain::{
+        presence::{Collaborator, PresenceMessage},
+        users::User,
+    },
+    extractors::headers::XUserId,
+    presence::PresenceHub,
+    repository::{
+        projects::{ProjectGetError, ProjectRepository},
+        users::{UserGetError, UserRepository},
+    },
+};
+
+#[tracing::instrument(skip(project_repository, hub))]
+pub async fn get_projects_presence<P: ProjectRepository>(
+    Extension(project_repository): Extension<P>,
+    Extension(hub): Extension<PresenceHub>,
+    TypedHeader(XUserId(user_id)): TypedHeader<XUserId>,
+    Path(project_id): Path<i32>,
+) -> Result<Json<Vec<Collaborator>>, StatusCode> {
+    info!("Received attempt to get project presence");
+
+    match project_repository.has_access(project_id, user_id).await {
+        Ok(true) => (),
+        Ok(false) => return Err(StatusCode::FORBIDDEN),
+        Err(ProjectGetError::Missing) => return Err(StatusCode::NOT_FOUND),
+        Err(ProjectGetError::Unknown) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+
+    Ok(Json(hub.snapshot(project_id)))
+}
+
+#[tracing::instrument(skip(project_repository, user_repository, hub, ws))]
+pub async fn get_projects_presence_socket<P: ProjectRepository, U: UserRepository>(
+    Extension(project_repository): Extension<P>,
+    Extension(user_repository): Extension<U>,
+    Extension(hub): Extension<PresenceHub>,
+    TypedHeader(XUserId(user_id)): TypedHeader<XUserId>,
+    Path(project_id): Path<i32>,
+    ws: WebSocketUpgrade,
+) -> Result<Response, StatusCode> {
+    info!("Received attempt to join project presence");
+
+    match project_repository.has_access(project_id, user_id).await {
+        Ok(true) => (),
+        Ok(false) => return Err(StatusCode::FORBIDDEN),
+        Err(ProjectGetError::Missing) => return Err(StatusCode::NOT_FOUND),
+        Err(ProjectGetError::Unknown) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+
+    let user = match user_repository.get_by_id(user_id).await {
+        Ok(user) => user,
+        Err(UserGetError::Missing) => return Err(StatusCode::NOT_FOUND),
+        Err(UserGetError::Unknown) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+
+    Ok(ws.on_upgrade(move |socket| handle_socket(socket, hub, project_id, user)))
+}
+
+#[tracing::instrument(skip(socket, hub, user), fields(user_id = user.id))]
+async fn handle_socket(mut socket: WebSocket, hub: PresenceHub, project_id: i32, user: User) {
+    let user_id = user.id;
+    let mut events = hub.join(project_id, &user);
+
+    loop {
+        tokio::select! {
+            message = socket.recv() => match message {
+                Some(Ok(Message::Text(text))) => {
+                    match serde_json::from_str::<PresenceMessage>(&text) {
+                        Ok(PresenceMessage::Cursor(cursor)) => {
+                            hub.update_cursor(project_id, user_id, cursor)
+                        }
+                        Ok(PresenceMessage::Heartbeat) => hub.touch(project_id, user_id),
+                        Err(err) => warn!(%err, "Malformed presence message"),
+                    }
+                }
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => hub.touch(project_id, user_id),
+            },
+            event = events.recv() => match event {
+                Ok(event) => {
+                    let text = serde_json::to_string(&event).unwrap();
+                    if socket.send(Message::Text(text)).await.is_err() {
+                        break;
+                    }
+                }
+                Err(RecvError::Lagged(skipped)) => warn!(skipped, "Presence events dropped"),
+                Err(RecvError::Closed) => break,
+            },
+        }
+    }
+
+    hub.leave(project_id, user_id);
+}
@@ -0,0 +1,120 @@
+use axum::{
+    body::{Bytes, StreamBody},
+    extract::Path,
+    headers::ContentType,
+    response::{IntoResponse, Response},
+    Extension, Json, TypedHeader,
+};
+use http::{header, StatusCode};
+use tracing::info;
+
+use crate::{
+    domain::users::{Profile, ProfileData},
+    extractors::headers::XUserId,
+    repository::profiles::{ProfileGetError, ProfileRepository, ProfileUpdateError},
+    validation::ValidatedJson,
+};
+
+/// Avatars are shown inline by browsers, so anything that could carry scripts is refused
+const AVATAR_CONTENT_TYPES: [&str; 4] = ["image/png", "image/jpeg", "image/gif", "image/webp"];
+
+#[tracing::instrument(skip(repository))]
+pub async fn get_users_me_profile<T: ProfileRepository>(
+    Extension(repository): Extension<T>,
+    TypedHeader(XUserId(user_id)): TypedHeader<XUserId>,
+) -> Result<Json<Profile>, StatusCode> {
+    info!("Received attempt to get profile");
+
+    match repository.get(user_id).await {
+        Ok(profile) => Ok(Json(profile)),
+        Err(ProfileGetError::Missing) => Err(StatusCode::NOT_FOUND),
+        Err(ProfileGetError::Unknown) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+#[tracing::instrument(skip(repository))]
+pub async fn put_users_me_profile<T: ProfileRepository>(
+    Extension(repository): Extension<T>,
+    TypedHeader(XUserId(user_id)): TypedHeader<XUserId>,
+    ValidatedJson(data): ValidatedJson<ProfileData>,
+) -> Result<Json<Profile>, StatusCode> {
+    info!("Received profile update attempt");
+
+    match repository.update(user_id, &data).await {
+        Ok(profile) => Ok(Json(profile)),
+        Err(ProfileUpdateError::Missing) => Err(StatusCode::NOT_FOUND),
+        Err(ProfileUpdateError::NoSpace) => Err(StatusCode::INSUFFICIENT_STORAGE),
+        Err(ProfileUpdateError::Unknown) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+#[tracing::instrument(skip(repository, body))]
+pub async fn put_users_me_avatar<T: ProfileRepository>(
+    Extension(repository): Extension<T>,
+    TypedHeader(XUserId(user_id)): TypedHeader<XUserId>,
+    TypedHeader(content_type): TypedHeader<ContentType>,
+    body: Bytes,
+) -> StatusCode {
+    info!("Received avatar upload attempt");
+
+    let content_type = content_type.to_string();
+    let Some(content_type) = AVATAR_CONTENT_TYPES
+        .into_iter()
+        .find(|allowed| content_type.split(';').next().map(str::trim) == Some(*allowed))
+    else {
+        return StatusCode::UNSUPPORTED_MEDIA_TYPE;
+    };
+    if body.is_empty() {
+        return StatusCode::BAD_REQUEST;
+    }
+
+    match repository
+        .put_avatar(user_id, content_type, body.as_ref())
+        .await
+    {
+        Ok(()) => StatusCode::NO_CONTENT,
+        Err(ProfileUpdateError::Missing) => StatusCode::NOT_FOUND,
+        Err(ProfileUpdateError::NoSpace) => StatusCode::INSUFFICIENT_STORAGE,
+        Err(ProfileUpdateError::Unknown) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+#[tracing::instrument(skip(repository))]
+pub async fn delete_users_me_avatar<T: ProfileRepository>(
+    Extension(repository): Extension<T>,
+    TypedHeader(XUserId(user_id)): TypedHeader<XUserId>,
+) -> StatusCode {
+    info!("Received avatar removal attempt");
+
+    match repository.delete_avatar(user_id).await {
+        Ok(()) => StatusCode::NO_CONTENT,
+        Err(ProfileUpdateError::Missing) => StatusCode::NOT_FOUND,
+        Err(ProfileUpdateError::NoSpace) | Err(ProfileUpdateError::Unknown) => {
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+#[tracing::instrument(skip(repository))]
+pub async fn get_users_avatar<T: ProfileRepository>(
+    Extension(repository): Extension<T>,
+    Path(user_email): Path<String>,
+) -> Result<Response, StatusCode> {
+    info!("Received attempt to get avatar");
+
+    match repository.get_avatar(&user_email).await {
+        Ok((content_type, stream)) => Ok((
+            [
+                (header::CONTENT_TYPE, content_type),
+                (header::X_CONTENT_TYPE_OPTIONS, String::from("nosniff")),
+            ],
+            StreamBody::new(stream),
+        )
+            .into_response()),
+        Err(ProfileGetError::Missing) => Err(StatusCode::NOT_FOUND),
+        Err(ProfileGetError::Unknown) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+#[cfg(test)]
+mod tests;
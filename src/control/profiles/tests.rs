@@ -0,0 +1,110 @@
+use axum::Extension;
+use http::StatusCode;
+use mockall::predicate;
+use validator::Validate;
+
+use crate::repository::profiles::MockProfileRepository;
+
+use super::*;
+
+fn mock_profile_data(orcid: &str) -> ProfileData {
+    ProfileData {
+        display_name: Some(String::from("Jane Doe")),
+        affiliation: Some(String::from("University of Somewhere")),
+        orcid: Some(String::from(orcid)),
+    }
+}
+
+#[test]
+fn profile_data_checks_orcid() {
+    assert!(mock_profile_data("0000-0002-1825-0097").validate().is_ok());
+    assert!(mock_profile_data("0000-0002-1694-233X").validate().is_ok());
+    // wrong check digit
+    assert!(mock_profile_data("0000-0002-1825-0098").validate().is_err());
+    assert!(mock_profile_data("0000000218250097").validate().is_err());
+}
+
+#[tokio::test]
+async fn put_users_me_profile_success() {
+    let mut repository = MockProfileRepository::new();
+
+    let data = mock_profile_data("0000-0002-1825-0097");
+    let profile = Profile {
+        display_name: data.display_name.clone(),
+        affiliation: data.affiliation.clone(),
+        orcid: data.orcid.clone(),
+        has_avatar: false,
+    };
+    let profile_cpy = profile.clone();
+
+    repository
+        .expect_update()
+        .with(predicate::eq(1), predicate::eq(data.clone()))
+        .times(1)
+        .return_once(|_, _| Ok(profile_cpy));
+
+    let res = put_users_me_profile(
+        Extension(repository),
+        TypedHeader(XUserId(1)),
+        ValidatedJson(data),
+    )
+    .await;
+
+    assert_eq!(profile, res.unwrap().0);
+}
+
+#[tokio::test]
+async fn put_users_me_avatar_refuses_other_content_types() {
+    let mut repository = MockProfileRepository::new();
+    repository.expect_put_avatar().never();
+
+    let res = put_users_me_avatar(
+        Extension(repository),
+        TypedHeader(XUserId(1)),
+        TypedHeader(ContentType::html()),
+        Bytes::from_static(b"<script></script>"),
+    )
+    .await;
+
+    assert_eq!(StatusCode::UNSUPPORTED_MEDIA_TYPE, res);
+}
+
+#[tokio::test]
+async fn put_users_me_avatar_success() {
+    let mut repository = MockProfileRepository::new();
+
+    repository
+        .expect_put_avatar()
+        .with(
+            predicate::eq(1),
+            predicate::eq("image/png"),
+            predicate::eq(b"png".as_slice()),
+        )
+        .times(1)
+        .return_once(|_, _, _| Ok(()));
+
+    let res = put_users_me_avatar(
+        Extension(repository),
+        TypedHeader(XUserId(1)),
+        TypedHeader(ContentType::png()),
+        Bytes::from_static(b"png"),
+    )
+    .await;
+
+    assert_eq!(StatusCode::NO_CONTENT, res);
+}
+
+#[tokio::test]
+async fn get_users_avatar_missing_error() {
+    let mut repository = MockProfileRepository::new();
+
+    repository
+        .expect_get_avatar()
+        .with(predicate::eq("jane@email.com"))
+        .times(1)
+        .return_once(|_| Err(ProfileGetError::Missing));
+
+    let res = get_users_avatar(Extension(repository), Path(String::from("jane@email.com"))).await;
+
+    assert_eq!(StatusCode::NOT_FOUND, res.unwrap_err());
+}
@@ -3,8 +3,13 @@ use http::StatusCode;
 use tracing::{error, info, warn};
 
 use crate::{
-    domain::projects::{Project, ProjectMetadata},
+    control::activity::record_event,
+    domain::{
+        events::ProjectEventType,
+        projects::{Project, ProjectMetadata, ProjectSettings},
+    },
     extractors::headers::XUserId,
+    repository::events::EventRepository,
     repository::projects::ProjectUpdateError,
     repository::projects::{ProjectGetError, ProjectInsertError, ProjectRepository},
 };
@@ -23,30 +28,53 @@ pub async fn get_projects<T: ProjectRepository>(
     }
 }
 
-#[tracing::instrument(skip(repository))]
-pub async fn post_projects<T: ProjectRepository>(
+#[tracing::instrument(skip(repository, event_repository))]
+pub async fn post_projects<T: ProjectRepository, E: EventRepository>(
     Extension(repository): Extension<T>,
+    Extension(event_repository): Extension<E>,
     TypedHeader(XUserId(user_id)): TypedHeader<XUserId>,
     Json(data): Json<ProjectMetadata>,
 ) -> Result<(StatusCode, Json<Project>), StatusCode> {
     info!("Received project creation attempt");
 
     match repository.insert(&data, user_id).await {
-        Ok(project) => Ok((StatusCode::CREATED, Json(project))),
+        Ok(project) => {
+            record_event(
+                &event_repository,
+                project.project_id,
+                user_id,
+                ProjectEventType::ProjectCreate,
+                None,
+            )
+            .await;
+            Ok((StatusCode::CREATED, Json(project)))
+        }
         Err(ProjectInsertError::Unknown) => Err(StatusCode::INTERNAL_SERVER_ERROR),
     }
 }
 
-#[tracing::instrument(skip(repository))]
-pub async fn put_projects_metadata<T: ProjectRepository>(
+#[tracing::instrument(skip(repository, event_repository))]
+pub async fn put_projects_metadata<T: ProjectRepository, E: EventRepository>(
     Extension(repository): Extension<T>,
+    Extension(event_repository): Extension<E>,
+    TypedHeader(XUserId(user_id)): TypedHeader<XUserId>,
     Path(project_id): Path<i32>,
     Json(data): Json<ProjectMetadata>,
 ) -> StatusCode {
     info!("Received project update attempt");
 
     match repository.update(project_id, &data).await {
-        Ok(()) => StatusCode::NO_CONTENT,
+        Ok(()) => {
+            record_event(
+                &event_repository,
+                project_id,
+                user_id,
+                ProjectEventType::MetadataUpdate,
+                None,
+            )
+            .await;
+            StatusCode::NO_CONTENT
+        }
         Err(ProjectUpdateError::Unknown) => StatusCode::INTERNAL_SERVER_ERROR,
     }
 }
@@ -70,3 +98,39 @@ pub async fn get_projects_metadata<T: ProjectRepository>(
         }
     }
 }
+
+#[tracing::instrument(skip(repository, event_repository))]
+pub async fn put_projects_settings<T: ProjectRepository, E: EventRepository>(
+    Extension(repository): Extension<T>,
+    Extension(event_repository): Extension<E>,
+    TypedHeader(XUserId(user_id)): TypedHeader<XUserId>,
+    Path(project_id): Path<i32>,
+    Json(data): Json<ProjectSettings>,
+) -> StatusCode {
+    info!("Received project settings update attempt");
+
+    match repository.get_meta(project_id).await {
+        Ok(project) if project.owner_id == user_id => (),
+        Ok(_) => {
+            warn!("Only the owner can change project settings");
+            return StatusCode::FORBIDDEN;
+        }
+        Err(ProjectGetError::Missing) => return StatusCode::NOT_FOUND,
+        Err(ProjectGetError::Unknown) => return StatusCode::INTERNAL_SERVER_ERROR,
+    }
+
+    match repository.update_settings(project_id, &data).await {
+        Ok(()) => {
+            record_event(
+                &event_repository,
+                project_id,
+                user_id,
+                ProjectEventType::SettingsUpdate,
+                None,
+            )
+            .await;
+            StatusCode::NO_CONTENT
+        }
+        Err(ProjectUpdateError::Unknown) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
@@ -1,19 +1,43 @@
-use axum::{body::Bytes, extract::Path, Extension, Json, TypedHeader};
-use http::StatusCode;
+use axum::{
+    body::{Bytes, StreamBody},
+    extract::Path,
+    response::{IntoResponse, Redirect, Response},
+    Extension, Json, TypedHeader,
+};
+use http::{header, StatusCode};
 use tracing::info;
 
 use crate::{
-    domain::resources::{Resource, ResourceMetadata},
+    control::{activity::record_event, usage::quota_exceeded_response},
+    domain::{
+        events::ProjectEventType,
+        resources::{Resource, ResourceCopyData, ResourceMetadata},
+    },
     extractors::headers::XUserId,
     repository::{
+        events::EventRepository,
         projects::{ProjectGetError, ProjectRepository},
         resources::{
-            ResourceGetError, ResourceInsertError, ResourceRepository, ResourceUpdateError,
+            ResourceCopyError, ResourceGetError, ResourceInsertError, ResourceRepository,
+            ResourceUpdateError,
         },
     },
     validation::ValidatedJson,
 };
 
+async fn check_access<P: ProjectRepository>(
+    project_repository: &P,
+    project_id: i32,
+    user_id: i32,
+) -> Result<(), Response> {
+    match project_repository.has_access(project_id, user_id).await {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(StatusCode::FORBIDDEN.into_response()),
+        Err(ProjectGetError::Missing) => Err(StatusCode::NOT_FOUND.into_response()),
+        Err(ProjectGetError::Unknown) => Err(StatusCode::INTERNAL_SERVER_ERROR.into_response()),
+    }
+}
+
 #[tracing::instrument(skip(project_repository, resource_repository))]
 pub async fn get_projects_resources<P: ProjectRepository, R: ResourceRepository>(
     Extension(project_repository): Extension<P>,
@@ -36,44 +60,179 @@ pub async fn get_projects_resources<P: ProjectRepository, R: ResourceRepository>
     }
 }
 
-#[tracing::instrument(skip(project_repository, resource_repository))]
-pub async fn post_projects_resources<P: ProjectRepository, R: ResourceRepository>(
+#[tracing::instrument(skip(project_repository, resource_repository, event_repository))]
+pub async fn post_projects_resources<
+    P: ProjectRepository,
+    R: ResourceRepository,
+    E: EventRepository,
+>(
     Extension(project_repository): Extension<P>,
     Extension(resource_repository): Extension<R>,
+    Extension(event_repository): Extension<E>,
     TypedHeader(XUserId(user_id)): TypedHeader<XUserId>,
     Path(project_id): Path<i32>,
     ValidatedJson(data): ValidatedJson<ResourceMetadata>,
-) -> Result<(StatusCode, Json<Resource>), StatusCode> {
+) -> Result<(StatusCode, Json<Resource>), Response> {
     info!("Received resource creation attempt");
 
-    match project_repository.get_meta(project_id).await {
-        Err(ProjectGetError::Missing) => return Err(StatusCode::NOT_FOUND),
-        Err(ProjectGetError::Unknown) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
-        Ok(_) => (),
-    }
+    check_access(&project_repository, project_id, user_id).await?;
 
     match resource_repository.insert(project_id, &data).await {
-        Ok(resource) => Ok((StatusCode::CREATED, Json(resource))),
-        Err(ResourceInsertError::Duplicate) => Err(StatusCode::CONFLICT),
-        Err(ResourceInsertError::Unknown) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+        Ok(resource) => {
+            record_event(
+                &event_repository,
+                project_id,
+                user_id,
+                ProjectEventType::ResourceCreate,
+                Some(resource.resource_id),
+            )
+            .await;
+            Ok((StatusCode::CREATED, Json(resource)))
+        }
+        Err(ResourceInsertError::Missing) => Err(StatusCode::NOT_FOUND.into_response()),
+        Err(ResourceInsertError::Duplicate) => Err(StatusCode::CONFLICT.into_response()),
+        Err(ResourceInsertError::QuotaExceeded(exceeded)) => Err(quota_exceeded_response(exceeded)),
+        Err(ResourceInsertError::Unknown) => Err(StatusCode::INTERNAL_SERVER_ERROR.into_response()),
     }
 }
 
-#[tracing::instrument(skip(repository, body))]
-pub async fn put_projects_resources<T: ResourceRepository>(
-    Extension(repository): Extension<T>,
+#[tracing::instrument(skip(project_repository, resource_repository, event_repository, body))]
+pub async fn put_projects_resources<
+    P: ProjectRepository,
+    R: ResourceRepository,
+    E: EventRepository,
+>(
+    Extension(project_repository): Extension<P>,
+    Extension(resource_repository): Extension<R>,
+    Extension(event_repository): Extension<E>,
+    TypedHeader(XUserId(user_id)): TypedHeader<XUserId>,
     Path((project_id, resource_id)): Path<(i32, i32)>,
     body: Bytes,
-) -> StatusCode {
+) -> Response {
     info!("Received resource content update attempt");
     // TODO: some mime type checking
 
-    match repository
+    if let Err(response) = check_access(&project_repository, project_id, user_id).await {
+        return response;
+    }
+
+    match resource_repository
         .update(project_id, resource_id, body.as_ref())
         .await
     {
-        Ok(_) => StatusCode::NO_CONTENT,
-        Err(ResourceUpdateError::Missing) => StatusCode::NOT_FOUND,
-        Err(ResourceUpdateError::Unknown) => StatusCode::INTERNAL_SERVER_ERROR,
+        Ok(_) => {
+            record_event(
+                &event_repository,
+                project_id,
+                user_id,
+                ProjectEventType::ResourceUpdate,
+                Some(resource_id),
+            )
+            .await;
+            StatusCode::NO_CONTENT.into_response()
+        }
+        Err(ResourceUpdateError::Missing) => StatusCode::NOT_FOUND.into_response(),
+        Err(ResourceUpdateError::NoSpace) => StatusCode::INSUFFICIENT_STORAGE.into_response(),
+        Err(ResourceUpdateError::QuotaExceeded(exceeded)) => quota_exceeded_response(exceeded),
+        Err(ResourceUpdateError::Unknown) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    }
+}
+
+#[tracing::instrument(skip(project_repository, resource_repository))]
+pub async fn get_projects_resources_content<P: ProjectRepository, R: ResourceRepository>(
+    Extension(project_repository): Extension<P>,
+    Extension(resource_repository): Extension<R>,
+    TypedHeader(XUserId(user_id)): TypedHeader<XUserId>,
+    Path((project_id, resource_id)): Path<(i32, i32)>,
+) -> Result<Response, StatusCode> {
+    info!("Received resource content retrieval attempt");
+
+    match project_repository.has_access(project_id, user_id).await {
+        Ok(true) => (),
+        Ok(false) => return Err(StatusCode::FORBIDDEN),
+        Err(ProjectGetError::Missing) => return Err(StatusCode::NOT_FOUND),
+        Err(ProjectGetError::Unknown) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+
+    let resource = match resource_repository.get_meta(project_id, resource_id).await {
+        Ok(resource) => resource,
+        Err(ResourceGetError::Missing) => return Err(StatusCode::NOT_FOUND),
+        Err(ResourceGetError::Unknown) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+
+    match resource_repository.download_url(&resource).await {
+        Ok(Some(url)) => return Ok(Redirect::temporary(&url).into_response()),
+        Ok(None) => (),
+        Err(ResourceGetError::Missing) => return Err(StatusCode::NOT_FOUND),
+        Err(ResourceGetError::Unknown) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+
+    let stream = match resource_repository.read_stream(&resource).await {
+        Ok(stream) => stream,
+        Err(ResourceGetError::Missing) => return Err(StatusCode::NOT_FOUND),
+        Err(ResourceGetError::Unknown) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+
+    Ok((
+        [(header::CONTENT_TYPE, "application/octet-stream")],
+        StreamBody::new(stream),
+    )
+        .into_response())
+}
+
+#[tracing::instrument(skip(project_repository, resource_repository, event_repository))]
+pub async fn post_projects_resources_copy<
+    P: ProjectRepository,
+    R: ResourceRepository,
+    E: EventRepository,
+>(
+    Extension(project_repository): Extension<P>,
+    Extension(resource_repository): Extension<R>,
+    Extension(event_repository): Extension<E>,
+    TypedHeader(XUserId(user_id)): TypedHeader<XUserId>,
+    Path((project_id, resource_id)): Path<(i32, i32)>,
+    ValidatedJson(data): ValidatedJson<ResourceCopyData>,
+) -> Result<(StatusCode, Json<Resource>), Response> {
+    info!("Received resource copy attempt");
+
+    let target_project_id = data.project_id.unwrap_or(project_id);
+    for checked_project_id in [project_id, target_project_id] {
+        check_access(&project_repository, checked_project_id, user_id).await?;
+    }
+
+    let resource = match resource_repository.get_meta(project_id, resource_id).await {
+        Ok(resource) => resource,
+        Err(ResourceGetError::Missing) => return Err(StatusCode::NOT_FOUND.into_response()),
+        Err(ResourceGetError::Unknown) => {
+            return Err(StatusCode::INTERNAL_SERVER_ERROR.into_response())
+        }
+    };
+
+    let target = ResourceMetadata {
+        name: data.name,
+        folder_id: data.folder_id,
+    };
+    match resource_repository
+        .copy(&resource, target_project_id, &target)
+        .await
+    {
+        Ok(copy) => {
+            record_event(
+                &event_repository,
+                target_project_id,
+                user_id,
+                ProjectEventType::ResourceCreate,
+                Some(copy.resource_id),
+            )
+            .await;
+            Ok((StatusCode::CREATED, Json(copy)))
+        }
+        Err(ResourceCopyError::Missing) => Err(StatusCode::NOT_FOUND.into_response()),
+        Err(ResourceCopyError::Duplicate) => Err(StatusCode::CONFLICT.into_response()),
+        Err(ResourceCopyError::QuotaExceeded(exceeded)) => Err(quota_exceeded_response(exceeded)),
+        Err(ResourceCopyError::Unknown) => Err(StatusCode::INTERNAL_SERVER_ERROR.into_response()),
     }
 }
+
+#[cfg(test)]
+mod tests;
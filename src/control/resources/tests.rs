@@ -0,0 +1,213 @@
+use axum::{body::HttpBody, Extension};
+use http::StatusCode;
+use mockall::predicate;
+
+use crate::{
+    domain::usage::{QuotaExceeded, QuotaScope},
+    repository::{
+        events::MockEventRepository, projects::MockProjectRepository,
+        resources::MockResourceRepository,
+    },
+};
+
+use super::*;
+
+fn mock_resource(project_id: i32, resource_id: i32) -> Resource {
+    Resource {
+        resource_id,
+        project_id,
+        name: String::from("logo.png"),
+        folder_id: None,
+        content_hash: Some(String::from("ab").repeat(32)),
+    }
+}
+
+fn mock_project_repository(access: bool) -> MockProjectRepository {
+    let mut project_repository = MockProjectRepository::new();
+
+    project_repository
+        .expect_has_access()
+        .with(predicate::eq(1), predicate::eq(5))
+        .returning(move |_, _| Ok(access));
+
+    project_repository
+}
+
+#[tokio::test]
+async fn post_projects_resources_copy_between_projects() {
+    let mut project_repository = MockProjectRepository::new();
+    let mut resource_repository = MockResourceRepository::new();
+    let mut event_repository = MockEventRepository::new();
+
+    project_repository
+        .expect_has_access()
+        .with(predicate::in_iter([1, 2]), predicate::eq(5))
+        .times(2)
+        .returning(|_, _| Ok(true));
+    resource_repository
+        .expect_get_meta()
+        .with(predicate::eq(1), predicate::eq(3))
+        .returning(|project_id, resource_id| Ok(mock_resource(project_id, resource_id)));
+    resource_repository
+        .expect_copy()
+        .withf(|resource, target_project_id, target| {
+            resource.resource_id == 3
+                && *target_project_id == 2
+                && target.name == "logo-copy.png"
+                && target.folder_id == Some(4)
+        })
+        .times(1)
+        .returning(|resource, _, _| {
+            Ok(Resource {
+                resource_id: 8,
+                project_id: 2,
+                name: String::from("logo-copy.png"),
+                folder_id: Some(4),
+                content_hash: resource.content_hash.clone(),
+            })
+        });
+    event_repository
+        .expect_insert()
+        .with(
+            predicate::eq(2),
+            predicate::eq(5),
+            predicate::eq(ProjectEventType::ResourceCreate),
+            predicate::eq(Some(8)),
+        )
+        .times(1)
+        .returning(|_, _, _, _| Ok(()));
+
+    let result = post_projects_resources_copy(
+        Extension(project_repository),
+        Extension(resource_repository),
+        Extension(event_repository),
+        TypedHeader(XUserId(5)),
+        Path((1, 3)),
+        ValidatedJson(ResourceCopyData {
+            project_id: Some(2),
+            name: String::from("logo-copy.png"),
+            folder_id: Some(4),
+        }),
+    )
+    .await;
+
+    match result {
+        Ok((status, Json(resource))) => {
+            assert_eq!(StatusCode::CREATED, status);
+            assert_eq!(2, resource.project_id);
+        }
+        Err(response) => panic!("unexpected status {}", response.status()),
+    }
+}
+
+#[tokio::test]
+async fn post_projects_resources_copy_target_forbidden_error() {
+    let mut project_repository = MockProjectRepository::new();
+    let mut resource_repository = MockResourceRepository::new();
+
+    project_repository
+        .expect_has_access()
+        .returning(|project_id, _| Ok(project_id == 1));
+    resource_repository.expect_copy().times(0);
+
+    let result = post_projects_resources_copy(
+        Extension(project_repository),
+        Extension(resource_repository),
+        Extension(MockEventRepository::new()),
+        TypedHeader(XUserId(5)),
+        Path((1, 3)),
+        ValidatedJson(ResourceCopyData {
+            project_id: Some(2),
+            name: String::from("logo.png"),
+            folder_id: None,
+        }),
+    )
+    .await;
+
+    assert_eq!(
+        Some(StatusCode::FORBIDDEN),
+        result.err().map(|response| response.status())
+    );
+}
+
+#[tokio::test]
+async fn put_projects_resources_quota_exceeded_error() {
+    let mut resource_repository = MockResourceRepository::new();
+    let mut event_repository = MockEventRepository::new();
+
+    resource_repository
+        .expect_update()
+        .with(predicate::eq(1), predicate::eq(3), predicate::always())
+        .times(1)
+        .returning(|_, _, content| {
+            Err(ResourceUpdateError::QuotaExceeded(QuotaExceeded {
+                scope: QuotaScope::Owner,
+                quota_bytes: 1024,
+                used_bytes: 1000,
+                requested_bytes: content.len() as i64,
+            }))
+        });
+    event_repository.expect_insert().times(0);
+
+    let response = put_projects_resources(
+        Extension(mock_project_repository(true)),
+        Extension(resource_repository),
+        Extension(event_repository),
+        TypedHeader(XUserId(5)),
+        Path((1, 3)),
+        Bytes::from(vec![0; 64]),
+    )
+    .await;
+
+    assert_eq!(StatusCode::PAYLOAD_TOO_LARGE, response.status());
+    let body = response.into_body().data().await.unwrap().unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!("owner", body["scope"]);
+    assert_eq!(64, body["requested_bytes"]);
+}
+
+#[tokio::test]
+async fn put_projects_resources_stranger_forbidden_error() {
+    let mut resource_repository = MockResourceRepository::new();
+    let mut event_repository = MockEventRepository::new();
+
+    resource_repository.expect_update().times(0);
+    event_repository.expect_insert().times(0);
+
+    let response = put_projects_resources(
+        Extension(mock_project_repository(false)),
+        Extension(resource_repository),
+        Extension(event_repository),
+        TypedHeader(XUserId(5)),
+        Path((1, 3)),
+        Bytes::from(vec![0; 64]),
+    )
+    .await;
+
+    assert_eq!(StatusCode::FORBIDDEN, response.status());
+}
+
+#[tokio::test]
+async fn post_projects_resources_stranger_forbidden_error() {
+    let mut resource_repository = MockResourceRepository::new();
+
+    resource_repository.expect_insert().times(0);
+
+    let result = post_projects_resources(
+        Extension(mock_project_repository(false)),
+        Extension(resource_repository),
+        Extension(MockEventRepository::new()),
+        TypedHeader(XUserId(5)),
+        Path(1),
+        ValidatedJson(ResourceMetadata {
+            name: String::from("logo.png"),
+            folder_id: None,
+        }),
+    )
+    .await;
+
+    assert_eq!(
+        Some(StatusCode::FORBIDDEN),
+        result.err().map(|response| response.status())
+    );
+}
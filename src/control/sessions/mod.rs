@@ -1,14 +1,16 @@
-use std::fmt::Debug;
+use std::{fmt::Debug, time::Duration};
 
 use axum::{
+    extract::Query,
     headers::{authorization::Bearer, Authorization},
     Extension, Json, TypedHeader,
 };
 use http::StatusCode;
-use tracing::info;
+use tracing::{error, info};
 
 use crate::{
-    domain::sessions::{Session, SessionData},
+    constants::SESSION_PURGE_INTERVAL_IN_SECONDS,
+    domain::sessions::{Session, SessionData, SessionInfo, SessionRevokeOptions},
     repository::sessions::{
         SessionDeleteError, SessionGetError, SessionInsertError, SessionRepository,
     },
@@ -54,5 +56,86 @@ pub async fn delete_sessions<T: SessionRepository + Debug>(
     }
 }
 
+async fn current_session<T: SessionRepository>(
+    repository: &T,
+    session_id: &str,
+) -> Result<Session, StatusCode> {
+    match repository.get(session_id).await {
+        Ok(session) => Ok(session),
+        Err(SessionGetError::Missing) => Err(StatusCode::NOT_FOUND),
+        Err(SessionGetError::Unknown) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn get_sessions_all<T: SessionRepository + Debug>(
+    Extension(repository): Extension<T>,
+    TypedHeader(session_id): TypedHeader<Authorization<Bearer>>,
+) -> Result<Json<Vec<SessionInfo>>, StatusCode> {
+    info!("Received attempt to list sessions");
+
+    let session = current_session(&repository, session_id.token()).await?;
+    match repository
+        .list_by_user(session.user.id, session_id.token())
+        .await
+    {
+        Ok(sessions) => Ok(Json(sessions)),
+        Err(SessionGetError::Missing | SessionGetError::Unknown) => {
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+#[tracing::instrument(skip_all, fields(keep_current = options.keep_current))]
+pub async fn delete_sessions_all<T: SessionRepository + Debug>(
+    Extension(repository): Extension<T>,
+    TypedHeader(session_id): TypedHeader<Authorization<Bearer>>,
+    Query(options): Query<SessionRevokeOptions>,
+) -> StatusCode {
+    info!("Received attempt to delete all sessions");
+
+    let session = match current_session(&repository, session_id.token()).await {
+        Ok(session) => session,
+        Err(status) => return status,
+    };
+    let keep_id = options
+        .keep_current
+        .then(|| String::from(session_id.token()));
+    match repository
+        .delete_all_for_user(session.user.id, keep_id)
+        .await
+    {
+        Ok(deleted) => {
+            info!(deleted, "Deleted sessions");
+            StatusCode::NO_CONTENT
+        }
+        Err(SessionDeleteError::Unknown) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+/// Removes expired sessions, they are already treated as missing before that
+#[tracing::instrument(skip_all)]
+pub async fn purge_expired_sessions<T: SessionRepository>(repository: &T) -> Option<u64> {
+    match repository.purge_expired().await {
+        Ok(purged) => {
+            info!(purged, "Purged expired sessions");
+            Some(purged)
+        }
+        Err(SessionDeleteError::Unknown) => {
+            error!("Could not purge expired sessions");
+            None
+        }
+    }
+}
+
+pub async fn run_session_purge<T: SessionRepository>(repository: T) {
+    let mut interval =
+        tokio::time::interval(Duration::from_secs(*SESSION_PURGE_INTERVAL_IN_SECONDS));
+    loop {
+        interval.tick().await;
+        purge_expired_sessions(&repository).await;
+    }
+}
+
 #[cfg(test)]
 mod tests;
@@ -5,7 +5,7 @@ use sqlx::types::chrono::Utc;
 
 use crate::{
     domain::{
-        sessions::{Session, SessionData},
+        sessions::{Session, SessionData, SessionInfo, SessionRevokeOptions},
         users::User,
     },
     repository::sessions::MockSessionRepository,
@@ -29,7 +29,9 @@ fn mock_user() -> User {
     User {
         id: 1,
         email: mock_email(),
+        display_name: None,
         password_hash: mock_password(),
+        verified_at: None,
     }
 }
 
@@ -42,6 +44,8 @@ fn mock_session_data() -> SessionData {
         id: mock_session_id(),
         user_id: 1,
         expires: Utc::now().timestamp(),
+        ip: Some(String::from("127.0.0.1")),
+        user_agent: None,
     }
 }
 
@@ -196,3 +200,106 @@ async fn delete_sessions_unknown_error() {
         delete_sessions(Extension(session_repository), mock_header()).await
     )
 }
+
+#[tokio::test]
+async fn purge_expired_sessions_normal() {
+    let mut session_repository = MockSessionRepository::new();
+
+    session_repository
+        .expect_purge_expired()
+        .times(1)
+        .returning(|| Ok(3));
+
+    assert_eq!(Some(3), purge_expired_sessions(&session_repository).await);
+}
+
+#[tokio::test]
+async fn purge_expired_sessions_error() {
+    let mut session_repository = MockSessionRepository::new();
+
+    session_repository
+        .expect_purge_expired()
+        .times(1)
+        .returning(|| Err(SessionDeleteError::Unknown));
+
+    assert_eq!(None, purge_expired_sessions(&session_repository).await);
+}
+
+#[tokio::test]
+async fn get_sessions_all_normal() {
+    let mut session_repository = MockSessionRepository::new();
+
+    let info = SessionInfo {
+        current: true,
+        created_at: Utc::now().naive_utc(),
+        last_seen: Utc::now().naive_utc(),
+        expires: Utc::now().timestamp(),
+        ip: None,
+        user_agent: Some(String::from("Firefox")),
+    };
+    let info_cpy = info.clone();
+
+    session_repository
+        .expect_get()
+        .with(predicate::eq(mock_session_id()))
+        .times(1)
+        .return_once(|_| Ok(mock_session()));
+    session_repository
+        .expect_list_by_user()
+        .withf(|user_id, current_id| *user_id == 1 && current_id == mock_session_id())
+        .times(1)
+        .return_once(|_, _| Ok(vec![info_cpy]));
+
+    let res = get_sessions_all(Extension(session_repository), mock_header()).await;
+
+    assert_eq!(vec![info], res.unwrap().0)
+}
+
+#[tokio::test]
+async fn delete_sessions_all_keep_current() {
+    let mut session_repository = MockSessionRepository::new();
+
+    session_repository
+        .expect_get()
+        .with(predicate::eq(mock_session_id()))
+        .times(1)
+        .return_once(|_| Ok(mock_session()));
+    session_repository
+        .expect_delete_all_for_user()
+        .withf(|user_id, keep_id| *user_id == 1 && *keep_id == Some(mock_session_id()))
+        .times(1)
+        .returning(|_, _| Ok(2));
+
+    assert_eq!(
+        StatusCode::NO_CONTENT,
+        delete_sessions_all(
+            Extension(session_repository),
+            mock_header(),
+            Query(SessionRevokeOptions { keep_current: true })
+        )
+        .await
+    )
+}
+
+#[tokio::test]
+async fn delete_sessions_all_missing_error() {
+    let mut session_repository = MockSessionRepository::new();
+
+    session_repository
+        .expect_get()
+        .times(1)
+        .returning(|_| Err(SessionGetError::Missing));
+    session_repository.expect_delete_all_for_user().times(0);
+
+    assert_eq!(
+        StatusCode::NOT_FOUND,
+        delete_sessions_all(
+            Extension(session_repository),
+            mock_header(),
+            Query(SessionRevokeOptions {
+                keep_current: false
+            })
+        )
+        .await
+    )
+}
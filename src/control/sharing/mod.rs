@@ -3,38 +3,77 @@ use http::StatusCode;
 use tracing::info;
 
 use crate::{
+    control::activity::record_event,
+    domain::events::ProjectEventType,
     extractors::headers::XUserId,
-    repository::sharing::{
-        ProjectSharingCreateError, ProjectSharingRepository, ProjectSharingUpdateError,
+    repository::{
+        events::EventRepository,
+        sharing::{ProjectSharingCreateError, ProjectSharingRepository, ProjectSharingUpdateError},
+        users::{UserGetError, UserRepository},
     },
 };
 
 //creates a collaboration entry in collaboration table, returns collaboration_id
 #[tracing::instrument(skip_all)]
-pub async fn post_projects_sharing<T: ProjectSharingRepository>(
+pub async fn post_projects_sharing<
+    T: ProjectSharingRepository,
+    E: EventRepository,
+    U: UserRepository,
+>(
     Extension(repository): Extension<T>,
+    Extension(event_repository): Extension<E>,
+    Extension(user_repository): Extension<U>,
     TypedHeader(XUserId(user_id)): TypedHeader<XUserId>,
     Path(token): Path<String>,
 ) -> StatusCode {
     info!("Receive sharing entry creation attempt");
 
+    // share tokens travel in links, an unconfirmed address could be anyone's
+    match user_repository.get_by_id(user_id).await {
+        Ok(user) if user.verified_at.is_some() => (),
+        Ok(_) => return StatusCode::FORBIDDEN,
+        Err(UserGetError::Missing) => return StatusCode::NOT_FOUND,
+        Err(UserGetError::Unknown) => return StatusCode::INTERNAL_SERVER_ERROR,
+    }
+
     match repository.update(token, user_id).await {
-        Ok(_) => StatusCode::CREATED,
+        Ok(project_id) => {
+            record_event(
+                &event_repository,
+                project_id,
+                user_id,
+                ProjectEventType::SharingRedeem,
+                None,
+            )
+            .await;
+            StatusCode::CREATED
+        }
         Err(ProjectSharingUpdateError::Unknown) => StatusCode::INTERNAL_SERVER_ERROR,
     }
 }
 
 //overwrites friend_id to a number
 #[tracing::instrument(skip_all)]
-pub async fn put_projects_sharing<T: ProjectSharingRepository>(
+pub async fn put_projects_sharing<T: ProjectSharingRepository, E: EventRepository>(
     Extension(repository): Extension<T>,
+    Extension(event_repository): Extension<E>,
     TypedHeader(XUserId(user_id)): TypedHeader<XUserId>,
     Path(project_id): Path<i32>,
 ) -> Result<String, StatusCode> {
     info!("Receive sharing entry creation attempt");
 
     match repository.create(project_id, user_id).await {
-        Ok(token) => Ok(token),
+        Ok(token) => {
+            record_event(
+                &event_repository,
+                project_id,
+                user_id,
+                ProjectEventType::SharingCreate,
+                None,
+            )
+            .await;
+            Ok(token)
+        }
         Err(ProjectSharingCreateError::Unknown) => Err(StatusCode::INTERNAL_SERVER_ERROR),
     }
 }
@@ -0,0 +1,221 @@
+use axum::{
+    extract::Path,
+    response::{IntoResponse, Response},
+    Extension, Json, TypedHeader,
+};
+use http::{header, StatusCode};
+use tracing::{info, warn};
+
+use crate::{
+    control::activity::record_event,
+    domain::{
+        events::ProjectEventType,
+        projects::{Project, ProjectMetadata},
+        snapshots::{Snapshot, SnapshotData},
+    },
+    extractors::headers::XUserId,
+    repository::{
+        events::EventRepository,
+        projects::{ProjectDeleteError, ProjectGetError, ProjectInsertError, ProjectRepository},
+        snapshots::{
+            SnapshotGetError, SnapshotInsertError, SnapshotRepository, SnapshotRestoreError,
+        },
+    },
+    validation::ValidatedJson,
+};
+
+async fn check_access<P: ProjectRepository>(
+    project_repository: &P,
+    project_id: i32,
+    user_id: i32,
+) -> Result<(), StatusCode> {
+    match project_repository.has_access(project_id, user_id).await {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(StatusCode::FORBIDDEN),
+        Err(ProjectGetError::Missing) => Err(StatusCode::NOT_FOUND),
+        Err(ProjectGetError::Unknown) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+#[tracing::instrument(skip(project_repository, snapshot_repository))]
+pub async fn get_projects_snapshots<P: ProjectRepository, S: SnapshotRepository>(
+    Extension(project_repository): Extension<P>,
+    Extension(snapshot_repository): Extension<S>,
+    TypedHeader(XUserId(user_id)): TypedHeader<XUserId>,
+    Path(project_id): Path<i32>,
+) -> Result<Json<Vec<Snapshot>>, StatusCode> {
+    info!("Received attempt to get project snapshots");
+
+    check_access(&project_repository, project_id, user_id).await?;
+
+    match snapshot_repository.get(project_id).await {
+        Ok(snapshots) => Ok(Json(snapshots)),
+        Err(SnapshotGetError::Missing) => Err(StatusCode::NOT_FOUND),
+        Err(SnapshotGetError::Unknown) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+#[tracing::instrument(skip(project_repository, snapshot_repository, event_repository))]
+pub async fn post_projects_snapshots<
+    P: ProjectRepository,
+    S: SnapshotRepository,
+    E: EventRepository,
+>(
+    Extension(project_repository): Extension<P>,
+    Extension(snapshot_repository): Extension<S>,
+    Extension(event_repository): Extension<E>,
+    TypedHeader(XUserId(user_id)): TypedHeader<XUserId>,
+    Path(project_id): Path<i32>,
+    ValidatedJson(data): ValidatedJson<SnapshotData>,
+) -> Result<(StatusCode, Json<Snapshot>), StatusCode> {
+    info!("Received snapshot creation attempt");
+
+    check_access(&project_repository, project_id, user_id).await?;
+
+    match snapshot_repository.insert(project_id, user_id, &data).await {
+        Ok(snapshot) => {
+            record_event(
+                &event_repository,
+                project_id,
+                user_id,
+                ProjectEventType::SnapshotCreate,
+                Some(snapshot.snapshot_id),
+            )
+            .await;
+            Ok((StatusCode::CREATED, Json(snapshot)))
+        }
+        Err(SnapshotInsertError::Unknown) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+#[tracing::instrument(skip(project_repository, snapshot_repository))]
+pub async fn get_projects_snapshots_archive<P: ProjectRepository, S: SnapshotRepository>(
+    Extension(project_repository): Extension<P>,
+    Extension(snapshot_repository): Extension<S>,
+    TypedHeader(XUserId(user_id)): TypedHeader<XUserId>,
+    Path((project_id, snapshot_id)): Path<(i32, i32)>,
+) -> Result<Response, StatusCode> {
+    info!("Received snapshot archive download attempt");
+
+    check_access(&project_repository, project_id, user_id).await?;
+
+    let archive = match snapshot_repository.archive(project_id, snapshot_id).await {
+        Ok(archive) => archive,
+        Err(SnapshotGetError::Missing) => return Err(StatusCode::NOT_FOUND),
+        Err(SnapshotGetError::Unknown) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+
+    let disposition = format!(
+        "attachment; filename=\"project-{}-snapshot-{}.tar\"",
+        project_id, snapshot_id
+    );
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, String::from("application/x-tar")),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        archive,
+    )
+        .into_response())
+}
+
+#[tracing::instrument(skip(project_repository, snapshot_repository, event_repository))]
+pub async fn post_projects_snapshots_restore<
+    P: ProjectRepository,
+    S: SnapshotRepository,
+    E: EventRepository,
+>(
+    Extension(project_repository): Extension<P>,
+    Extension(snapshot_repository): Extension<S>,
+    Extension(event_repository): Extension<E>,
+    TypedHeader(XUserId(user_id)): TypedHeader<XUserId>,
+    Path((project_id, snapshot_id)): Path<(i32, i32)>,
+) -> StatusCode {
+    info!("Received snapshot restore attempt");
+
+    match project_repository.get_meta(project_id).await {
+        Ok(project) if project.owner_id == user_id => (),
+        Ok(_) => {
+            warn!("Only the owner can restore a snapshot in place");
+            return StatusCode::FORBIDDEN;
+        }
+        Err(ProjectGetError::Missing) => return StatusCode::NOT_FOUND,
+        Err(ProjectGetError::Unknown) => return StatusCode::INTERNAL_SERVER_ERROR,
+    }
+
+    match snapshot_repository
+        .restore(project_id, snapshot_id, project_id)
+        .await
+    {
+        Ok(()) => {
+            record_event(
+                &event_repository,
+                project_id,
+                user_id,
+                ProjectEventType::SnapshotRestore,
+                Some(snapshot_id),
+            )
+            .await;
+            StatusCode::NO_CONTENT
+        }
+        Err(SnapshotRestoreError::Missing) => StatusCode::NOT_FOUND,
+        Err(SnapshotRestoreError::QuotaExceeded) => StatusCode::PAYLOAD_TOO_LARGE,
+        Err(SnapshotRestoreError::Unknown) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+#[tracing::instrument(skip(project_repository, snapshot_repository, event_repository))]
+pub async fn post_projects_snapshots_copy<
+    P: ProjectRepository,
+    S: SnapshotRepository,
+    E: EventRepository,
+>(
+    Extension(project_repository): Extension<P>,
+    Extension(snapshot_repository): Extension<S>,
+    Extension(event_repository): Extension<E>,
+    TypedHeader(XUserId(user_id)): TypedHeader<XUserId>,
+    Path((project_id, snapshot_id)): Path<(i32, i32)>,
+    Json(data): Json<ProjectMetadata>,
+) -> Result<(StatusCode, Json<Project>), StatusCode> {
+    info!("Received snapshot copy attempt");
+
+    check_access(&project_repository, project_id, user_id).await?;
+
+    let project = match project_repository.insert(&data, user_id).await {
+        Ok(project) => project,
+        Err(ProjectInsertError::Unknown) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+
+    let status = match snapshot_repository
+        .restore(project_id, snapshot_id, project.project_id)
+        .await
+    {
+        Ok(()) => {
+            record_event(
+                &event_repository,
+                project.project_id,
+                user_id,
+                ProjectEventType::ProjectCreate,
+                None,
+            )
+            .await;
+            return Ok((StatusCode::CREATED, Json(project)));
+        }
+        Err(SnapshotRestoreError::Missing) => StatusCode::NOT_FOUND,
+        Err(SnapshotRestoreError::QuotaExceeded) => StatusCode::PAYLOAD_TOO_LARGE,
+        Err(SnapshotRestoreError::Unknown) => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+
+    // the copy is not left behind half empty, counting against the quota
+    match project_repository.delete(project.project_id).await {
+        Ok(()) => Err(status),
+        Err(ProjectDeleteError::Unknown) => {
+            warn!(project.project_id, "Could not remove failed copy");
+            Err(status)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,208 @@
+use axum::Extension;
+use http::StatusCode;
+use mockall::predicate;
+use sqlx::types::chrono::Utc;
+
+use crate::{
+    domain::projects::Project,
+    repository::{
+        events::MockEventRepository, projects::MockProjectRepository,
+        snapshots::MockSnapshotRepository,
+    },
+};
+
+use super::*;
+
+fn mock_owner_id() -> i32 {
+    1
+}
+
+fn mock_project(project_id: i32) -> Project {
+    Project {
+        project_id,
+        main_document_id: project_id,
+        owner_id: mock_owner_id(),
+        owner_email: String::from("email"),
+        owner_display_name: None,
+        project_name: String::from("project"),
+        created_at: Utc::now().naive_utc(),
+        last_modified: Utc::now().naive_utc(),
+        track_changes: false,
+        used_bytes: 0,
+    }
+}
+
+#[tokio::test]
+async fn post_projects_snapshots_restore_forbidden_error() {
+    let mut project_repository = MockProjectRepository::new();
+    let mut snapshot_repository = MockSnapshotRepository::new();
+
+    project_repository
+        .expect_get_meta()
+        .with(predicate::eq(1))
+        .returning(|_| Ok(mock_project(1)));
+    snapshot_repository.expect_restore().times(0);
+
+    assert_eq!(
+        StatusCode::FORBIDDEN,
+        post_projects_snapshots_restore(
+            Extension(project_repository),
+            Extension(snapshot_repository),
+            Extension(MockEventRepository::new()),
+            TypedHeader(XUserId(2)),
+            Path((1, 1)),
+        )
+        .await
+    )
+}
+
+#[tokio::test]
+async fn post_projects_snapshots_copy_normal() {
+    let mut project_repository = MockProjectRepository::new();
+    let mut snapshot_repository = MockSnapshotRepository::new();
+    let mut event_repository = MockEventRepository::new();
+
+    project_repository
+        .expect_has_access()
+        .with(predicate::eq(1), predicate::eq(2))
+        .returning(|_, _| Ok(true));
+    project_repository
+        .expect_insert()
+        .withf(|data, owner_id| data.name == "camera-ready" && *owner_id == 2)
+        .times(1)
+        .returning(|_, _| Ok(mock_project(7)));
+    snapshot_repository
+        .expect_restore()
+        .with(predicate::eq(1), predicate::eq(3), predicate::eq(7))
+        .times(1)
+        .returning(|_, _, _| Ok(()));
+    event_repository
+        .expect_insert()
+        .with(
+            predicate::eq(7),
+            predicate::eq(2),
+            predicate::eq(ProjectEventType::ProjectCreate),
+            predicate::eq(None),
+        )
+        .times(1)
+        .returning(|_, _, _, _| Ok(()));
+
+    let result = post_projects_snapshots_copy(
+        Extension(project_repository),
+        Extension(snapshot_repository),
+        Extension(event_repository),
+        TypedHeader(XUserId(2)),
+        Path((1, 3)),
+        Json(ProjectMetadata {
+            name: String::from("camera-ready"),
+        }),
+    )
+    .await;
+
+    match result {
+        Ok((status, Json(project))) => {
+            assert_eq!(StatusCode::CREATED, status);
+            assert_eq!(7, project.project_id);
+        }
+        Err(status) => panic!("unexpected status {}", status),
+    }
+}
+
+#[tokio::test]
+async fn post_projects_snapshots_copy_quota_exceeded_error() {
+    let mut project_repository = MockProjectRepository::new();
+    let mut snapshot_repository = MockSnapshotRepository::new();
+    let mut event_repository = MockEventRepository::new();
+
+    project_repository
+        .expect_has_access()
+        .returning(|_, _| Ok(true));
+    project_repository
+        .expect_insert()
+        .times(1)
+        .returning(|_, _| Ok(mock_project(7)));
+    snapshot_repository
+        .expect_restore()
+        .times(1)
+        .returning(|_, _, _| Err(SnapshotRestoreError::QuotaExceeded));
+    project_repository
+        .expect_delete()
+        .with(predicate::eq(7))
+        .times(1)
+        .returning(|_| Ok(()));
+    event_repository.expect_insert().times(0);
+
+    let result = post_projects_snapshots_copy(
+        Extension(project_repository),
+        Extension(snapshot_repository),
+        Extension(event_repository),
+        TypedHeader(XUserId(2)),
+        Path((1, 3)),
+        Json(ProjectMetadata {
+            name: String::from("camera-ready"),
+        }),
+    )
+    .await;
+
+    assert_eq!(Some(StatusCode::PAYLOAD_TOO_LARGE), result.err());
+}
+
+#[tokio::test]
+async fn post_projects_snapshots_copy_missing_error() {
+    let mut project_repository = MockProjectRepository::new();
+    let mut snapshot_repository = MockSnapshotRepository::new();
+
+    project_repository
+        .expect_has_access()
+        .returning(|_, _| Ok(true));
+    project_repository
+        .expect_insert()
+        .returning(|_, _| Ok(mock_project(7)));
+    snapshot_repository
+        .expect_restore()
+        .returning(|_, _, _| Err(SnapshotRestoreError::Missing));
+    project_repository
+        .expect_delete()
+        .with(predicate::eq(7))
+        .times(1)
+        .returning(|_| Err(ProjectDeleteError::Unknown));
+
+    let result = post_projects_snapshots_copy(
+        Extension(project_repository),
+        Extension(snapshot_repository),
+        Extension(MockEventRepository::new()),
+        TypedHeader(XUserId(2)),
+        Path((1, 3)),
+        Json(ProjectMetadata {
+            name: String::from("camera-ready"),
+        }),
+    )
+    .await;
+
+    assert_eq!(Some(StatusCode::NOT_FOUND), result.err());
+}
+
+#[tokio::test]
+async fn get_projects_snapshots_archive_missing_error() {
+    let mut project_repository = MockProjectRepository::new();
+    let mut snapshot_repository = MockSnapshotRepository::new();
+
+    project_repository
+        .expect_has_access()
+        .returning(|_, _| Ok(true));
+    snapshot_repository
+        .expect_archive()
+        .with(predicate::eq(1), predicate::eq(9))
+        .times(1)
+        .returning(|_, _| Err(SnapshotGetError::Missing));
+
+    let result = get_projects_snapshots_archive(
+        Extension(project_repository),
+        Extension(snapshot_repository),
+        TypedHeader(XUserId(1)),
+        Path((1, 9)),
+    )
+    .await;
+
+    assert_eq!(Some(StatusCode::NOT_FOUND), result.err());
+}
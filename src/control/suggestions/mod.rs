@@ -0,0 +1,378 @@
+use axum::{
+    extract::Path,
+    response::{IntoResponse, Response},
+    Extension, Json, TypedHeader,
+};
+use http::StatusCode;
+use tracing::{info, warn};
+
+use crate::{
+    control::{activity::record_event, documents::propagate_edit, usage::quota_exceeded_response},
+    domain::{
+        events::ProjectEventType,
+        suggestions::{
+            Suggestion, SuggestionKind, SuggestionReview, SuggestionSelection, SuggestionStatus,
+        },
+    },
+    extractors::headers::XUserId,
+    repository::{
+        comments::CommentRepository,
+        documents::{DocumentGetError, DocumentRepository, DocumentUpdateError},
+        events::EventRepository,
+        projects::{ProjectGetError, ProjectRepository},
+        suggestions::{SuggestionGetError, SuggestionRepository, SuggestionUpdateError},
+    },
+};
+
+async fn check_owner<P: ProjectRepository>(
+    project_repository: &P,
+    project_id: i32,
+    user_id: i32,
+) -> Result<(), StatusCode> {
+    match project_repository.get_meta(project_id).await {
+        Ok(project) if project.owner_id == user_id => Ok(()),
+        Ok(_) => {
+            warn!("Only the owner can review suggestions");
+            Err(StatusCode::FORBIDDEN)
+        }
+        Err(ProjectGetError::Missing) => Err(StatusCode::NOT_FOUND),
+        Err(ProjectGetError::Unknown) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+/// Applies a pending suggestion to its document, retiring it as outdated if the text moved on
+async fn accept<D: DocumentRepository, C: CommentRepository, S: SuggestionRepository>(
+    document_repository: &D,
+    comment_repository: &C,
+    suggestion_repository: &S,
+    project_id: i32,
+    suggestion_id: i32,
+) -> Result<(), Response> {
+    let suggestion = match suggestion_repository
+        .get_one(project_id, suggestion_id)
+        .await
+    {
+        Ok(suggestion) if suggestion.status == SuggestionStatus::Pending => suggestion,
+        Ok(_) => return Err(StatusCode::CONFLICT.into_response()),
+        Err(SuggestionGetError::Missing) => return Err(StatusCode::NOT_FOUND.into_response()),
+        Err(SuggestionGetError::Unknown) => {
+            return Err(StatusCode::INTERNAL_SERVER_ERROR.into_response())
+        }
+    };
+
+    let document = match document_repository
+        .get_meta(project_id, suggestion.document_id)
+        .await
+    {
+        Ok(document) => document,
+        Err(DocumentGetError::Missing) => return Err(StatusCode::NOT_FOUND.into_response()),
+        Err(DocumentGetError::Unknown) => {
+            return Err(StatusCode::INTERNAL_SERVER_ERROR.into_response())
+        }
+    };
+
+    let text = match document_repository.read_file(&document).await {
+        Ok(text) => text,
+        Err(DocumentGetError::Missing) => return Err(StatusCode::NOT_FOUND.into_response()),
+        Err(DocumentGetError::Unknown) => {
+            return Err(StatusCode::INTERNAL_SERVER_ERROR.into_response())
+        }
+    };
+
+    let edit = suggestion.as_edit();
+    let matches = suggestion.kind == SuggestionKind::Insertion
+        || edit.removed_text(&text) == suggestion.content;
+    let content = match edit.apply(&text) {
+        Some(content) if matches => content,
+        _ => {
+            warn!("Suggestion no longer matches the document");
+            let _ = suggestion_repository
+                .update_status(project_id, suggestion_id, SuggestionStatus::Outdated)
+                .await;
+            return Err(StatusCode::CONFLICT.into_response());
+        }
+    };
+
+    match document_repository.write_file(&document, &content).await {
+        Ok(()) => (),
+        Err(DocumentUpdateError::NoSpace) => {
+            return Err(StatusCode::INSUFFICIENT_STORAGE.into_response())
+        }
+        Err(DocumentUpdateError::QuotaExceeded(exceeded)) => {
+            return Err(quota_exceeded_response(exceeded))
+        }
+        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR.into_response()),
+    }
+
+    match suggestion_repository
+        .update_status(project_id, suggestion_id, SuggestionStatus::Accepted)
+        .await
+    {
+        Ok(()) => (),
+        Err(SuggestionUpdateError::Missing) => return Err(StatusCode::CONFLICT.into_response()),
+        Err(SuggestionUpdateError::Unknown) => {
+            return Err(StatusCode::INTERNAL_SERVER_ERROR.into_response())
+        }
+    }
+
+    propagate_edit(
+        comment_repository,
+        suggestion_repository,
+        document.document_id,
+        &edit,
+    )
+    .await;
+
+    Ok(())
+}
+
+async fn select_pending<S: SuggestionRepository>(
+    suggestion_repository: &S,
+    project_id: i32,
+    selection: &SuggestionSelection,
+) -> Result<Vec<Suggestion>, StatusCode> {
+    let pending = match suggestion_repository.get(project_id).await {
+        Ok(pending) => pending,
+        Err(SuggestionGetError::Missing) => return Err(StatusCode::NOT_FOUND),
+        Err(SuggestionGetError::Unknown) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+
+    Ok(match &selection.suggestion_ids {
+        Some(ids) => pending
+            .into_iter()
+            .filter(|suggestion| ids.contains(&suggestion.suggestion_id))
+            .collect(),
+        None => pending,
+    })
+}
+
+#[tracing::instrument(skip(project_repository, suggestion_repository))]
+pub async fn get_projects_suggestions<P: ProjectRepository, S: SuggestionRepository>(
+    Extension(project_repository): Extension<P>,
+    Extension(suggestion_repository): Extension<S>,
+    TypedHeader(XUserId(user_id)): TypedHeader<XUserId>,
+    Path(project_id): Path<i32>,
+) -> Result<Json<Vec<Suggestion>>, StatusCode> {
+    info!("Received attempt to get pending suggestions");
+
+    match project_repository.has_access(project_id, user_id).await {
+        Ok(true) => (),
+        Ok(false) => return Err(StatusCode::FORBIDDEN),
+        Err(ProjectGetError::Missing) => return Err(StatusCode::NOT_FOUND),
+        Err(ProjectGetError::Unknown) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+
+    match suggestion_repository.get(project_id).await {
+        Ok(suggestions) => Ok(Json(suggestions)),
+        Err(SuggestionGetError::Missing) => Err(StatusCode::NOT_FOUND),
+        Err(SuggestionGetError::Unknown) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+#[tracing::instrument(skip(
+    project_repository,
+    document_repository,
+    comment_repository,
+    suggestion_repository,
+    event_repository
+))]
+pub async fn post_projects_suggestions_accept<
+    P: ProjectRepository,
+    D: DocumentRepository,
+    C: CommentRepository,
+    S: SuggestionRepository,
+    E: EventRepository,
+>(
+    Extension(project_repository): Extension<P>,
+    Extension(document_repository): Extension<D>,
+    Extension(comment_repository): Extension<C>,
+    Extension(suggestion_repository): Extension<S>,
+    Extension(event_repository): Extension<E>,
+    TypedHeader(XUserId(user_id)): TypedHeader<XUserId>,
+    Path((project_id, suggestion_id)): Path<(i32, i32)>,
+) -> Response {
+    info!("Received suggestion acceptance attempt");
+
+    if let Err(status) = check_owner(&project_repository, project_id, user_id).await {
+        return status.into_response();
+    }
+
+    match accept(
+        &document_repository,
+        &comment_repository,
+        &suggestion_repository,
+        project_id,
+        suggestion_id,
+    )
+    .await
+    {
+        Ok(()) => {
+            record_event(
+                &event_repository,
+                project_id,
+                user_id,
+                ProjectEventType::SuggestionAccept,
+                Some(suggestion_id),
+            )
+            .await;
+            StatusCode::NO_CONTENT.into_response()
+        }
+        Err(response) => response,
+    }
+}
+
+#[tracing::instrument(skip(project_repository, suggestion_repository, event_repository))]
+pub async fn post_projects_suggestions_reject<
+    P: ProjectRepository,
+    S: SuggestionRepository,
+    E: EventRepository,
+>(
+    Extension(project_repository): Extension<P>,
+    Extension(suggestion_repository): Extension<S>,
+    Extension(event_repository): Extension<E>,
+    TypedHeader(XUserId(user_id)): TypedHeader<XUserId>,
+    Path((project_id, suggestion_id)): Path<(i32, i32)>,
+) -> StatusCode {
+    info!("Received suggestion rejection attempt");
+
+    if let Err(status) = check_owner(&project_repository, project_id, user_id).await {
+        return status;
+    }
+
+    match suggestion_repository
+        .update_status(project_id, suggestion_id, SuggestionStatus::Rejected)
+        .await
+    {
+        Ok(()) => {
+            record_event(
+                &event_repository,
+                project_id,
+                user_id,
+                ProjectEventType::SuggestionReject,
+                Some(suggestion_id),
+            )
+            .await;
+            StatusCode::NO_CONTENT
+        }
+        Err(SuggestionUpdateError::Missing) => StatusCode::NOT_FOUND,
+        Err(SuggestionUpdateError::Unknown) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+#[tracing::instrument(skip(
+    project_repository,
+    document_repository,
+    comment_repository,
+    suggestion_repository,
+    event_repository
+))]
+pub async fn post_projects_suggestions_accept_all<
+    P: ProjectRepository,
+    D: DocumentRepository,
+    C: CommentRepository,
+    S: SuggestionRepository,
+    E: EventRepository,
+>(
+    Extension(project_repository): Extension<P>,
+    Extension(document_repository): Extension<D>,
+    Extension(comment_repository): Extension<C>,
+    Extension(suggestion_repository): Extension<S>,
+    Extension(event_repository): Extension<E>,
+    TypedHeader(XUserId(user_id)): TypedHeader<XUserId>,
+    Path(project_id): Path<i32>,
+    Json(selection): Json<SuggestionSelection>,
+) -> Result<Json<SuggestionReview>, Response> {
+    info!("Received bulk suggestion acceptance attempt");
+
+    check_owner(&project_repository, project_id, user_id)
+        .await
+        .map_err(IntoResponse::into_response)?;
+
+    let selected = select_pending(&suggestion_repository, project_id, &selection)
+        .await
+        .map_err(IntoResponse::into_response)?;
+
+    // each acceptance rebases the remaining suggestions, so they are applied one at a time
+    let mut review = SuggestionReview::default();
+    for suggestion in selected {
+        match accept(
+            &document_repository,
+            &comment_repository,
+            &suggestion_repository,
+            project_id,
+            suggestion.suggestion_id,
+        )
+        .await
+        {
+            Ok(()) => {
+                record_event(
+                    &event_repository,
+                    project_id,
+                    user_id,
+                    ProjectEventType::SuggestionAccept,
+                    Some(suggestion.suggestion_id),
+                )
+                .await;
+                review.accepted.push(suggestion.suggestion_id)
+            }
+            Err(response) if response.status() == StatusCode::CONFLICT => {
+                review.outdated.push(suggestion.suggestion_id)
+            }
+            Err(response) => return Err(response),
+        }
+    }
+
+    Ok(Json(review))
+}
+
+#[tracing::instrument(skip(project_repository, suggestion_repository, event_repository))]
+pub async fn post_projects_suggestions_reject_all<
+    P: ProjectRepository,
+    S: SuggestionRepository,
+    E: EventRepository,
+>(
+    Extension(project_repository): Extension<P>,
+    Extension(suggestion_repository): Extension<S>,
+    Extension(event_repository): Extension<E>,
+    TypedHeader(XUserId(user_id)): TypedHeader<XUserId>,
+    Path(project_id): Path<i32>,
+    Json(selection): Json<SuggestionSelection>,
+) -> Result<Json<SuggestionReview>, StatusCode> {
+    info!("Received bulk suggestion rejection attempt");
+
+    check_owner(&project_repository, project_id, user_id).await?;
+
+    let selected = select_pending(&suggestion_repository, project_id, &selection).await?;
+
+    let mut review = SuggestionReview::default();
+    for suggestion in selected {
+        match suggestion_repository
+            .update_status(
+                project_id,
+                suggestion.suggestion_id,
+                SuggestionStatus::Rejected,
+            )
+            .await
+        {
+            Ok(()) => {
+                record_event(
+                    &event_repository,
+                    project_id,
+                    user_id,
+                    ProjectEventType::SuggestionReject,
+                    Some(suggestion.suggestion_id),
+                )
+                .await;
+                review.rejected.push(suggestion.suggestion_id)
+            }
+            Err(SuggestionUpdateError::Missing) => (),
+            Err(SuggestionUpdateError::Unknown) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+        }
+    }
+
+    Ok(Json(review))
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,245 @@
+use axum::{body::HttpBody, Extension};
+use http::StatusCode;
+use mockall::predicate;
+use sqlx::types::chrono::Utc;
+
+use crate::{
+    domain::{
+        documents::Document,
+        projects::Project,
+        suggestions::{Suggestion, SuggestionKind, SuggestionStatus},
+        usage::{QuotaExceeded, QuotaScope},
+    },
+    repository::{
+        comments::MockCommentRepository, documents::MockDocumentRepository,
+        events::MockEventRepository, projects::MockProjectRepository,
+        suggestions::MockSuggestionRepository,
+    },
+};
+
+use super::*;
+
+fn mock_owner_id() -> i32 {
+    1
+}
+
+fn mock_project() -> Project {
+    Project {
+        project_id: 1,
+        main_document_id: 1,
+        owner_id: mock_owner_id(),
+        owner_email: String::from("email"),
+        owner_display_name: None,
+        project_name: String::from("project"),
+        created_at: Utc::now().naive_utc(),
+        last_modified: Utc::now().naive_utc(),
+        track_changes: true,
+        used_bytes: 0,
+    }
+}
+
+fn mock_document() -> Document {
+    Document {
+        document_id: 1,
+        project_id: 1,
+        name: String::from("main.tex"),
+        folder_id: None,
+    }
+}
+
+fn mock_suggestion(kind: SuggestionKind, content: &str) -> Suggestion {
+    Suggestion {
+        suggestion_id: 1,
+        project_id: 1,
+        document_id: 1,
+        author_id: 2,
+        author_email: String::from("advisor"),
+        author_display_name: None,
+        kind,
+        position: 6,
+        content: String::from(content),
+        status: SuggestionStatus::Pending,
+        created_at: Utc::now().naive_utc(),
+    }
+}
+
+fn mock_project_repository() -> MockProjectRepository {
+    let mut project_repository = MockProjectRepository::new();
+
+    project_repository
+        .expect_get_meta()
+        .with(predicate::eq(1))
+        .returning(|_| Ok(mock_project()));
+
+    project_repository
+}
+
+fn mock_document_repository(text: &'static str) -> MockDocumentRepository {
+    let mut document_repository = MockDocumentRepository::new();
+
+    document_repository
+        .expect_get_meta()
+        .with(predicate::eq(1), predicate::eq(1))
+        .returning(|_, _| Ok(mock_document()));
+    document_repository
+        .expect_read_file()
+        .returning(move |_| Ok(String::from(text)));
+
+    document_repository
+}
+
+fn mock_event_repository(times: usize) -> MockEventRepository {
+    let mut event_repository = MockEventRepository::new();
+
+    event_repository
+        .expect_insert()
+        .times(times)
+        .returning(|_, _, _, _| Ok(()));
+
+    event_repository
+}
+
+#[tokio::test]
+async fn post_projects_suggestions_accept_normal() {
+    let mut document_repository = mock_document_repository("Hello world");
+    let mut comment_repository = MockCommentRepository::new();
+    let mut suggestion_repository = MockSuggestionRepository::new();
+
+    suggestion_repository
+        .expect_get_one()
+        .with(predicate::eq(1), predicate::eq(1))
+        .times(1)
+        .returning(|_, _| Ok(mock_suggestion(SuggestionKind::Insertion, "big ")));
+    document_repository
+        .expect_write_file()
+        .withf(|_, content| content == "Hello big world")
+        .times(1)
+        .returning(|_, _| Ok(()));
+    suggestion_repository
+        .expect_update_status()
+        .with(
+            predicate::eq(1),
+            predicate::eq(1),
+            predicate::eq(SuggestionStatus::Accepted),
+        )
+        .times(1)
+        .returning(|_, _, _| Ok(()));
+    comment_repository
+        .expect_reanchor()
+        .times(1)
+        .returning(|_, _| Ok(()));
+    suggestion_repository
+        .expect_rebase()
+        .times(1)
+        .returning(|_, _| Ok(()));
+
+    assert_eq!(
+        StatusCode::NO_CONTENT,
+        post_projects_suggestions_accept(
+            Extension(mock_project_repository()),
+            Extension(document_repository),
+            Extension(comment_repository),
+            Extension(suggestion_repository),
+            Extension(mock_event_repository(1)),
+            TypedHeader(XUserId(mock_owner_id())),
+            Path((1, 1)),
+        )
+        .await
+        .status()
+    )
+}
+
+#[tokio::test]
+async fn post_projects_suggestions_accept_quota_exceeded_error() {
+    let mut document_repository = mock_document_repository("Hello world");
+    let mut suggestion_repository = MockSuggestionRepository::new();
+
+    suggestion_repository
+        .expect_get_one()
+        .times(1)
+        .returning(|_, _| Ok(mock_suggestion(SuggestionKind::Insertion, "big ")));
+    document_repository
+        .expect_write_file()
+        .times(1)
+        .returning(|_, _| {
+            Err(DocumentUpdateError::QuotaExceeded(QuotaExceeded {
+                scope: QuotaScope::Project,
+                quota_bytes: 16,
+                used_bytes: 11,
+                requested_bytes: 4,
+            }))
+        });
+    suggestion_repository.expect_update_status().times(0);
+
+    let response = post_projects_suggestions_accept(
+        Extension(mock_project_repository()),
+        Extension(document_repository),
+        Extension(MockCommentRepository::new()),
+        Extension(suggestion_repository),
+        Extension(mock_event_repository(0)),
+        TypedHeader(XUserId(mock_owner_id())),
+        Path((1, 1)),
+    )
+    .await;
+
+    assert_eq!(StatusCode::PAYLOAD_TOO_LARGE, response.status());
+    let body = response.into_body().data().await.unwrap().unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!("project", body["scope"]);
+    assert_eq!(4, body["requested_bytes"]);
+}
+
+#[tokio::test]
+async fn post_projects_suggestions_accept_outdated_error() {
+    let mut document_repository = mock_document_repository("Hello small world");
+    let mut suggestion_repository = MockSuggestionRepository::new();
+
+    suggestion_repository
+        .expect_get_one()
+        .times(1)
+        .returning(|_, _| Ok(mock_suggestion(SuggestionKind::Deletion, "big ")));
+    document_repository.expect_write_file().times(0);
+    suggestion_repository
+        .expect_update_status()
+        .with(
+            predicate::eq(1),
+            predicate::eq(1),
+            predicate::eq(SuggestionStatus::Outdated),
+        )
+        .times(1)
+        .returning(|_, _, _| Ok(()));
+
+    assert_eq!(
+        StatusCode::CONFLICT,
+        post_projects_suggestions_accept(
+            Extension(mock_project_repository()),
+            Extension(document_repository),
+            Extension(MockCommentRepository::new()),
+            Extension(suggestion_repository),
+            Extension(mock_event_repository(0)),
+            TypedHeader(XUserId(mock_owner_id())),
+            Path((1, 1)),
+        )
+        .await
+        .status()
+    )
+}
+
+#[tokio::test]
+async fn post_projects_suggestions_reject_forbidden_error() {
+    let mut suggestion_repository = MockSuggestionRepository::new();
+
+    suggestion_repository.expect_update_status().times(0);
+
+    assert_eq!(
+        StatusCode::FORBIDDEN,
+        post_projects_suggestions_reject(
+            Extension(mock_project_repository()),
+            Extension(suggestion_repository),
+            Extension(mock_event_repository(0)),
+            TypedHeader(XUserId(2)),
+            Path((1, 1)),
+        )
+        .await
+    )
+}
@@ -0,0 +1,34 @@
+use axum::{
+    response::{IntoResponse, Response},
+    Extension, Json, TypedHeader,
+};
+use http::StatusCode;
+use tracing::{info, warn};
+
+use crate::{
+    domain::usage::{QuotaExceeded, Usage},
+    extractors::headers::XUserId,
+    repository::usage::{UsageGetError, UsageRepository},
+};
+
+/// Rejects a write with the quota it would exceed, so clients can tell the user what to free
+pub fn quota_exceeded_response(exceeded: QuotaExceeded) -> Response {
+    warn!(?exceeded, "Write rejected by quota");
+    (StatusCode::PAYLOAD_TOO_LARGE, Json(exceeded)).into_response()
+}
+
+#[tracing::instrument(skip(usage_repository))]
+pub async fn get_users_me_usage<U: UsageRepository>(
+    Extension(usage_repository): Extension<U>,
+    TypedHeader(XUserId(user_id)): TypedHeader<XUserId>,
+) -> Result<Json<Usage>, StatusCode> {
+    info!("Received attempt to get storage usage");
+
+    match usage_repository.get(user_id).await {
+        Ok(usage) => Ok(Json(usage)),
+        Err(UsageGetError::Unknown) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+#[cfg(test)]
+mod tests;
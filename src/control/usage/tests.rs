@@ -0,0 +1,36 @@
+use axum::Extension;
+use mockall::predicate;
+
+use crate::{domain::usage::ProjectUsage, repository::usage::MockUsageRepository};
+
+use super::*;
+
+#[tokio::test]
+async fn get_users_me_usage_normal() {
+    let mut usage_repository = MockUsageRepository::new();
+
+    usage_repository
+        .expect_get()
+        .with(predicate::eq(3))
+        .times(1)
+        .returning(|_| {
+            Ok(Usage {
+                used_bytes: 2048,
+                quota_bytes: Some(4096),
+                project_quota_bytes: None,
+                projects: vec![ProjectUsage {
+                    project_id: 1,
+                    project_name: String::from("thesis"),
+                    used_bytes: 2048,
+                }],
+            })
+        });
+
+    match get_users_me_usage(Extension(usage_repository), TypedHeader(XUserId(3))).await {
+        Ok(Json(usage)) => {
+            assert_eq!(2048, usage.used_bytes);
+            assert_eq!(1, usage.projects.len());
+        }
+        Err(status) => panic!("unexpected status {}", status),
+    }
+}
@@ -2,24 +2,45 @@ use std::fmt::Debug;
 
 use axum::{extract::Path, Extension, Json};
 use http::StatusCode;
-use tracing::info;
+use tracing::{info, warn};
 
 use crate::{
+    control::{passwords::hash_password, verifications::send_verification},
     domain::users::{User, UserData},
-    repository::users::{UserGetError, UserInsertError, UserRepository},
+    notify::SharedNotifier,
+    repository::{
+        email_verifications::EmailVerificationRepository,
+        users::{UserGetError, UserInsertError, UserRepository},
+    },
+    validation::ValidatedJson,
 };
 
 #[tracing::instrument(skip_all, fields(email = data.email))]
-pub async fn post_users<T: UserRepository + Debug>(
+pub async fn post_users<T: UserRepository + Debug, V: EmailVerificationRepository>(
     Extension(repository): Extension<T>,
-    Json(data): Json<UserData>,
+    Extension(verification_repository): Extension<V>,
+    Extension(notifier): Extension<SharedNotifier>,
+    ValidatedJson(data): ValidatedJson<UserData>,
 ) -> StatusCode {
     info!("Received user creation attempt");
-    match repository.insert(data).await {
-        Ok(()) => StatusCode::CREATED,
-        Err(UserInsertError::Duplicate) => StatusCode::CONFLICT,
-        Err(UserInsertError::Unknown) => StatusCode::INTERNAL_SERVER_ERROR,
+    let password_hash = match hash_password(data.password).await {
+        Ok(password_hash) => password_hash,
+        Err(status) => return status,
+    };
+    let user_id = match repository.insert(&data.email, &password_hash).await {
+        Ok(user_id) => user_id,
+        Err(UserInsertError::Duplicate) => return StatusCode::CONFLICT,
+        Err(UserInsertError::Unknown) => return StatusCode::INTERNAL_SERVER_ERROR,
+    };
+
+    // the user can ask for another link, so the account is kept either way
+    if send_verification(&verification_repository, &notifier, user_id, &data.email)
+        .await
+        .is_err()
+    {
+        warn!(user_id, "Could not start email verification");
     }
+    StatusCode::CREATED
 }
 
 #[tracing::instrument(skip(repository))]
@@ -1,10 +1,14 @@
+use std::sync::Arc;
+
 use axum::Extension;
 use http::StatusCode;
 use mockall::predicate;
+use validator::Validate;
 
 use crate::{
     domain::users::{User, UserData},
-    repository::users::MockUserRepository,
+    notify::MockNotifier,
+    repository::{email_verifications::MockEmailVerificationRepository, users::MockUserRepository},
 };
 
 use super::*;
@@ -20,7 +24,7 @@ fn mock_password() -> String {
 fn mock_user_data() -> UserData {
     UserData {
         email: mock_email(),
-        password_hash: mock_password(),
+        password: mock_password(),
     }
 }
 
@@ -28,39 +32,86 @@ fn mock_user() -> User {
     User {
         id: 1,
         email: mock_email(),
+        display_name: None,
         password_hash: mock_password(),
+        verified_at: None,
     }
 }
 
+#[test]
+fn user_data_checks_email() {
+    let mut data = mock_user_data();
+    assert!(data.validate().is_err());
+
+    data.email = String::from("john@email.com");
+    assert!(data.validate().is_ok());
+}
+
+#[test]
+fn user_data_checks_password_length() {
+    let mut data = mock_user_data();
+    data.email = String::from("john@email.com");
+
+    data.password = String::from("short");
+    assert!(data.validate().is_err());
+}
+
 #[tokio::test]
 async fn post_users_normal() {
     let mut user_repository = MockUserRepository::new();
+    let mut verification_repository = MockEmailVerificationRepository::new();
+    let mut notifier = MockNotifier::new();
 
     user_repository
         .expect_insert()
-        .with(predicate::eq(mock_user_data()))
+        .withf(|email, password_hash| {
+            email == mock_email() && bcrypt::verify(mock_password(), password_hash).unwrap()
+        })
+        .times(1)
+        .returning(|_, _| Ok(1));
+    verification_repository
+        .expect_insert()
+        .with(predicate::eq(1))
+        .times(1)
+        .returning(|_| Ok(String::from("token")));
+    notifier
+        .expect_send()
+        .withf(|notification| notification.to == mock_email())
         .times(1)
         .returning(|_| Ok(()));
 
     assert_eq!(
         StatusCode::CREATED,
-        post_users(Extension(user_repository), Json(mock_user_data())).await
+        post_users(
+            Extension(user_repository),
+            Extension(verification_repository),
+            Extension(Arc::new(notifier) as SharedNotifier),
+            ValidatedJson(mock_user_data())
+        )
+        .await
     )
 }
 
 #[tokio::test]
 async fn post_users_duplicate_error() {
     let mut user_repository = MockUserRepository::new();
+    let mut verification_repository = MockEmailVerificationRepository::new();
 
     user_repository
         .expect_insert()
-        .with(predicate::eq(mock_user_data()))
         .times(1)
-        .returning(|_| Err(UserInsertError::Duplicate));
+        .returning(|_, _| Err(UserInsertError::Duplicate));
+    verification_repository.expect_insert().never();
 
     assert_eq!(
         StatusCode::CONFLICT,
-        post_users(Extension(user_repository), Json(mock_user_data())).await
+        post_users(
+            Extension(user_repository),
+            Extension(verification_repository),
+            Extension(Arc::new(MockNotifier::new()) as SharedNotifier),
+            ValidatedJson(mock_user_data())
+        )
+        .await
     )
 }
 
@@ -70,13 +121,18 @@ async fn post_users_service_unknown_error() {
 
     user_repository
         .expect_insert()
-        .with(predicate::eq(mock_user_data()))
         .times(1)
-        .returning(|_| Err(UserInsertError::Unknown));
+        .returning(|_, _| Err(UserInsertError::Unknown));
 
     assert_eq!(
         StatusCode::INTERNAL_SERVER_ERROR,
-        post_users(Extension(user_repository), Json(mock_user_data())).await
+        post_users(
+            Extension(user_repository),
+            Extension(MockEmailVerificationRepository::new()),
+            Extension(Arc::new(MockNotifier::new()) as SharedNotifier),
+            ValidatedJson(mock_user_data())
+        )
+        .await
     )
 }
 
@@ -127,3 +183,13 @@ async fn get_users_unknown_error() {
     assert!(res.is_err());
     assert_eq!(StatusCode::INTERNAL_SERVER_ERROR, res.unwrap_err())
 }
+
+#[test]
+fn user_json_omits_password_hash() {
+    let json = serde_json::to_value(mock_user()).unwrap();
+
+    assert_eq!(
+        serde_json::json!({ "id": 1, "email": "email", "display_name": null, "verified_at": null }),
+        json
+    );
+}
@@ -0,0 +1,83 @@
+use axum::{Extension, Json, TypedHeader};
+use http::StatusCode;
+use tracing::{error, info};
+
+use crate::{
+    constants::EMAIL_VERIFICATION_URL,
+    domain::users::EmailVerification,
+    extractors::headers::XUserId,
+    notify::{Notification, SharedNotifier},
+    repository::{
+        email_verifications::{
+            EmailVerificationInsertError, EmailVerificationRedeemError, EmailVerificationRepository,
+        },
+        users::{UserGetError, UserRepository},
+    },
+};
+
+/// Issues a token and mails the verification link to the user
+pub async fn send_verification<V: EmailVerificationRepository>(
+    verification_repository: &V,
+    notifier: &SharedNotifier,
+    user_id: i32,
+    email: &str,
+) -> Result<(), EmailVerificationInsertError> {
+    let token = verification_repository.insert(user_id).await?;
+
+    let notification = Notification {
+        to: email.to_string(),
+        subject: String::from("Verify your email address"),
+        body: format!(
+            "Follow this link to confirm this address belongs to you:\n{}{}\n\nIf you did not create an account, ignore this message.",
+            *EMAIL_VERIFICATION_URL, token
+        ),
+    };
+    if notifier.send(&notification).await.is_err() {
+        error!(user_id, "Could not send email verification");
+        return Err(EmailVerificationInsertError::Unknown);
+    }
+    Ok(())
+}
+
+/// Sends a new verification link, earlier links stay valid until they expire
+#[tracing::instrument(skip(user_repository, verification_repository, notifier))]
+pub async fn post_users_me_verification<U: UserRepository, V: EmailVerificationRepository>(
+    Extension(user_repository): Extension<U>,
+    Extension(verification_repository): Extension<V>,
+    Extension(notifier): Extension<SharedNotifier>,
+    TypedHeader(XUserId(user_id)): TypedHeader<XUserId>,
+) -> StatusCode {
+    info!("Received email verification request");
+
+    let user = match user_repository.get_by_id(user_id).await {
+        Ok(user) => user,
+        Err(UserGetError::Missing) => return StatusCode::NOT_FOUND,
+        Err(UserGetError::Unknown) => return StatusCode::INTERNAL_SERVER_ERROR,
+    };
+
+    match send_verification(&verification_repository, &notifier, user.id, &user.email).await {
+        Ok(()) => StatusCode::ACCEPTED,
+        Err(EmailVerificationInsertError::Verified) => StatusCode::CONFLICT,
+        Err(EmailVerificationInsertError::Unknown) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn post_users_verification_confirm<V: EmailVerificationRepository>(
+    Extension(verification_repository): Extension<V>,
+    Json(verification): Json<EmailVerification>,
+) -> StatusCode {
+    info!("Received email verification attempt");
+
+    match verification_repository.redeem(&verification.token).await {
+        Ok(user_id) => {
+            info!(user_id, "Email verified");
+            StatusCode::NO_CONTENT
+        }
+        Err(EmailVerificationRedeemError::Invalid) => StatusCode::NOT_FOUND,
+        Err(EmailVerificationRedeemError::Unknown) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+#[cfg(test)]
+mod tests;
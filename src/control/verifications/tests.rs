@@ -0,0 +1,110 @@
+use std::sync::Arc;
+
+use axum::Extension;
+use http::StatusCode;
+use mockall::predicate;
+use sqlx::types::chrono::NaiveDateTime;
+
+use crate::{
+    domain::users::User,
+    notify::MockNotifier,
+    repository::{email_verifications::MockEmailVerificationRepository, users::MockUserRepository},
+};
+
+use super::*;
+
+fn mock_user(verified_at: Option<NaiveDateTime>) -> User {
+    User {
+        id: 1,
+        email: String::from("john@email.com"),
+        display_name: None,
+        password_hash: String::from("hash"),
+        verified_at,
+    }
+}
+
+#[tokio::test]
+async fn post_users_me_verification_sends_token() {
+    let mut user_repository = MockUserRepository::new();
+    let mut verification_repository = MockEmailVerificationRepository::new();
+    let mut notifier = MockNotifier::new();
+
+    user_repository
+        .expect_get_by_id()
+        .with(predicate::eq(1))
+        .times(1)
+        .returning(|_| Ok(mock_user(None)));
+    verification_repository
+        .expect_insert()
+        .with(predicate::eq(1))
+        .times(1)
+        .returning(|_| Ok(String::from("token")));
+    notifier
+        .expect_send()
+        .withf(|notification| {
+            notification.to == "john@email.com" && notification.body.contains("token")
+        })
+        .times(1)
+        .returning(|_| Ok(()));
+
+    assert_eq!(
+        StatusCode::ACCEPTED,
+        post_users_me_verification(
+            Extension(user_repository),
+            Extension(verification_repository),
+            Extension(Arc::new(notifier) as SharedNotifier),
+            TypedHeader(XUserId(1)),
+        )
+        .await
+    );
+}
+
+#[tokio::test]
+async fn post_users_me_verification_already_verified_error() {
+    let mut user_repository = MockUserRepository::new();
+    let mut verification_repository = MockEmailVerificationRepository::new();
+    let mut notifier = MockNotifier::new();
+
+    user_repository
+        .expect_get_by_id()
+        .times(1)
+        .returning(|_| Ok(mock_user(None)));
+    verification_repository
+        .expect_insert()
+        .times(1)
+        .returning(|_| Err(EmailVerificationInsertError::Verified));
+    notifier.expect_send().never();
+
+    assert_eq!(
+        StatusCode::CONFLICT,
+        post_users_me_verification(
+            Extension(user_repository),
+            Extension(verification_repository),
+            Extension(Arc::new(notifier) as SharedNotifier),
+            TypedHeader(XUserId(1)),
+        )
+        .await
+    );
+}
+
+#[tokio::test]
+async fn post_users_verification_confirm_invalid_token_error() {
+    let mut verification_repository = MockEmailVerificationRepository::new();
+
+    verification_repository
+        .expect_redeem()
+        .with(predicate::eq("token"))
+        .times(1)
+        .returning(|_| Err(EmailVerificationRedeemError::Invalid));
+
+    assert_eq!(
+        StatusCode::NOT_FOUND,
+        post_users_verification_confirm(
+            Extension(verification_repository),
+            Json(EmailVerification {
+                token: String::from("token"),
+            }),
+        )
+        .await
+    );
+}
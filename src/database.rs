@@ -1,10 +1,13 @@
 use sqlx::{
-    postgres::{PgConnectOptions, PgPool},
+    postgres::{PgConnectOptions, PgConnection, PgPool},
     Error,
 };
 
 use crate::constants::FALLBACK_DB_URL;
 
+/// Advisory lock every running service holds shared, repairs take it exclusively
+pub const SERVICE_LOCK_ID: i64 = 3_200_001;
+
 pub async fn create_conn_pool() -> Result<PgPool, Error> {
     // https://www.postgresql.org/docs/current/libpq-envars.html
     // https://docs.rs/sqlx/latest/sqlx/postgres/struct.PgConnectOptions.html
@@ -18,3 +21,15 @@ pub async fn create_conn_pool() -> Result<PgPool, Error> {
     sqlx::migrate!().run(&pool).await?;
     Ok(pool)
 }
+
+/// Marks the service as running for as long as the returned connection stays open.
+/// The connection is detached, since the pool would keep the lock on an idle connection
+/// and release it whenever it closed that connection.
+pub async fn hold_service_lock(pool: &PgPool) -> Result<PgConnection, Error> {
+    let mut connection = pool.acquire().await?.detach();
+    sqlx::query("SELECT pg_advisory_lock_shared($1)")
+        .bind(SERVICE_LOCK_ID)
+        .execute(&mut connection)
+        .await?;
+    Ok(connection)
+}
@@ -0,0 +1,96 @@
+/// A single contiguous replacement turning one text into another.
+/// Offsets are counted in characters, not bytes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextEdit {
+    pub start: usize,
+    pub removed: usize,
+    pub inserted: String,
+}
+
+impl TextEdit {
+    /// Finds the smallest edit between `old` and `new` by trimming their common prefix and suffix
+    pub fn between(old: &str, new: &str) -> Option<Self> {
+        let old: Vec<char> = old.chars().collect();
+        let new: Vec<char> = new.chars().collect();
+
+        let prefix = old
+            .iter()
+            .zip(new.iter())
+            .take_while(|(a, b)| a == b)
+            .count();
+        if prefix == old.len() && prefix == new.len() {
+            return None;
+        }
+
+        let suffix = old[prefix..]
+            .iter()
+            .rev()
+            .zip(new[prefix..].iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+
+        Some(Self {
+            start: prefix,
+            removed: old.len() - prefix - suffix,
+            inserted: new[prefix..new.len() - suffix].iter().collect(),
+        })
+    }
+
+    pub fn inserted_len(&self) -> usize {
+        self.inserted.chars().count()
+    }
+
+    fn shift(&self, position: usize) -> Option<usize> {
+        if position <= self.start {
+            Some(position)
+        } else if position >= self.start + self.removed {
+            Some(position + self.inserted_len() - self.removed)
+        } else {
+            None
+        }
+    }
+
+    /// Maps the start of a range, collapsing onto the edit when the position was removed
+    pub fn map_start(&self, position: usize) -> usize {
+        self.shift(position).unwrap_or(self.start)
+    }
+
+    /// Maps the end of a range, extending over the inserted text when the position was removed
+    pub fn map_end(&self, position: usize) -> usize {
+        self.shift(position)
+            .unwrap_or(self.start + self.inserted_len())
+    }
+
+    /// Moves a range that does not overlap the edit, returning `None` when the two conflict
+    pub fn rebase_range(&self, start: usize, end: usize) -> Option<(usize, usize)> {
+        if end <= self.start {
+            Some((start, end))
+        } else if start >= self.start + self.removed {
+            let delta = |position: usize| position + self.inserted_len() - self.removed;
+            Some((delta(start), delta(end)))
+        } else {
+            None
+        }
+    }
+
+    /// Applies the edit, returning `None` if the text is too short for it
+    pub fn apply(&self, text: &str) -> Option<String> {
+        let chars: Vec<char> = text.chars().collect();
+        if self.start + self.removed > chars.len() {
+            return None;
+        }
+
+        let mut result: String = chars[..self.start].iter().collect();
+        result.push_str(&self.inserted);
+        result.extend(chars[self.start + self.removed..].iter());
+        Some(result)
+    }
+
+    /// Returns the text this edit removes from `text`
+    pub fn removed_text(&self, text: &str) -> String {
+        text.chars().skip(self.start).take(self.removed).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests;
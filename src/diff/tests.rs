@@ -0,0 +1,87 @@
+use super::*;
+
+#[test]
+fn between_identical_texts() {
+    assert_eq!(None, TextEdit::between("abc", "abc"))
+}
+
+#[test]
+fn between_insertion() {
+    let edit = TextEdit::between("hello world", "hello big world").unwrap();
+
+    assert_eq!(
+        TextEdit {
+            start: 6,
+            removed: 0,
+            inserted: String::from("big "),
+        },
+        edit
+    )
+}
+
+#[test]
+fn between_deletion() {
+    let edit = TextEdit::between("hello big world", "hello world").unwrap();
+
+    assert_eq!(
+        TextEdit {
+            start: 6,
+            removed: 4,
+            inserted: String::new(),
+        },
+        edit
+    )
+}
+
+#[test]
+fn between_counts_characters() {
+    let edit = TextEdit::between("zażółć", "zażółta").unwrap();
+
+    assert_eq!(5, edit.start);
+    assert_eq!(1, edit.removed);
+    assert_eq!("ta", edit.inserted)
+}
+
+#[test]
+fn map_positions_around_edit() {
+    let edit = TextEdit::between("hello big world", "hello small world").unwrap();
+
+    assert_eq!(2, edit.map_start(2));
+    assert_eq!(edit.start, edit.map_start(8));
+    assert_eq!(edit.start + 5, edit.map_end(8));
+    assert_eq!(13, edit.map_end(11))
+}
+
+#[test]
+fn rebase_range_around_edit() {
+    let insertion = TextEdit {
+        start: 5,
+        removed: 0,
+        inserted: String::from("abc"),
+    };
+
+    assert_eq!(Some((1, 5)), insertion.rebase_range(1, 5));
+    assert_eq!(Some((8, 10)), insertion.rebase_range(5, 7));
+    assert_eq!(None, insertion.rebase_range(4, 6))
+}
+
+#[test]
+fn apply_roundtrip() {
+    let old = "\\section{Intro}\nText";
+    let new = "\\section{Introduction}\nMore text";
+    let edit = TextEdit::between(old, new).unwrap();
+
+    assert_eq!(Some(String::from(new)), edit.apply(old));
+    assert_eq!("}\nT", edit.removed_text(old))
+}
+
+#[test]
+fn apply_out_of_bounds() {
+    let edit = TextEdit {
+        start: 10,
+        removed: 2,
+        inserted: String::new(),
+    };
+
+    assert_eq!(None, edit.apply("short"))
+}
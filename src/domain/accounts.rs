@@ -0,0 +1,34 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ProjectTransfer {
+    pub project_id: i32,
+    /// Email of a collaborator of the project, who becomes its owner
+    pub new_owner_email: String,
+}
+
+/// Owned projects without a transfer are deleted along with the account
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct AccountDeletion {
+    /// Reports what would be affected without changing anything
+    #[serde(default)]
+    pub dry_run: bool,
+    #[serde(default)]
+    pub transfers: Vec<ProjectTransfer>,
+    /// Removes the activity of the user in remaining projects instead of anonymizing it
+    #[serde(default)]
+    pub remove_activity: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct AccountDeletionReport {
+    pub dry_run: bool,
+    pub transferred_projects: Vec<i32>,
+    pub deleted_projects: Vec<i32>,
+    /// Projects of others the user no longer collaborates on
+    pub left_projects: Vec<i32>,
+    pub ended_sessions: u64,
+    pub removed_events: u64,
+    /// Events, comments, suggestions and snapshots kept under an anonymous author
+    pub anonymized_entries: u64,
+}
@@ -0,0 +1,50 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{types::chrono::NaiveDateTime, FromRow};
+use validator::Validate;
+
+use crate::extractors::time::{json_optional_time, json_time};
+
+#[derive(sqlx::Type, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[sqlx(type_name = "text", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ApiTokenScope {
+    /// Only requests that change nothing
+    Read,
+    /// Any request, reading included
+    Write,
+}
+
+#[derive(FromRow, Debug, Clone, PartialEq, Serialize)]
+pub struct ApiToken {
+    pub token_id: i32,
+    pub user_id: i32,
+    pub name: String,
+    pub scope: ApiTokenScope,
+    pub project_id: Option<i32>,
+    #[serde(with = "json_time")]
+    pub created_at: NaiveDateTime,
+    #[serde(with = "json_time")]
+    pub expires_at: NaiveDateTime,
+    #[serde(with = "json_optional_time")]
+    pub last_used_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Validate)]
+pub struct ApiTokenData {
+    #[validate(length(min = 1, max = 128))]
+    pub name: String,
+    pub scope: ApiTokenScope,
+    /// Restricts the token to one project the user has access to
+    #[serde(default)]
+    pub project_id: Option<i32>,
+    #[validate(range(min = 1, max = 365))]
+    pub expires_in_days: i32,
+}
+
+/// Returned once on creation, the secret cannot be retrieved afterwards
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CreatedApiToken {
+    #[serde(flatten)]
+    pub token: ApiToken,
+    pub secret: String,
+}
@@ -0,0 +1,73 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{types::chrono::NaiveDateTime, FromRow};
+use validator::{Validate, ValidationError};
+
+use crate::extractors::time::json_time;
+
+#[derive(FromRow, Debug, Clone, PartialEq, Serialize)]
+pub struct Comment {
+    pub comment_id: i32,
+    pub thread_id: i32,
+    pub author_id: i32,
+    #[sqlx(rename = "email")]
+    pub author_email: String,
+    #[sqlx(rename = "display_name")]
+    pub author_display_name: Option<String>,
+    pub content: String,
+    pub mentions: Vec<i32>,
+    #[serde(with = "json_time")]
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(FromRow, Debug, Clone, PartialEq, Serialize)]
+pub struct CommentThread {
+    pub thread_id: i32,
+    pub project_id: i32,
+    pub document_id: i32,
+    pub author_id: i32,
+    pub anchor_start: i32,
+    pub anchor_end: i32,
+    pub resolved: bool,
+    #[serde(with = "json_time")]
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Thread {
+    #[serde(flatten)]
+    pub thread: CommentThread,
+    pub comments: Vec<Comment>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Validate)]
+pub struct CommentData {
+    #[validate(length(min = 1, max = 4096))]
+    pub content: String,
+    #[serde(default)]
+    pub mentions: Vec<i32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Validate)]
+#[validate(schema(function = "validate_anchor"))]
+pub struct ThreadData {
+    pub document_id: i32,
+    #[validate(range(min = 0))]
+    pub anchor_start: i32,
+    #[validate(range(min = 0))]
+    pub anchor_end: i32,
+    #[validate]
+    #[serde(flatten)]
+    pub comment: CommentData,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ThreadStatus {
+    pub resolved: bool,
+}
+
+fn validate_anchor(data: &ThreadData) -> Result<(), ValidationError> {
+    if data.anchor_start > data.anchor_end {
+        return Err(ValidationError::new("anchor_start after anchor_end"));
+    }
+    Ok(())
+}
@@ -6,6 +6,7 @@ pub struct Document {
     pub document_id: i32,
     pub project_id: i32,
     pub name: String,
+    pub folder_id: Option<i32>,
 }
 
 #[derive(FromRow, Debug, Clone, PartialEq, Deserialize)]
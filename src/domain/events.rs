@@ -0,0 +1,64 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{types::chrono::NaiveDateTime, FromRow};
+
+use crate::extractors::time::json_time;
+
+#[derive(sqlx::Type, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[sqlx(type_name = "text", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ProjectEventType {
+    ProjectCreate,
+    MetadataUpdate,
+    SettingsUpdate,
+    DocumentUpdate,
+    ResourceCreate,
+    ResourceUpdate,
+    SharingCreate,
+    SharingRedeem,
+    CommentCreate,
+    CommentReply,
+    CommentStatusUpdate,
+    SuggestionCreate,
+    SuggestionAccept,
+    SuggestionReject,
+    SnapshotCreate,
+    SnapshotRestore,
+    FolderCreate,
+    FolderUpdate,
+    FolderDelete,
+    OwnershipTransfer,
+    CollaboratorRemove,
+}
+
+#[derive(FromRow, Debug, Clone, PartialEq, Serialize)]
+pub struct ProjectEvent {
+    pub event_id: i32,
+    pub project_id: i32,
+    pub actor_id: i32,
+    #[sqlx(rename = "email")]
+    pub actor_email: String,
+    #[sqlx(rename = "display_name")]
+    pub actor_display_name: Option<String>,
+    pub event_type: ProjectEventType,
+    /// Id of the document, resource, folder, thread, suggestion or snapshot the event refers to,
+    /// or of the user for ownership transfers and collaborator removals
+    pub target_id: Option<i32>,
+    #[serde(with = "json_time")]
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ActivityFilter {
+    pub actor_id: Option<i32>,
+    pub event_type: Option<ProjectEventType>,
+    /// Only events older than this one are returned, used to fetch the next page
+    pub before: Option<i32>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ActivityPage {
+    pub events: Vec<ProjectEvent>,
+    /// Value of `before` for the next page, absent on the last page
+    pub next: Option<i32>,
+}
@@ -0,0 +1,48 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use validator::Validate;
+
+use crate::{
+    constants::NAME_REGEX,
+    domain::{documents::Document, resources::Resource},
+    validation::validate_path_segment,
+};
+
+#[derive(FromRow, Debug, Clone, PartialEq, Serialize)]
+pub struct Folder {
+    pub folder_id: i32,
+    pub project_id: i32,
+    pub parent_id: Option<i32>,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Validate)]
+pub struct FolderData {
+    #[validate(
+        length(min = 1, max = 128),
+        regex = "NAME_REGEX",
+        custom = "validate_path_segment"
+    )]
+    pub name: String,
+    /// Parent folder, the project root when absent
+    #[serde(default)]
+    pub parent_id: Option<i32>,
+}
+
+/// Folder a document or resource is moved into
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct FolderPlacement {
+    /// The project root when absent
+    #[serde(default)]
+    pub folder_id: Option<i32>,
+}
+
+/// Contents of a folder, or of the project root when `folder_id` is absent
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FolderTree {
+    pub folder_id: Option<i32>,
+    pub name: String,
+    pub folders: Vec<FolderTree>,
+    pub documents: Vec<Document>,
+    pub resources: Vec<Resource>,
+}
@@ -0,0 +1,16 @@
+use serde::Serialize;
+use sqlx::types::chrono::NaiveDateTime;
+
+use crate::extractors::time::json_time;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GcReport {
+    #[serde(with = "json_time")]
+    pub started_at: NaiveDateTime,
+    /// Hashes of stored contents that lost their last reference
+    pub removed_blobs: Vec<String>,
+    /// Keys of stored files that no database row refers to
+    pub removed_orphans: Vec<String>,
+    /// Orphans seen but still within the grace period
+    pub pending_orphans: usize,
+}
@@ -1,6 +1,16 @@
+pub mod accounts;
+pub mod api_tokens;
+pub mod comments;
 pub mod crud;
 pub mod documents;
+pub mod events;
+pub mod folders;
+pub mod gc;
+pub mod presence;
 pub mod projects;
 pub mod resources;
 pub mod sessions;
+pub mod snapshots;
+pub mod suggestions;
+pub mod usage;
 pub mod users;
@@ -0,0 +1,60 @@
+use serde::{Deserialize, Serialize};
+use sqlx::types::chrono::NaiveDateTime;
+
+use crate::extractors::time::json_time;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Selection {
+    pub anchor: usize,
+    pub head: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Cursor {
+    pub document_id: i32,
+    pub position: usize,
+    pub selection: Option<Selection>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Collaborator {
+    pub user_id: i32,
+    pub email: String,
+    pub display_name: Option<String>,
+    pub cursors: Vec<Cursor>,
+    pub idle: bool,
+    #[serde(with = "json_time")]
+    pub last_active: NaiveDateTime,
+}
+
+/// Events broadcast to every socket connected to a project
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PresenceEvent {
+    Join {
+        user_id: i32,
+        email: String,
+        display_name: Option<String>,
+    },
+    Leave {
+        user_id: i32,
+    },
+    Cursor {
+        user_id: i32,
+        cursor: Cursor,
+    },
+    Idle {
+        user_id: i32,
+    },
+    Active {
+        user_id: i32,
+    },
+}
+
+/// Messages accepted from a connected client
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PresenceMessage {
+    Cursor(Cursor),
+    Heartbeat,
+}
@@ -10,14 +10,23 @@ pub struct Project {
     pub owner_id: i32,
     #[sqlx(rename = "email")]
     pub owner_email: String,
+    #[sqlx(rename = "display_name")]
+    pub owner_display_name: Option<String>,
     pub project_name: String,
 
     #[serde(with = "json_time")]
     pub created_at: NaiveDateTime,
     #[serde(with = "json_time")]
     pub last_modified: NaiveDateTime,
+    pub track_changes: bool,
+    /// Bytes used by the documents and resources of the project
+    pub used_bytes: i64,
 }
 #[derive(FromRow, Debug, Clone, PartialEq, Deserialize)]
 pub struct ProjectMetadata {
     pub name: String,
 }
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ProjectSettings {
+    pub track_changes: bool,
+}
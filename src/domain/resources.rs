@@ -2,17 +2,41 @@ use serde::{Deserialize, Serialize};
 use sqlx;
 use validator::Validate;
 
-use crate::constants::NAME_REGEX;
+use crate::{constants::NAME_REGEX, validation::validate_path_segment};
 
 #[derive(sqlx::FromRow, Debug, Clone, PartialEq, Serialize)]
 pub struct Resource {
     pub resource_id: i32,
     pub project_id: i32,
     pub name: String,
+    pub folder_id: Option<i32>,
+    #[serde(skip)]
+    pub content_hash: Option<String>,
 }
 
 #[derive(Debug, Clone, PartialEq, Deserialize, Validate)]
 pub struct ResourceMetadata {
-    #[validate(length(min = 1, max = 128), regex = "NAME_REGEX")]
+    #[validate(
+        length(min = 1, max = 128),
+        regex = "NAME_REGEX",
+        custom = "validate_path_segment"
+    )]
     pub name: String,
+    /// Folder to create the resource in, the project root when absent
+    #[serde(default)]
+    pub folder_id: Option<i32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Validate)]
+pub struct ResourceCopyData {
+    /// Project receiving the copy, the source project when absent
+    pub project_id: Option<i32>,
+    #[validate(
+        length(min = 1, max = 128),
+        regex = "NAME_REGEX",
+        custom = "validate_path_segment"
+    )]
+    pub name: String,
+    #[serde(default)]
+    pub folder_id: Option<i32>,
 }
@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
-use sqlx;
+use sqlx::{self, types::chrono::NaiveDateTime};
+
+use crate::extractors::time::json_time;
 
 use super::users::User;
 
@@ -18,4 +20,30 @@ pub struct SessionData {
     pub id: String,
     pub user_id: i32,
     pub expires: i64,
+    #[sqlx(default)]
+    #[serde(default)]
+    pub ip: Option<String>,
+    #[sqlx(default)]
+    #[serde(default)]
+    pub user_agent: Option<String>,
+}
+
+/// Where a user is logged in, without the session id itself
+#[derive(sqlx::FromRow, Debug, Clone, PartialEq, Serialize)]
+pub struct SessionInfo {
+    /// Whether this is the session the request was made with
+    pub current: bool,
+    #[serde(with = "json_time")]
+    pub created_at: NaiveDateTime,
+    #[serde(with = "json_time")]
+    pub last_seen: NaiveDateTime,
+    pub expires: i64,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct SessionRevokeOptions {
+    #[serde(default)]
+    pub keep_current: bool,
 }
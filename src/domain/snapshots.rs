@@ -0,0 +1,37 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{types::chrono::NaiveDateTime, FromRow};
+use validator::Validate;
+
+use crate::extractors::time::json_time;
+
+#[derive(sqlx::Type, Debug, Clone, Copy, PartialEq, Serialize)]
+#[sqlx(type_name = "text", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum SnapshotFileKind {
+    Document,
+    Resource,
+}
+
+#[derive(FromRow, Debug, Clone, PartialEq, Serialize)]
+pub struct SnapshotFile {
+    pub kind: SnapshotFileKind,
+    pub name: String,
+    pub content_hash: String,
+    pub size: i64,
+}
+
+#[derive(FromRow, Debug, Clone, PartialEq, Serialize)]
+pub struct Snapshot {
+    pub snapshot_id: i32,
+    pub project_id: i32,
+    pub author_id: i32,
+    pub name: String,
+    #[serde(with = "json_time")]
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Validate)]
+pub struct SnapshotData {
+    #[validate(length(min = 1, max = 128))]
+    pub name: String,
+}
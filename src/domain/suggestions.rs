@@ -0,0 +1,100 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{types::chrono::NaiveDateTime, FromRow};
+
+use crate::{diff::TextEdit, extractors::time::json_time};
+
+#[derive(sqlx::Type, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[sqlx(type_name = "text", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum SuggestionKind {
+    Insertion,
+    Deletion,
+}
+
+#[derive(sqlx::Type, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[sqlx(type_name = "text", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum SuggestionStatus {
+    Pending,
+    Accepted,
+    Rejected,
+    Outdated,
+}
+
+#[derive(FromRow, Debug, Clone, PartialEq, Serialize)]
+pub struct Suggestion {
+    pub suggestion_id: i32,
+    pub project_id: i32,
+    pub document_id: i32,
+    pub author_id: i32,
+    #[sqlx(rename = "email")]
+    pub author_email: String,
+    #[sqlx(rename = "display_name")]
+    pub author_display_name: Option<String>,
+    pub kind: SuggestionKind,
+    pub position: i32,
+    pub content: String,
+    pub status: SuggestionStatus,
+    #[serde(with = "json_time")]
+    pub created_at: NaiveDateTime,
+}
+
+impl Suggestion {
+    pub fn as_edit(&self) -> TextEdit {
+        match self.kind {
+            SuggestionKind::Insertion => TextEdit {
+                start: self.position as usize,
+                removed: 0,
+                inserted: self.content.clone(),
+            },
+            SuggestionKind::Deletion => TextEdit {
+                start: self.position as usize,
+                removed: self.content.chars().count(),
+                inserted: String::new(),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SuggestionData {
+    pub kind: SuggestionKind,
+    pub position: i32,
+    pub content: String,
+}
+
+impl SuggestionData {
+    /// Splits an edit of `text` into a deletion and an insertion at the same position
+    pub fn from_edit(text: &str, edit: &TextEdit) -> Vec<Self> {
+        let mut suggestions = Vec::new();
+        if edit.removed > 0 {
+            suggestions.push(Self {
+                kind: SuggestionKind::Deletion,
+                position: edit.start as i32,
+                content: edit.removed_text(text),
+            });
+        }
+        if !edit.inserted.is_empty() {
+            suggestions.push(Self {
+                kind: SuggestionKind::Insertion,
+                position: edit.start as i32,
+                content: edit.inserted.clone(),
+            });
+        }
+        suggestions
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct SuggestionSelection {
+    /// Selects every pending suggestion of the project when missing
+    #[serde(default)]
+    pub suggestion_ids: Option<Vec<i32>>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SuggestionReview {
+    pub accepted: Vec<i32>,
+    pub rejected: Vec<i32>,
+    pub outdated: Vec<i32>,
+}
@@ -0,0 +1,34 @@
+use serde::Serialize;
+use sqlx::FromRow;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuotaScope {
+    Project,
+    Owner,
+}
+
+/// Describes a write rejected because it would exceed a quota
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct QuotaExceeded {
+    pub scope: QuotaScope,
+    pub quota_bytes: i64,
+    pub used_bytes: i64,
+    pub requested_bytes: i64,
+}
+
+#[derive(FromRow, Debug, Clone, PartialEq, Serialize)]
+pub struct ProjectUsage {
+    pub project_id: i32,
+    pub project_name: String,
+    pub used_bytes: i64,
+}
+
+/// Bytes used by the projects a user owns, quotas are absent when unlimited
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Usage {
+    pub used_bytes: i64,
+    pub quota_bytes: Option<i64>,
+    pub project_quota_bytes: Option<i64>,
+    pub projects: Vec<ProjectUsage>,
+}
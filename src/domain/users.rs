@@ -1,15 +1,84 @@
 use serde::{Deserialize, Serialize};
+use sqlx::types::chrono::NaiveDateTime;
+use validator::Validate;
+
+use crate::{
+    constants::ORCID_REGEX, extractors::time::json_optional_time,
+    validation::validate_orcid_checksum,
+};
 
 #[derive(sqlx::FromRow, Debug, Clone, PartialEq, Serialize)]
 pub struct User {
     #[sqlx(rename = "user_id")]
     pub id: i32,
     pub email: String,
+    pub display_name: Option<String>,
+    /// Never sent to clients, passwords are checked with `POST /users/verify`
+    #[serde(skip_serializing)]
     pub password_hash: String,
+    /// Unverified users cannot join projects through share tokens
+    #[serde(with = "json_optional_time")]
+    pub verified_at: Option<NaiveDateTime>,
 }
 
-#[derive(Debug, Deserialize, PartialEq)]
+#[derive(Debug, Deserialize, PartialEq, Validate)]
 pub struct UserData {
+    #[validate(email, length(max = 128))]
     pub email: String,
-    pub password_hash: String,
+    /// Plaintext, hashed by the service before it is stored
+    #[validate(length(min = 8))]
+    pub password: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Credentials {
+    pub email: String,
+    pub password: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Validate)]
+pub struct PasswordChange {
+    pub old_password: String,
+    #[validate(length(min = 8))]
+    pub new_password: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct PasswordResetRequest {
+    pub email: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct EmailVerification {
+    pub token: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Validate)]
+pub struct PasswordReset {
+    pub token: String,
+    #[validate(length(min = 8))]
+    pub new_password: String,
+}
+
+#[derive(sqlx::FromRow, Debug, Clone, PartialEq, Serialize)]
+pub struct Profile {
+    pub display_name: Option<String>,
+    pub affiliation: Option<String>,
+    pub orcid: Option<String>,
+    /// The avatar is served by `GET /users/{user_email}/avatar`
+    pub has_avatar: bool,
+}
+
+/// Replaces every profile field, absent fields are cleared
+#[derive(Debug, Clone, PartialEq, Deserialize, Validate)]
+pub struct ProfileData {
+    #[validate(length(min = 1, max = 128))]
+    #[serde(default)]
+    pub display_name: Option<String>,
+    #[validate(length(min = 1, max = 256))]
+    #[serde(default)]
+    pub affiliation: Option<String>,
+    #[validate(regex = "ORCID_REGEX", custom = "validate_orcid_checksum")]
+    #[serde(default)]
+    pub orcid: Option<String>,
 }
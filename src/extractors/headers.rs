@@ -28,3 +28,30 @@ impl Header for XUserId {
         values.extend(std::iter::once(value));
     }
 }
+
+pub struct XAdminToken(pub String);
+
+impl Header for XAdminToken {
+    fn name() -> &'static HeaderName {
+        &constants::XADMINTOKEN_HEADER_NAME
+    }
+
+    fn decode<'i, I>(values: &mut I) -> Result<Self, headers::Error>
+    where
+        I: Iterator<Item = &'i HeaderValue>,
+    {
+        if let Ok(s) = values.next().ok_or_else(headers::Error::invalid)?.to_str() {
+            return Ok(XAdminToken(s.to_string()));
+        }
+        Err(headers::Error::invalid())
+    }
+
+    fn encode<E>(&self, values: &mut E)
+    where
+        E: Extend<HeaderValue>,
+    {
+        if let Ok(value) = HeaderValue::from_str(&self.0) {
+            values.extend(std::iter::once(value));
+        }
+    }
+}
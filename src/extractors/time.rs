@@ -20,3 +20,15 @@ pub mod json_time {
             .naive_utc())
     }
 }
+
+pub mod json_optional_time {
+    use super::*;
+    use serde::{Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        time: &Option<NaiveDateTime>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        time.map(|time| time.to_string()).serialize(serializer)
+    }
+}
@@ -0,0 +1,277 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::anyhow;
+use sqlx::{postgres::PgConnection, PgPool};
+use tracing::{info, warn};
+
+use crate::{
+    database::{self, SERVICE_LOCK_ID},
+    domain::{documents::Document, resources::Resource},
+    storage::{
+        self, avatar_key, document_key, object_key, resource_key, BlobStore, OBJECTS_PREFIX,
+        PROJECTS_PREFIX, USERS_PREFIX,
+    },
+};
+
+/// Orphans are moved below this prefix instead of being deleted, and the checker skips it
+pub const QUARANTINE_PREFIX: &str = "quarantine";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExpectedKind {
+    /// Recreated as an empty file on repair
+    File,
+    /// Content-addressed, an empty file would not match its hash
+    Object,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct FsckReport {
+    pub missing: Vec<(String, ExpectedKind)>,
+    pub orphan_files: Vec<String>,
+    pub orphan_directories: Vec<String>,
+}
+
+impl FsckReport {
+    pub fn is_clean(&self) -> bool {
+        self.missing.is_empty()
+            && self.orphan_files.is_empty()
+            && self.orphan_directories.is_empty()
+    }
+
+    pub fn is_orphan(&self, key: &str) -> bool {
+        self.orphan_files.iter().any(|orphan| orphan == key)
+            || self
+                .orphan_directories
+                .iter()
+                .any(|directory| key.starts_with(&format!("{}/", directory)))
+    }
+}
+
+/// Returns the directory to report instead of the key when no project owns it
+fn orphan_directory(key: &str, project_ids: &HashSet<i32>) -> Option<String> {
+    match key.split('/').collect::<Vec<_>>().as_slice() {
+        [PROJECTS_PREFIX, project_id, _, ..] => match project_id.parse::<i32>() {
+            Ok(project_id) if project_ids.contains(&project_id) => None,
+            _ => Some(format!("{}/{}", PROJECTS_PREFIX, project_id)),
+        },
+        [PROJECTS_PREFIX, ..] | [OBJECTS_PREFIX, ..] | [USERS_PREFIX, ..] | [_] => None,
+        // anything else predates the id-based layout or was never written by the service
+        [directory, ..] => Some(directory.to_string()),
+        [] => None,
+    }
+}
+
+/// Compares the keys the database refers to with the keys present in storage.
+/// Keys inside a directory no project owns are reported once, as an orphan directory.
+pub fn compare(
+    expected: &BTreeMap<String, ExpectedKind>,
+    project_ids: &HashSet<i32>,
+    stored: &[String],
+) -> FsckReport {
+    let stored: BTreeSet<&str> = stored
+        .iter()
+        .map(String::as_str)
+        .filter(|key| !key.starts_with(&format!("{}/", QUARANTINE_PREFIX)))
+        .collect();
+
+    let missing = expected
+        .iter()
+        .filter(|(key, _)| !stored.contains(key.as_str()))
+        .map(|(key, kind)| (key.clone(), kind.clone()))
+        .collect();
+
+    let mut orphan_files = Vec::new();
+    let mut orphan_directories = BTreeSet::new();
+    for key in stored {
+        if expected.contains_key(key) {
+            continue;
+        }
+
+        match orphan_directory(key, project_ids) {
+            Some(directory) => {
+                orphan_directories.insert(directory);
+            }
+            None => orphan_files.push(key.to_string()),
+        }
+    }
+
+    FsckReport {
+        missing,
+        orphan_files,
+        orphan_directories: orphan_directories.into_iter().collect(),
+    }
+}
+
+/// Returns every key the database refers to, along with the existing project ids
+pub async fn expected_keys(
+    pool: &PgPool,
+) -> Result<(BTreeMap<String, ExpectedKind>, HashSet<i32>), sqlx::Error> {
+    let project_ids = sqlx::query_scalar::<_, i32>("SELECT project_id FROM projects")
+        .fetch_all(pool)
+        .await?;
+
+    let documents = sqlx::query_as::<_, Document>(
+        "SELECT document_id, project_id, name, folder_id FROM documents",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let resources = sqlx::query_as::<_, Resource>(
+        "SELECT resource_id, project_id, name, folder_id, content_hash FROM resources",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let object_hashes = sqlx::query_scalar::<_, String>(
+        "
+        SELECT content_hash FROM blobs
+        UNION
+        SELECT content_hash FROM snapshot_files
+    ",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let avatar_user_ids = sqlx::query_scalar::<_, i32>(
+        "SELECT user_id FROM users WHERE avatar_content_type IS NOT NULL",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut expected = BTreeMap::new();
+    for document in &documents {
+        expected.insert(document_key(document), ExpectedKind::File);
+    }
+    for resource in &resources {
+        let kind = match resource.content_hash {
+            Some(_) => ExpectedKind::Object,
+            None => ExpectedKind::File,
+        };
+        expected.insert(resource_key(resource), kind);
+    }
+    for content_hash in &object_hashes {
+        expected.insert(object_key(content_hash), ExpectedKind::Object);
+    }
+    for user_id in avatar_user_ids {
+        expected.insert(avatar_key(user_id), ExpectedKind::File);
+    }
+
+    Ok((expected, project_ids.into_iter().collect()))
+}
+
+async fn repair(store: &dyn BlobStore, report: &FsckReport) -> anyhow::Result<FsckReport> {
+    let mut remaining = FsckReport::default();
+
+    for (key, kind) in &report.missing {
+        match kind {
+            ExpectedKind::File => match store.put(key, b"").await {
+                Ok(()) => info!(key, "Recreated empty file"),
+                Err(err) => {
+                    warn!(key, ?err, "Could not recreate file");
+                    remaining.missing.push((key.clone(), kind.clone()));
+                }
+            },
+            ExpectedKind::Object => {
+                warn!(key, "Cannot recreate content-addressed object");
+                remaining.missing.push((key.clone(), kind.clone()));
+            }
+        }
+    }
+
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let orphan_keys = store
+        .list("")
+        .await
+        .map_err(|err| anyhow!("Could not list storage: {:?}", err))?
+        .into_iter()
+        .filter(|key| report.is_orphan(key));
+
+    for key in orphan_keys {
+        let target = format!("{}/{}/{}", QUARANTINE_PREFIX, timestamp, key);
+        match store.rename(&key, &target).await {
+            Ok(()) => info!(key, target, "Quarantined orphan"),
+            Err(err) => {
+                warn!(key, ?err, "Could not quarantine orphan");
+                remaining.orphan_files.push(key);
+            }
+        }
+    }
+
+    Ok(remaining)
+}
+
+/// Takes the service lock exclusively, `None` while any service is running.
+/// Repairs need it because a running service writes files before committing their rows,
+/// so files it is writing would look like orphans.
+pub async fn try_repair_lock(pool: &PgPool) -> Result<Option<PgConnection>, sqlx::Error> {
+    let mut connection = pool.acquire().await?.detach();
+    let locked = sqlx::query_scalar::<_, bool>("SELECT pg_try_advisory_lock($1)")
+        .bind(SERVICE_LOCK_ID)
+        .fetch_one(&mut connection)
+        .await?;
+
+    Ok(locked.then_some(connection))
+}
+
+fn log_report(report: &FsckReport) {
+    for (key, kind) in &report.missing {
+        warn!(key, ?kind, "Missing file");
+    }
+    for key in &report.orphan_files {
+        warn!(key, "Orphan file");
+    }
+    for directory in &report.orphan_directories {
+        warn!(directory, "Orphan directory");
+    }
+
+    info!(
+        missing = report.missing.len(),
+        orphan_files = report.orphan_files.len(),
+        orphan_directories = report.orphan_directories.len(),
+        "Consistency check finished"
+    );
+}
+
+/// Checks that storage matches the database, optionally repairing what it can
+#[tracing::instrument]
+pub async fn run(repair_issues: bool) -> anyhow::Result<()> {
+    tracing_subscriber::fmt().init();
+
+    let store = storage::from_config()?;
+    let pool = database::create_conn_pool().await?;
+    let _repair_lock = match repair_issues {
+        true => match try_repair_lock(&pool).await? {
+            Some(connection) => Some(connection),
+            None => return Err(anyhow!("Stop every running service before repairing")),
+        },
+        false => None,
+    };
+
+    let store = storage::encryption::with_encryption(store, &pool).await?;
+    storage::migrate_storage(&pool, store.as_ref()).await?;
+
+    let (expected, project_ids) = expected_keys(&pool).await?;
+    let stored = store
+        .list("")
+        .await
+        .map_err(|err| anyhow!("Could not list storage: {:?}", err))?;
+
+    let mut report = compare(&expected, &project_ids, &stored);
+    log_report(&report);
+
+    if repair_issues && !report.is_clean() {
+        report = repair(store.as_ref(), &report).await?;
+        log_report(&report);
+    }
+
+    match report.is_clean() {
+        true => Ok(()),
+        false => Err(anyhow!("Storage and database are inconsistent")),
+    }
+}
+
+#[cfg(test)]
+mod tests;
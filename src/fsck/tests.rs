@@ -0,0 +1,95 @@
+use crate::storage::MemoryBlobStore;
+
+use super::*;
+
+fn expected() -> BTreeMap<String, ExpectedKind> {
+    BTreeMap::from([
+        (String::from("projects/1/documents/1"), ExpectedKind::File),
+        (String::from("projects/1/documents/2"), ExpectedKind::File),
+        (object_key(&"ab".repeat(32)), ExpectedKind::Object),
+    ])
+}
+
+#[test]
+fn compare_reports_missing_and_orphans() {
+    let stored = vec![
+        String::from("projects/1/documents/1"),
+        String::from("projects/1/documents/9"),
+        String::from("projects/7/documents/3"),
+        String::from("projects/7/resources/4"),
+        String::from("1/main.tex"),
+        String::from("quarantine/1700000000/projects/9/documents/5"),
+    ];
+
+    let report = compare(&expected(), &HashSet::from([1]), &stored);
+
+    assert_eq!(
+        vec![
+            (object_key(&"ab".repeat(32)), ExpectedKind::Object),
+            (String::from("projects/1/documents/2"), ExpectedKind::File),
+        ],
+        report.missing
+    );
+    assert_eq!(
+        vec![String::from("projects/1/documents/9")],
+        report.orphan_files
+    );
+    // files of deleted projects and of the name-based layout are reported per directory
+    assert_eq!(
+        vec![String::from("1"), String::from("projects/7")],
+        report.orphan_directories
+    );
+}
+
+#[test]
+fn compare_reports_avatars_of_users_without_one_as_files() {
+    let mut expected = expected();
+    expected.insert(avatar_key(1), ExpectedKind::File);
+    let mut stored: Vec<String> = expected.keys().cloned().collect();
+    stored.push(avatar_key(2));
+
+    let report = compare(&expected, &HashSet::from([1]), &stored);
+
+    assert_eq!(vec![avatar_key(2)], report.orphan_files);
+    assert!(report.orphan_directories.is_empty());
+}
+
+#[test]
+fn compare_consistent_storage_is_clean() {
+    let stored: Vec<String> = expected().into_keys().collect();
+
+    assert!(compare(&expected(), &HashSet::from([1]), &stored).is_clean());
+}
+
+#[tokio::test]
+async fn repair_recreates_files_and_quarantines_orphans() {
+    let store = MemoryBlobStore::default();
+    store.put("projects/1/documents/1", b"Hello").await.unwrap();
+    store.put("projects/1/documents/9", b"").await.unwrap();
+    store.put("projects/7/documents/3", b"").await.unwrap();
+
+    let stored = store.list("").await.unwrap();
+    let report = compare(&expected(), &HashSet::from([1]), &stored);
+    let remaining = repair(&store, &report).await.unwrap();
+
+    assert!(store.exists("projects/1/documents/2").await.unwrap());
+    assert!(!store.exists("projects/1/documents/9").await.unwrap());
+    assert!(!store.exists("projects/7/documents/3").await.unwrap());
+    assert_eq!(2, store.list(QUARANTINE_PREFIX).await.unwrap().len());
+    // the content of a missing object cannot be brought back
+    assert_eq!(
+        vec![(object_key(&"ab".repeat(32)), ExpectedKind::Object)],
+        remaining.missing
+    );
+    assert!(remaining.orphan_files.is_empty());
+}
+
+#[sqlx::test]
+async fn try_repair_lock_refused_while_service_runs(pool: PgPool) {
+    // the lock is released once the connection closes, which the service waits for
+    assert!(try_repair_lock(&pool).await.unwrap().is_some());
+
+    let _service_lock = database::hold_service_lock(&pool).await.unwrap();
+
+    assert!(try_repair_lock(&pool).await.unwrap().is_none());
+}
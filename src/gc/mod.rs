@@ -0,0 +1,183 @@
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use anyhow::anyhow;
+use sqlx::{types::chrono::Utc, PgPool};
+use tokio::sync::Mutex;
+use tracing::{error, info, warn};
+
+use crate::{
+    constants::{GC_GRACE_PERIOD_IN_SECONDS, GC_INTERVAL_IN_SECONDS},
+    domain::gc::GcReport,
+    fsck::{compare, expected_keys},
+    storage::{object_key, BlobError, SharedBlobStore},
+};
+
+#[derive(Default)]
+struct CollectorState {
+    /// Orphaned keys with the moment they were first seen unreferenced,
+    /// kept in memory only, so every process starts the grace period over
+    candidates: HashMap<String, Instant>,
+    last_report: Option<GcReport>,
+}
+
+/// Removes stored contents nothing refers to, once they stayed unreferenced for the grace period
+#[derive(Clone)]
+pub struct GarbageCollector {
+    pool: PgPool,
+    store: SharedBlobStore,
+    grace_period: Duration,
+    // held for a whole run, so periodic and on-demand runs never overlap
+    state: Arc<Mutex<CollectorState>>,
+}
+
+/// Tracks the current orphans and returns those that have been orphaned for longer than the grace period
+fn expire_candidates(
+    candidates: &mut HashMap<String, Instant>,
+    orphans: Vec<String>,
+    now: Instant,
+    grace_period: Duration,
+) -> Vec<String> {
+    let mut current = HashMap::new();
+    for key in orphans {
+        let first_seen = candidates.get(&key).copied().unwrap_or(now);
+        current.insert(key, first_seen);
+    }
+    *candidates = current;
+
+    let expired: Vec<String> = candidates
+        .iter()
+        .filter(|(_, first_seen)| now.duration_since(**first_seen) >= grace_period)
+        .map(|(key, _)| key.clone())
+        .collect();
+    for key in &expired {
+        candidates.remove(key);
+    }
+
+    expired
+}
+
+impl GarbageCollector {
+    pub fn new(pool: &PgPool, store: &SharedBlobStore) -> Self {
+        Self {
+            pool: pool.clone(),
+            store: store.clone(),
+            grace_period: Duration::from_secs(*GC_GRACE_PERIOD_IN_SECONDS),
+            state: Arc::default(),
+        }
+    }
+
+    pub async fn last_report(&self) -> Option<GcReport> {
+        self.state.lock().await.last_report.clone()
+    }
+
+    pub async fn run_periodically(self) {
+        let mut interval = tokio::time::interval(Duration::from_secs(*GC_INTERVAL_IN_SECONDS));
+        loop {
+            interval.tick().await;
+            if let Err(err) = self.collect().await {
+                error!(%err, "Garbage collection failed");
+            }
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn collect(&self) -> anyhow::Result<GcReport> {
+        let mut state = self.state.lock().await;
+        let started_at = Utc::now().naive_utc();
+
+        let removed_blobs = self.collect_blobs().await?;
+        let removed_orphans = self.collect_orphans(&mut state.candidates).await?;
+
+        let report = GcReport {
+            started_at,
+            removed_blobs,
+            removed_orphans,
+            pending_orphans: state.candidates.len(),
+        };
+        info!(
+            removed_blobs = report.removed_blobs.len(),
+            removed_orphans = report.removed_orphans.len(),
+            pending_orphans = report.pending_orphans,
+            "Garbage collection finished"
+        );
+
+        state.last_report = Some(report.clone());
+        Ok(report)
+    }
+
+    /// Drops contents whose reference count stayed at zero for the grace period.
+    /// Rows are locked while their objects are deleted, so a concurrent write of
+    /// the same content waits and then stores the object again.
+    async fn collect_blobs(&self) -> anyhow::Result<Vec<String>> {
+        let mut tx = self.pool.begin().await?;
+
+        let unreferenced_sql = "
+            SELECT content_hash
+            FROM blobs
+            WHERE ref_count = 0 AND updated_at < NOW() - $1 * INTERVAL '1 second'
+            FOR UPDATE SKIP LOCKED
+        ";
+        let unreferenced = sqlx::query_scalar::<_, String>(unreferenced_sql)
+            .bind(self.grace_period.as_secs() as f64)
+            .fetch_all(&mut tx)
+            .await?;
+
+        let mut removed = Vec::new();
+        for content_hash in unreferenced {
+            match self.store.delete(&object_key(&content_hash)).await {
+                Ok(()) | Err(BlobError::Missing) => {
+                    info!(content_hash, "Removed unreferenced blob");
+                    removed.push(content_hash);
+                }
+                Err(err) => warn!(content_hash, ?err, "Could not remove unreferenced blob"),
+            }
+        }
+
+        sqlx::query("DELETE FROM blobs WHERE content_hash = ANY($1)")
+            .bind(&removed)
+            .execute(&mut tx)
+            .await?;
+        tx.commit().await?;
+
+        Ok(removed)
+    }
+
+    /// Removes files left behind by rolled back transactions
+    async fn collect_orphans(
+        &self,
+        candidates: &mut HashMap<String, Instant>,
+    ) -> anyhow::Result<Vec<String>> {
+        let (expected, project_ids) = expected_keys(&self.pool).await?;
+        let stored = self
+            .store
+            .list("")
+            .await
+            .map_err(|err| anyhow!("Could not list storage: {:?}", err))?;
+
+        let report = compare(&expected, &project_ids, &stored);
+        let orphans = stored
+            .into_iter()
+            .filter(|key| report.is_orphan(key))
+            .collect();
+
+        let mut removed = Vec::new();
+        for key in expire_candidates(candidates, orphans, Instant::now(), self.grace_period) {
+            match self.store.delete(&key).await {
+                Ok(()) | Err(BlobError::Missing) => {
+                    info!(key, "Removed orphaned blob");
+                    removed.push(key);
+                }
+                Err(err) => warn!(key, ?err, "Could not remove orphaned blob"),
+            }
+        }
+
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,49 @@
+use super::*;
+
+fn keys(keys: &[&str]) -> Vec<String> {
+    keys.iter().map(|key| key.to_string()).collect()
+}
+
+#[test]
+fn expire_candidates_waits_for_grace_period() {
+    let mut candidates = HashMap::new();
+    let start = Instant::now();
+    let grace_period = Duration::from_secs(60);
+
+    let expired = expire_candidates(
+        &mut candidates,
+        keys(&["1/leftover.tex"]),
+        start,
+        grace_period,
+    );
+    assert!(expired.is_empty());
+
+    let expired = expire_candidates(
+        &mut candidates,
+        keys(&["1/leftover.tex", "7/main.tex"]),
+        start + Duration::from_secs(61),
+        grace_period,
+    );
+    assert_eq!(keys(&["1/leftover.tex"]), expired);
+    assert!(candidates.contains_key("7/main.tex"));
+    assert!(!candidates.contains_key("1/leftover.tex"));
+}
+
+#[test]
+fn expire_candidates_forgets_keys_referenced_again() {
+    let mut candidates = HashMap::new();
+    let start = Instant::now();
+    let grace_period = Duration::from_secs(60);
+
+    // a write that was still in flight during the first run has committed since
+    expire_candidates(&mut candidates, keys(&["1/main.tex"]), start, grace_period);
+    expire_candidates(&mut candidates, Vec::new(), start, grace_period);
+
+    let expired = expire_candidates(
+        &mut candidates,
+        keys(&["1/main.tex"]),
+        start + Duration::from_secs(61),
+        grace_period,
+    );
+    assert!(expired.is_empty());
+}
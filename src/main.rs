@@ -1,24 +1,34 @@
-use std::fs;
+use std::{env, net::SocketAddr, process};
 
 use tracing::{error, info};
 
+mod auth;
 mod constants;
 mod control;
 mod database;
+mod diff;
 mod domain;
 mod extractors;
-mod filesystem;
+mod fsck;
+mod gc;
+mod notify;
+mod presence;
 mod repository;
 mod routing;
+mod storage;
 mod validation;
 
-use constants::{FILE_DIR_PATH, SERVER_URL};
+use auth::Authenticator;
+use constants::SERVER_URL;
+use gc::GarbageCollector;
+use presence::PresenceHub;
+use repository::sessions::PgSessionRepository;
 
 #[tracing::instrument]
 pub async fn run() -> anyhow::Result<()> {
     tracing_subscriber::fmt().init();
 
-    fs::create_dir_all(FILE_DIR_PATH.as_path())?;
+    let store = storage::from_config()?;
 
     let pool = match database::create_conn_pool().await {
         Ok(pool) => pool,
@@ -28,9 +38,38 @@ pub async fn run() -> anyhow::Result<()> {
         }
     };
 
+    // waits for a running `fsck --repair`, and keeps new ones out while the server runs
+    let _service_lock = database::hold_service_lock(&pool).await?;
+
+    let store = storage::encryption::with_encryption(store, &pool).await?;
+    storage::migrate_storage(&pool, store.as_ref()).await?;
+
+    let presence_hub = PresenceHub::new();
+    tokio::spawn(presence_hub.clone().run_idle_sweeper());
+
+    let sessions_repository = PgSessionRepository::new(&pool);
+    tokio::spawn(control::sessions::run_session_purge(sessions_repository));
+
+    let notifier = notify::from_config();
+    let authenticator = Authenticator::from_config()?;
+
+    let collector = GarbageCollector::new(&pool, &store);
+    tokio::spawn(collector.clone().run_periodically());
+
     info!("Running server!");
     axum::Server::try_bind(&SERVER_URL)?
-        .serve(routing::main_router(&pool).into_make_service())
+        .serve(
+            routing::main_router(
+                &pool,
+                &store,
+                &presence_hub,
+                &collector,
+                &notifier,
+                &authenticator,
+            )
+            // the peer address is needed to check trusted proxies
+            .into_make_service_with_connect_info::<SocketAddr>(),
+        )
         .await
         .map_err(anyhow::Error::from)
 }
@@ -38,7 +77,17 @@ pub async fn run() -> anyhow::Result<()> {
 #[tokio::main]
 #[tracing::instrument]
 async fn main() {
-    if let Err(err) = run().await {
+    let result = match env::args().nth(1).as_deref() {
+        Some("fsck") => fsck::run(env::args().skip(2).any(|arg| arg == "--repair")).await,
+        Some("rotate-keys") => match env::args().nth(2) {
+            Some(new_key_file) => storage::encryption::rotate_master_key(&new_key_file).await,
+            None => Err(anyhow::anyhow!("Usage: rotate-keys <new-key-file>")),
+        },
+        _ => run().await,
+    };
+
+    if let Err(err) = result {
         error!(%err);
+        process::exit(1);
     }
 }
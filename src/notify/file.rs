@@ -0,0 +1,54 @@
+use std::path::PathBuf;
+
+use axum::async_trait;
+use tokio::{fs::OpenOptions, io::AsyncWriteExt, sync::Mutex};
+use tracing::error;
+
+use super::{Notification, Notifier, NotifyError};
+
+/// Appends notifications to a file, so tests and local setups can read what would have been sent
+#[derive(Debug)]
+pub struct FileNotifier {
+    path: PathBuf,
+    // keeps concurrent notifications from interleaving
+    lock: Mutex<()>,
+}
+
+impl FileNotifier {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            lock: Mutex::new(()),
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for FileNotifier {
+    async fn send(&self, notification: &Notification) -> Result<(), NotifyError> {
+        let entry = format!(
+            "To: {}\nSubject: {}\n\n{}\n\n",
+            notification.to, notification.subject, notification.body
+        );
+
+        let _guard = self.lock.lock().await;
+        let result = match OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await
+        {
+            // tokio files finish writes in the background unless flushed
+            Ok(mut file) => match file.write_all(entry.as_bytes()).await {
+                Ok(()) => file.flush().await,
+                Err(err) => Err(err),
+            },
+            Err(err) => Err(err),
+        };
+
+        result.map_err(|err| {
+            error!(%err, path = ?self.path, "Could not write notification");
+            NotifyError::Unknown
+        })
+    }
+}
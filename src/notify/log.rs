@@ -0,0 +1,21 @@
+use axum::async_trait;
+use tracing::info;
+
+use super::{Notification, Notifier, NotifyError};
+
+/// Writes notifications to the service log, meant for local development only
+#[derive(Debug, Clone, Default)]
+pub struct LogNotifier;
+
+#[async_trait]
+impl Notifier for LogNotifier {
+    async fn send(&self, notification: &Notification) -> Result<(), NotifyError> {
+        info!(
+            to = notification.to,
+            subject = notification.subject,
+            body = notification.body,
+            "Notification"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,61 @@
+mod file;
+mod log;
+
+use std::{fmt::Debug, str::FromStr, sync::Arc};
+
+use axum::async_trait;
+use mockall::automock;
+
+use crate::constants::{NOTIFIER, NOTIFIER_FILE_PATH};
+
+pub use self::{file::FileNotifier, log::LogNotifier};
+
+#[derive(Debug)]
+pub enum NotifyError {
+    Unknown,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Notification {
+    /// Email address of the recipient
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+/// Delivers messages to users outside of the application
+#[automock]
+#[async_trait]
+pub trait Notifier: Debug + Send + Sync {
+    async fn send(&self, notification: &Notification) -> Result<(), NotifyError>;
+}
+
+pub type SharedNotifier = Arc<dyn Notifier>;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NotifierKind {
+    Log,
+    File,
+}
+
+impl FromStr for NotifierKind {
+    type Err = String;
+
+    fn from_str(kind: &str) -> Result<Self, Self::Err> {
+        match kind {
+            "log" => Ok(Self::Log),
+            "file" => Ok(Self::File),
+            _ => Err(format!("Unknown notifier {}", kind)),
+        }
+    }
+}
+
+pub fn from_config() -> SharedNotifier {
+    match *NOTIFIER {
+        NotifierKind::Log => Arc::new(LogNotifier),
+        NotifierKind::File => Arc::new(FileNotifier::new(NOTIFIER_FILE_PATH.clone())),
+    }
+}
+
+#[cfg(test)]
+mod tests;
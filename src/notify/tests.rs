@@ -0,0 +1,35 @@
+use std::env;
+
+use rand::{distributions::Alphanumeric, Rng};
+
+use super::*;
+
+#[tokio::test]
+async fn file_notifier_appends_notifications() {
+    let name: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(16)
+        .map(char::from)
+        .collect();
+    let path = env::temp_dir().join(format!("notifications-{}", name));
+    let notifier = FileNotifier::new(path.clone());
+
+    for subject in ["First", "Second"] {
+        notifier
+            .send(&Notification {
+                to: String::from("john@email.com"),
+                subject: String::from(subject),
+                body: String::from("Hello"),
+            })
+            .await
+            .unwrap();
+    }
+
+    let written = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(
+        "To: john@email.com\nSubject: First\n\nHello\n\nTo: john@email.com\nSubject: Second\n\nHello\n\n",
+        written
+    );
+}
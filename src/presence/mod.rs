@@ -0,0 +1,182 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use sqlx::types::chrono::{NaiveDateTime, Utc};
+use tokio::sync::broadcast;
+use tracing::info;
+
+use crate::{
+    constants::{PRESENCE_IDLE_TIMEOUT_IN_SECONDS, PRESENCE_SWEEP_INTERVAL_IN_SECONDS},
+    domain::{
+        presence::{Collaborator, Cursor, PresenceEvent},
+        users::User,
+    },
+};
+
+const CHANNEL_CAPACITY: usize = 128;
+
+struct Member {
+    email: String,
+    display_name: Option<String>,
+    connections: usize,
+    cursors: HashMap<i32, Cursor>,
+    idle: bool,
+    last_active: NaiveDateTime,
+}
+
+struct ProjectChannel {
+    sender: broadcast::Sender<PresenceEvent>,
+    members: HashMap<i32, Member>,
+}
+
+/// In-memory registry of who is connected to which project
+#[derive(Clone, Default)]
+pub struct PresenceHub {
+    projects: Arc<Mutex<HashMap<i32, ProjectChannel>>>,
+}
+
+impl PresenceHub {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[tracing::instrument(skip(self, user), fields(user_id = user.id))]
+    pub fn join(&self, project_id: i32, user: &User) -> broadcast::Receiver<PresenceEvent> {
+        let user_id = user.id;
+        let mut projects = self.projects.lock().unwrap();
+        let channel = projects
+            .entry(project_id)
+            .or_insert_with(|| ProjectChannel {
+                sender: broadcast::channel(CHANNEL_CAPACITY).0,
+                members: HashMap::new(),
+            });
+        let receiver = channel.sender.subscribe();
+
+        let member = channel.members.entry(user_id).or_insert_with(|| Member {
+            email: user.email.clone(),
+            display_name: user.display_name.clone(),
+            connections: 0,
+            cursors: HashMap::new(),
+            idle: false,
+            last_active: Utc::now().naive_utc(),
+        });
+        member.connections += 1;
+
+        if member.connections == 1 {
+            info!("User joined project");
+            let _ = channel.sender.send(PresenceEvent::Join {
+                user_id,
+                email: user.email.clone(),
+                display_name: user.display_name.clone(),
+            });
+        }
+
+        receiver
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub fn leave(&self, project_id: i32, user_id: i32) {
+        let mut projects = self.projects.lock().unwrap();
+        let Some(channel) = projects.get_mut(&project_id) else {
+            return;
+        };
+
+        if let Some(member) = channel.members.get_mut(&user_id) {
+            member.connections -= 1;
+            if member.connections == 0 {
+                info!("User left project");
+                channel.members.remove(&user_id);
+                let _ = channel.sender.send(PresenceEvent::Leave { user_id });
+            }
+        }
+
+        if channel.members.is_empty() {
+            projects.remove(&project_id);
+        }
+    }
+
+    pub fn update_cursor(&self, project_id: i32, user_id: i32, cursor: Cursor) {
+        self.with_member(project_id, user_id, |member, sender| {
+            member.cursors.insert(cursor.document_id, cursor.clone());
+            let _ = sender.send(PresenceEvent::Cursor { user_id, cursor });
+        });
+    }
+
+    pub fn touch(&self, project_id: i32, user_id: i32) {
+        self.with_member(project_id, user_id, |_, _| ());
+    }
+
+    pub fn snapshot(&self, project_id: i32) -> Vec<Collaborator> {
+        let projects = self.projects.lock().unwrap();
+        let Some(channel) = projects.get(&project_id) else {
+            return Vec::new();
+        };
+
+        let mut collaborators: Vec<Collaborator> = channel
+            .members
+            .iter()
+            .map(|(user_id, member)| Collaborator {
+                user_id: *user_id,
+                email: member.email.clone(),
+                display_name: member.display_name.clone(),
+                cursors: member.cursors.values().cloned().collect(),
+                idle: member.idle,
+                last_active: member.last_active,
+            })
+            .collect();
+        collaborators.sort_by_key(|collaborator| collaborator.user_id);
+        collaborators
+    }
+
+    /// Marks members without recent activity as idle
+    pub fn sweep_idle(&self) {
+        let cutoff = Utc::now().timestamp() - *PRESENCE_IDLE_TIMEOUT_IN_SECONDS;
+        let mut projects = self.projects.lock().unwrap();
+
+        for channel in projects.values_mut() {
+            for (user_id, member) in channel.members.iter_mut() {
+                if !member.idle && member.last_active.timestamp() < cutoff {
+                    member.idle = true;
+                    let _ = channel
+                        .sender
+                        .send(PresenceEvent::Idle { user_id: *user_id });
+                }
+            }
+        }
+    }
+
+    pub async fn run_idle_sweeper(self) {
+        let mut interval =
+            tokio::time::interval(Duration::from_secs(*PRESENCE_SWEEP_INTERVAL_IN_SECONDS));
+        loop {
+            interval.tick().await;
+            self.sweep_idle();
+        }
+    }
+
+    fn with_member<F>(&self, project_id: i32, user_id: i32, f: F)
+    where
+        F: FnOnce(&mut Member, &broadcast::Sender<PresenceEvent>),
+    {
+        let mut projects = self.projects.lock().unwrap();
+        let Some(channel) = projects.get_mut(&project_id) else {
+            return;
+        };
+        let Some(member) = channel.members.get_mut(&user_id) else {
+            return;
+        };
+
+        member.last_active = Utc::now().naive_utc();
+        if member.idle {
+            member.idle = false;
+            let _ = channel.sender.send(PresenceEvent::Active { user_id });
+        }
+        f(member, &channel.sender);
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,162 @@
+use sqlx::types::chrono::NaiveDateTime;
+use tokio::sync::broadcast::error::TryRecvError;
+
+use crate::domain::presence::Selection;
+
+use super::*;
+
+fn mock_project_id() -> i32 {
+    1
+}
+
+fn mock_user(user_id: i32) -> User {
+    User {
+        id: user_id,
+        email: format!("user{}@email.com", user_id),
+        display_name: None,
+        password_hash: String::from("hash"),
+        verified_at: None,
+    }
+}
+
+fn mock_cursor() -> Cursor {
+    Cursor {
+        document_id: 3,
+        position: 42,
+        selection: Some(Selection {
+            anchor: 40,
+            head: 42,
+        }),
+    }
+}
+
+fn drain(receiver: &mut broadcast::Receiver<PresenceEvent>) -> Vec<PresenceEvent> {
+    let mut events = Vec::new();
+    loop {
+        match receiver.try_recv() {
+            Ok(event) => events.push(event),
+            Err(TryRecvError::Empty) => return events,
+            Err(err) => panic!("unexpected receive error: {}", err),
+        }
+    }
+}
+
+fn backdate(hub: &PresenceHub, project_id: i32, user_id: i32) {
+    let mut projects = hub.projects.lock().unwrap();
+    let member = projects
+        .get_mut(&project_id)
+        .unwrap()
+        .members
+        .get_mut(&user_id)
+        .unwrap();
+    member.last_active = NaiveDateTime::from_timestamp_opt(0, 0).unwrap();
+}
+
+#[test]
+fn join_several_tabs_announced_once() {
+    let hub = PresenceHub::new();
+    let mut observer = hub.join(mock_project_id(), &mock_user(1));
+    drain(&mut observer);
+
+    let _first_tab = hub.join(mock_project_id(), &mock_user(2));
+    let _second_tab = hub.join(mock_project_id(), &mock_user(2));
+
+    assert_eq!(
+        drain(&mut observer),
+        vec![PresenceEvent::Join {
+            user_id: 2,
+            email: String::from("user2@email.com"),
+            display_name: None,
+        }]
+    );
+    assert_eq!(hub.snapshot(mock_project_id()).len(), 2);
+}
+
+#[test]
+fn leave_last_tab_announced_once() {
+    let hub = PresenceHub::new();
+    let mut observer = hub.join(mock_project_id(), &mock_user(1));
+    let _first_tab = hub.join(mock_project_id(), &mock_user(2));
+    let _second_tab = hub.join(mock_project_id(), &mock_user(2));
+    drain(&mut observer);
+
+    hub.leave(mock_project_id(), 2);
+    assert_eq!(drain(&mut observer), vec![]);
+    assert_eq!(hub.snapshot(mock_project_id()).len(), 2);
+
+    hub.leave(mock_project_id(), 2);
+    assert_eq!(
+        drain(&mut observer),
+        vec![PresenceEvent::Leave { user_id: 2 }]
+    );
+    assert_eq!(hub.snapshot(mock_project_id()).len(), 1);
+}
+
+#[test]
+fn leave_everyone_project_forgotten() {
+    let hub = PresenceHub::new();
+    let _receiver = hub.join(mock_project_id(), &mock_user(1));
+
+    hub.leave(mock_project_id(), 1);
+
+    assert!(hub.projects.lock().unwrap().is_empty());
+    assert_eq!(hub.snapshot(mock_project_id()), vec![]);
+}
+
+#[test]
+fn snapshot_new_joiner_sees_cursors() {
+    let hub = PresenceHub::new();
+    let _receiver = hub.join(mock_project_id(), &mock_user(1));
+    hub.update_cursor(mock_project_id(), 1, mock_cursor());
+
+    let _receiver = hub.join(mock_project_id(), &mock_user(2));
+    let snapshot = hub.snapshot(mock_project_id());
+
+    assert_eq!(snapshot.len(), 2);
+    assert_eq!(snapshot[0].user_id, 1);
+    assert_eq!(snapshot[0].cursors, vec![mock_cursor()]);
+    assert!(!snapshot[0].idle);
+    assert_eq!(snapshot[1].user_id, 2);
+    assert_eq!(snapshot[1].cursors, vec![]);
+}
+
+#[test]
+fn sweep_idle_marks_inactive_members() {
+    let hub = PresenceHub::new();
+    let mut observer = hub.join(mock_project_id(), &mock_user(1));
+    let _receiver = hub.join(mock_project_id(), &mock_user(2));
+    drain(&mut observer);
+    backdate(&hub, mock_project_id(), 2);
+
+    hub.sweep_idle();
+    hub.sweep_idle();
+
+    assert_eq!(
+        drain(&mut observer),
+        vec![PresenceEvent::Idle { user_id: 2 }]
+    );
+    let snapshot = hub.snapshot(mock_project_id());
+    assert!(!snapshot[0].idle);
+    assert!(snapshot[1].idle);
+}
+
+#[test]
+fn touch_idle_member_active_again() {
+    let hub = PresenceHub::new();
+    let mut observer = hub.join(mock_project_id(), &mock_user(1));
+    drain(&mut observer);
+    backdate(&hub, mock_project_id(), 1);
+    hub.sweep_idle();
+
+    hub.touch(mock_project_id(), 1);
+    hub.touch(mock_project_id(), 1);
+
+    assert_eq!(
+        drain(&mut observer),
+        vec![
+            PresenceEvent::Idle { user_id: 1 },
+            PresenceEvent::Active { user_id: 1 },
+        ]
+    );
+    assert!(!hub.snapshot(mock_project_id())[0].idle);
+}
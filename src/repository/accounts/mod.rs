@@ -0,0 +1,270 @@
+use axum::async_trait;
+use mockall::automock;
+use sqlx::{PgPool, Postgres, Transaction};
+use tracing::{error, info};
+
+use crate::{
+    domain::{
+        accounts::{AccountDeletion, AccountDeletionReport},
+        events::ProjectEventType,
+        usage::QuotaExceeded,
+    },
+    repository::{
+        events::insert_event,
+        projects::delete_projects,
+        usage::{check_quota, QuotaError},
+    },
+};
+
+pub enum AccountDeleteError {
+    Missing,
+    /// The project is not owned by the user or the new owner does not collaborate on it
+    InvalidTransfer(i32),
+    QuotaExceeded(QuotaExceeded),
+    Unknown,
+}
+
+fn unknown(err: sqlx::Error) -> AccountDeleteError {
+    error!(%err);
+    AccountDeleteError::Unknown
+}
+
+#[automock]
+#[async_trait]
+pub trait AccountRepository {
+    /// Deletes the account in one transaction, which is rolled back for a dry run
+    async fn delete(
+        &self,
+        user_id: i32,
+        deletion: &AccountDeletion,
+    ) -> Result<AccountDeletionReport, AccountDeleteError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct PgAccountRepository {
+    pub pool: PgPool,
+}
+
+impl PgAccountRepository {
+    pub fn new(pool: &PgPool) -> Self {
+        Self { pool: pool.clone() }
+    }
+}
+
+async fn transfer_projects(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: i32,
+    deletion: &AccountDeletion,
+) -> Result<Vec<i32>, AccountDeleteError> {
+    let new_owner_sql = "
+        SELECT u.user_id
+        FROM projects AS p
+        JOIN sharing AS s
+        ON p.project_id = s.project_id
+        JOIN users AS u
+        ON s.friend_id = u.user_id
+        WHERE p.project_id = $1 AND p.owner_id = $2 AND u.email = $3
+    ";
+    let transfer_sql = "UPDATE projects SET owner_id = $2 WHERE project_id = $1";
+    // the new owner no longer needs to be a collaborator
+    let remove_collaborator_sql = "DELETE FROM sharing WHERE project_id = $1 AND friend_id = $2";
+
+    let mut transferred = Vec::new();
+    for transfer in &deletion.transfers {
+        let new_owner_id = sqlx::query_scalar::<_, i32>(new_owner_sql)
+            .bind(transfer.project_id)
+            .bind(user_id)
+            .bind(&transfer.new_owner_email)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(unknown)?
+            .ok_or(AccountDeleteError::InvalidTransfer(transfer.project_id))?;
+
+        for sql in [transfer_sql, remove_collaborator_sql] {
+            sqlx::query(sql)
+                .bind(transfer.project_id)
+                .bind(new_owner_id)
+                .execute(&mut *tx)
+                .await
+                .map_err(unknown)?;
+        }
+        insert_event(
+            &mut *tx,
+            transfer.project_id,
+            user_id,
+            ProjectEventType::OwnershipTransfer,
+            Some(new_owner_id),
+        )
+        .await
+        .map_err(unknown)?;
+
+        match check_quota(tx, transfer.project_id, 0).await {
+            Ok(()) => transferred.push(transfer.project_id),
+            Err(QuotaError::Exceeded(exceeded)) => {
+                return Err(AccountDeleteError::QuotaExceeded(exceeded))
+            }
+            Err(QuotaError::Unknown) => return Err(AccountDeleteError::Unknown),
+        }
+    }
+
+    Ok(transferred)
+}
+
+async fn delete_account(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: i32,
+    deletion: &AccountDeletion,
+) -> Result<AccountDeletionReport, AccountDeleteError> {
+    let lock_user_sql = "
+        SELECT user_id
+        FROM users
+        WHERE user_id = $1 AND deleted_at IS NULL
+        FOR UPDATE
+    ";
+    let owned_projects_sql = "
+        SELECT project_id
+        FROM projects
+        WHERE owner_id = $1
+        ORDER BY project_id
+    ";
+    let leave_projects_sql = "
+        DELETE FROM sharing
+        WHERE friend_id = $1
+        RETURNING project_id
+    ";
+    let anonymized_entries_sql = "
+        SELECT (
+            (SELECT COUNT(*) FROM project_events WHERE actor_id = $1)
+            + (SELECT COUNT(*) FROM comment_threads WHERE author_id = $1)
+            + (SELECT COUNT(*) FROM comments WHERE author_id = $1)
+            + (SELECT COUNT(*) FROM suggestions WHERE author_id = $1)
+            + (SELECT COUNT(*) FROM snapshots WHERE author_id = $1)
+        )::BIGINT
+    ";
+    // the avatar file is no longer referenced once its content type is cleared, the collector removes it
+    let anonymize_user_sql = "
+        UPDATE users
+        SET
+            email = 'deleted-' || user_id || '@deleted.invalid',
+            password_hash = '',
+            display_name = NULL,
+            affiliation = NULL,
+            orcid = NULL,
+            avatar_content_type = NULL,
+            deleted_at = NOW(),
+            updated_at = NOW()
+        WHERE user_id = $1
+    ";
+
+    sqlx::query_scalar::<_, i32>(lock_user_sql)
+        .bind(user_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(unknown)?
+        .ok_or(AccountDeleteError::Missing)?;
+
+    let transferred_projects = transfer_projects(tx, user_id, deletion).await?;
+
+    let deleted_projects = sqlx::query_scalar::<_, i32>(owned_projects_sql)
+        .bind(user_id)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(unknown)?;
+    delete_projects(tx, &deleted_projects)
+        .await
+        .map_err(unknown)?;
+
+    let left_projects = sqlx::query_scalar::<_, i32>(leave_projects_sql)
+        .bind(user_id)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(unknown)?;
+    for project_id in &left_projects {
+        insert_event(
+            &mut *tx,
+            *project_id,
+            user_id,
+            ProjectEventType::CollaboratorRemove,
+            Some(user_id),
+        )
+        .await
+        .map_err(unknown)?;
+    }
+
+    let ended_sessions = sqlx::query("DELETE FROM sessions WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(unknown)?
+        .rows_affected();
+    for delete_tokens_sql in [
+        "DELETE FROM password_resets WHERE user_id = $1",
+        "DELETE FROM email_verifications WHERE user_id = $1",
+        "DELETE FROM api_tokens WHERE user_id = $1",
+    ] {
+        sqlx::query(delete_tokens_sql)
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(unknown)?;
+    }
+
+    let removed_events = match deletion.remove_activity {
+        true => sqlx::query("DELETE FROM project_events WHERE actor_id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(unknown)?
+            .rows_affected(),
+        false => 0,
+    };
+
+    let anonymized_entries = sqlx::query_scalar::<_, i64>(anonymized_entries_sql)
+        .bind(user_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(unknown)?;
+    sqlx::query(anonymize_user_sql)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(unknown)?;
+
+    Ok(AccountDeletionReport {
+        dry_run: deletion.dry_run,
+        transferred_projects,
+        deleted_projects,
+        left_projects,
+        ended_sessions,
+        removed_events,
+        anonymized_entries: anonymized_entries as u64,
+    })
+}
+
+#[async_trait]
+impl AccountRepository for PgAccountRepository {
+    #[tracing::instrument(skip(self))]
+    async fn delete(
+        &self,
+        user_id: i32,
+        deletion: &AccountDeletion,
+    ) -> Result<AccountDeletionReport, AccountDeleteError> {
+        let mut tx = self.pool.begin().await.map_err(unknown)?;
+
+        let report = delete_account(&mut tx, user_id, deletion).await?;
+
+        // a dry run goes through every step, so the report matches what deletion would do
+        match deletion.dry_run {
+            true => tx.rollback().await.map_err(unknown)?,
+            false => {
+                tx.commit().await.map_err(unknown)?;
+                info!(?report, "Deleted account");
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests;
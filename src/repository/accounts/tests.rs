@@ -0,0 +1,53 @@
+use sqlx::PgPool;
+
+use crate::{
+    domain::accounts::ProjectTransfer,
+    repository::fixtures::{create_project, create_user, share, test_store, user_email},
+};
+
+use super::*;
+
+async fn project_events(pool: &PgPool, project_id: i32) -> Vec<(ProjectEventType, Option<i32>)> {
+    sqlx::query_as("SELECT event_type, target_id FROM project_events WHERE project_id = $1")
+        .bind(project_id)
+        .fetch_all(pool)
+        .await
+        .unwrap()
+}
+
+#[sqlx::test]
+async fn delete_account_records_transfers_and_departures(pool: PgPool) {
+    let store = test_store();
+    let user_id = create_user(&pool).await;
+    let new_owner_id = create_user(&pool).await;
+    let other_owner_id = create_user(&pool).await;
+    let transferred_id = create_project(&pool, &store, user_id).await.project_id;
+    let left_id = create_project(&pool, &store, other_owner_id)
+        .await
+        .project_id;
+    share(&pool, transferred_id, new_owner_id).await;
+    share(&pool, left_id, user_id).await;
+    let new_owner_email = user_email(&pool, new_owner_id).await;
+    let deletion = AccountDeletion {
+        dry_run: false,
+        transfers: vec![ProjectTransfer {
+            project_id: transferred_id,
+            new_owner_email,
+        }],
+        remove_activity: false,
+    };
+
+    let result = PgAccountRepository::new(&pool)
+        .delete(user_id, &deletion)
+        .await;
+
+    assert!(result.is_ok());
+    assert_eq!(
+        project_events(&pool, transferred_id).await,
+        vec![(ProjectEventType::OwnershipTransfer, Some(new_owner_id))]
+    );
+    assert_eq!(
+        project_events(&pool, left_id).await,
+        vec![(ProjectEventType::CollaboratorRemove, Some(user_id))]
+    );
+}
@@ -0,0 +1,148 @@
+use axum::async_trait;
+use mockall::automock;
+use sqlx::PgPool;
+use tracing::error;
+
+use crate::{
+    domain::api_tokens::{ApiToken, ApiTokenData, CreatedApiToken},
+    repository::password_resets::{generate_token, hash_token},
+};
+
+/// Tells access tokens apart from session ids, which are sent as `Bearer` as well
+pub const API_TOKEN_PREFIX: &str = "pat_";
+
+pub enum ApiTokenGetError {
+    /// The token does not exist or expired
+    Missing,
+    Unknown,
+}
+
+pub enum ApiTokenInsertError {
+    Unknown,
+}
+
+pub enum ApiTokenDeleteError {
+    Missing,
+    Unknown,
+}
+
+#[automock]
+#[async_trait]
+pub trait ApiTokenRepository {
+    async fn get_by_user(&self, user_id: i32) -> Result<Vec<ApiToken>, ApiTokenGetError>;
+    async fn insert(
+        &self,
+        user_id: i32,
+        data: &ApiTokenData,
+    ) -> Result<CreatedApiToken, ApiTokenInsertError>;
+    async fn delete(&self, user_id: i32, token_id: i32) -> Result<(), ApiTokenDeleteError>;
+    /// Looks up an unexpired token by its secret, recording that it was used
+    async fn authenticate(&self, secret: &str) -> Result<ApiToken, ApiTokenGetError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct PgApiTokenRepository {
+    pub pool: PgPool,
+}
+
+impl PgApiTokenRepository {
+    pub fn new(pool: &PgPool) -> Self {
+        Self { pool: pool.clone() }
+    }
+}
+
+#[async_trait]
+impl ApiTokenRepository for PgApiTokenRepository {
+    #[tracing::instrument(skip(self))]
+    async fn get_by_user(&self, user_id: i32) -> Result<Vec<ApiToken>, ApiTokenGetError> {
+        let tokens_get_sql = "
+            SELECT token_id, user_id, name, scope, project_id, created_at, expires_at, last_used_at
+            FROM api_tokens
+            WHERE user_id = $1
+            ORDER BY token_id
+        ";
+
+        match sqlx::query_as::<_, ApiToken>(tokens_get_sql)
+            .bind(user_id)
+            .fetch_all(&self.pool)
+            .await
+        {
+            Ok(tokens) => Ok(tokens),
+            Err(err) => {
+                error!(%err);
+                Err(ApiTokenGetError::Unknown)
+            }
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn insert(
+        &self,
+        user_id: i32,
+        data: &ApiTokenData,
+    ) -> Result<CreatedApiToken, ApiTokenInsertError> {
+        let token_insert_sql = "
+            INSERT INTO api_tokens (token_hash, user_id, name, scope, project_id, expires_at)
+            VALUES ($1, $2, $3, $4, $5, NOW() + $6 * INTERVAL '1 day')
+            RETURNING token_id, user_id, name, scope, project_id, created_at, expires_at, last_used_at
+        ";
+
+        let secret = format!("{}{}", API_TOKEN_PREFIX, generate_token());
+        match sqlx::query_as::<_, ApiToken>(token_insert_sql)
+            .bind(hash_token(&secret))
+            .bind(user_id)
+            .bind(&data.name)
+            .bind(data.scope)
+            .bind(data.project_id)
+            .bind(data.expires_in_days as f64)
+            .fetch_one(&self.pool)
+            .await
+        {
+            Ok(token) => Ok(CreatedApiToken { token, secret }),
+            Err(err) => {
+                error!(%err);
+                Err(ApiTokenInsertError::Unknown)
+            }
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn delete(&self, user_id: i32, token_id: i32) -> Result<(), ApiTokenDeleteError> {
+        match sqlx::query("DELETE FROM api_tokens WHERE token_id = $1 AND user_id = $2")
+            .bind(token_id)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await
+        {
+            Ok(result) if result.rows_affected() == 0 => Err(ApiTokenDeleteError::Missing),
+            Ok(_) => Ok(()),
+            Err(err) => {
+                error!(%err);
+                Err(ApiTokenDeleteError::Unknown)
+            }
+        }
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn authenticate(&self, secret: &str) -> Result<ApiToken, ApiTokenGetError> {
+        let token_use_sql = "
+            UPDATE api_tokens
+            SET last_used_at = NOW()
+            WHERE token_hash = $1 AND expires_at > NOW()
+            RETURNING token_id, user_id, name, scope, project_id, created_at, expires_at, last_used_at
+        ";
+
+        match sqlx::query_as::<_, ApiToken>(token_use_sql)
+            .bind(hash_token(secret))
+            .fetch_optional(&self.pool)
+            .await
+        {
+            Ok(Some(token)) => Ok(token),
+            Ok(None) => Err(ApiTokenGetError::Missing),
+            Err(err) => {
+                error!(%err);
+                Err(ApiTokenGetError::Unknown)
+            }
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use sha2::{Digest, Sha256};
+use sqlx::{Postgres, Transaction};
+use tracing::error;
+
+use crate::storage::{object_key, BlobError, SharedBlobStore};
+
+/// Stores content under its hash and takes a reference to it within the transaction.
+/// The blob row stays locked until the transaction ends, so garbage collection
+/// cannot remove the object between the write and the commit.
+pub async fn acquire_blob(
+    tx: &mut Transaction<'_, Postgres>,
+    store: &SharedBlobStore,
+    content: &[u8],
+) -> Result<String, BlobError> {
+    let content_hash = hex::encode(Sha256::digest(content));
+    reference_blob(tx, &content_hash).await?;
+
+    let key = object_key(&content_hash);
+    if !store.exists(&key).await? {
+        store.put(&key, content).await?;
+    }
+
+    Ok(content_hash)
+}
+
+/// Takes another reference to content that is already stored
+pub async fn reference_blob(
+    tx: &mut Transaction<'_, Postgres>,
+    content_hash: &str,
+) -> Result<(), BlobError> {
+    let reference_sql = "
+        INSERT INTO blobs (content_hash, ref_count)
+        VALUES ($1, 1)
+        ON CONFLICT (content_hash) DO UPDATE
+        SET ref_count = blobs.ref_count + 1, updated_at = NOW()
+    ";
+
+    match sqlx::query(reference_sql)
+        .bind(content_hash)
+        .execute(&mut *tx)
+        .await
+    {
+        Ok(_) => Ok(()),
+        Err(err) => {
+            error!(%err);
+            Err(BlobError::Unknown)
+        }
+    }
+}
+
+/// Drops a reference, the object itself is left for garbage collection
+pub async fn release_blob(
+    tx: &mut Transaction<'_, Postgres>,
+    content_hash: &str,
+) -> Result<(), BlobError> {
+    let release_sql = "
+        UPDATE blobs
+        SET ref_count = ref_count - 1, updated_at = NOW()
+        WHERE content_hash = $1
+    ";
+
+    match sqlx::query(release_sql)
+        .bind(content_hash)
+        .execute(&mut *tx)
+        .await
+    {
+        Ok(result) if result.rows_affected() == 1 => Ok(()),
+        Ok(_) => Err(BlobError::Missing),
+        Err(err) => {
+            error!(%err);
+            Err(BlobError::Unknown)
+        }
+    }
+}
@@ -0,0 +1,387 @@
+use axum::async_trait;
+use mockall::automock;
+use sqlx::{PgPool, Postgres, Transaction};
+use tracing::{error, info};
+
+use crate::{
+    diff::TextEdit,
+    domain::comments::{Comment, CommentData, CommentThread, Thread, ThreadData},
+};
+
+pub enum CommentGetError {
+    Unknown,
+}
+pub enum CommentInsertError {
+    Missing,
+    Unknown,
+}
+pub enum CommentUpdateError {
+    Missing,
+    Unknown,
+}
+
+#[automock]
+#[async_trait]
+pub trait CommentRepository {
+    async fn get(&self, project_id: i32) -> Result<Vec<Thread>, CommentGetError>;
+    async fn insert_thread(
+        &self,
+        project_id: i32,
+        author_id: i32,
+        data: &ThreadData,
+    ) -> Result<Thread, CommentInsertError>;
+    async fn insert_reply(
+        &self,
+        project_id: i32,
+        thread_id: i32,
+        author_id: i32,
+        data: &CommentData,
+    ) -> Result<Comment, CommentInsertError>;
+    async fn update_status(
+        &self,
+        project_id: i32,
+        thread_id: i32,
+        resolved: bool,
+    ) -> Result<(), CommentUpdateError>;
+    async fn reanchor(&self, document_id: i32, edit: &TextEdit) -> Result<(), CommentUpdateError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct PgCommentRepository {
+    pub pool: PgPool,
+}
+
+impl PgCommentRepository {
+    pub fn new(pool: &PgPool) -> Self {
+        Self { pool: pool.clone() }
+    }
+
+    async fn insert_comment(
+        tx: &mut Transaction<'_, Postgres>,
+        project_id: i32,
+        thread_id: i32,
+        author_id: i32,
+        data: &CommentData,
+    ) -> Result<Comment, sqlx::Error> {
+        let insert_comment_sql = "
+            INSERT INTO comments (thread_id, author_id, content)
+            VALUES ($1, $2, $3)
+            RETURNING comment_id
+        ";
+        let comment_id: (i32,) = sqlx::query_as(insert_comment_sql)
+            .bind(thread_id)
+            .bind(author_id)
+            .bind(&data.content)
+            .fetch_one(&mut *tx)
+            .await?;
+
+        // only collaborators of the project can be mentioned, anyone else is skipped
+        let insert_mentions_sql = "
+            INSERT INTO comment_mentions (comment_id, user_id)
+            SELECT $1, mentioned.user_id
+            FROM UNNEST($2::INTEGER[]) AS mentioned(user_id)
+            JOIN projects AS p
+            ON p.project_id = $3
+            LEFT JOIN sharing AS s
+            ON s.project_id = p.project_id AND s.friend_id = mentioned.user_id
+            WHERE p.owner_id = mentioned.user_id OR s.friend_id IS NOT NULL
+            ON CONFLICT DO NOTHING
+        ";
+        sqlx::query(insert_mentions_sql)
+            .bind(comment_id.0)
+            .bind(&data.mentions)
+            .bind(project_id)
+            .execute(&mut *tx)
+            .await?;
+
+        let get_comment_sql = "
+            SELECT c.comment_id, c.thread_id, c.author_id, u.email, u.display_name, c.content, c.created_at,
+                ARRAY(
+                    SELECT user_id FROM comment_mentions AS m WHERE m.comment_id = c.comment_id
+                ) AS mentions
+            FROM comments AS c
+            JOIN users AS u
+            ON c.author_id = u.user_id
+            WHERE c.comment_id = $1
+        ";
+        sqlx::query_as::<_, Comment>(get_comment_sql)
+            .bind(comment_id.0)
+            .fetch_one(&mut *tx)
+            .await
+    }
+}
+
+#[async_trait]
+impl CommentRepository for PgCommentRepository {
+    #[tracing::instrument(skip(self))]
+    async fn get(&self, project_id: i32) -> Result<Vec<Thread>, CommentGetError> {
+        let get_threads_sql = "
+            SELECT thread_id, project_id, document_id, author_id, anchor_start, anchor_end, resolved, created_at
+            FROM comment_threads
+            WHERE project_id = $1
+            ORDER BY thread_id
+        ";
+        let threads = sqlx::query_as::<_, CommentThread>(get_threads_sql)
+            .bind(project_id)
+            .fetch_all(&self.pool)
+            .await;
+
+        let threads = match threads {
+            Ok(threads) => threads,
+            Err(err) => {
+                error!(%err);
+                return Err(CommentGetError::Unknown);
+            }
+        };
+
+        let get_comments_sql = "
+            SELECT c.comment_id, c.thread_id, c.author_id, u.email, u.display_name, c.content, c.created_at,
+                ARRAY(
+                    SELECT user_id FROM comment_mentions AS m WHERE m.comment_id = c.comment_id
+                ) AS mentions
+            FROM comments AS c
+            JOIN comment_threads AS t
+            ON c.thread_id = t.thread_id
+            JOIN users AS u
+            ON c.author_id = u.user_id
+            WHERE t.project_id = $1
+            ORDER BY c.comment_id
+        ";
+        let comments = sqlx::query_as::<_, Comment>(get_comments_sql)
+            .bind(project_id)
+            .fetch_all(&self.pool)
+            .await;
+
+        let comments = match comments {
+            Ok(comments) => comments,
+            Err(err) => {
+                error!(%err);
+                return Err(CommentGetError::Unknown);
+            }
+        };
+
+        Ok(threads
+            .into_iter()
+            .map(|thread| Thread {
+                comments: comments
+                    .iter()
+                    .filter(|comment| comment.thread_id == thread.thread_id)
+                    .cloned()
+                    .collect(),
+                thread,
+            })
+            .collect())
+    }
+
+    #[tracing::instrument(skip(self, data))]
+    async fn insert_thread(
+        &self,
+        project_id: i32,
+        author_id: i32,
+        data: &ThreadData,
+    ) -> Result<Thread, CommentInsertError> {
+        let mut tx = match self.pool.begin().await {
+            Ok(tx) => tx,
+            Err(err) => {
+                error!(%err);
+                return Err(CommentInsertError::Unknown);
+            }
+        };
+
+        let insert_thread_sql = "
+            INSERT INTO comment_threads (project_id, document_id, author_id, anchor_start, anchor_end)
+            SELECT project_id, document_id, $3, $4, $5
+            FROM documents
+            WHERE project_id = $1 AND document_id = $2
+            RETURNING thread_id, project_id, document_id, author_id, anchor_start, anchor_end, resolved, created_at
+        ";
+        let result = sqlx::query_as::<_, CommentThread>(insert_thread_sql)
+            .bind(project_id)
+            .bind(data.document_id)
+            .bind(author_id)
+            .bind(data.anchor_start)
+            .bind(data.anchor_end)
+            .fetch_optional(&mut tx);
+
+        let thread = match result.await {
+            Ok(Some(thread)) => thread,
+            Ok(None) => return Err(CommentInsertError::Missing),
+            Err(err) => {
+                error!(%err);
+                return Err(CommentInsertError::Unknown);
+            }
+        };
+
+        info!("Created thread {}", thread.thread_id);
+
+        let comment = match Self::insert_comment(
+            &mut tx,
+            project_id,
+            thread.thread_id,
+            author_id,
+            &data.comment,
+        )
+        .await
+        {
+            Ok(comment) => comment,
+            Err(err) => {
+                error!(%err);
+                return Err(CommentInsertError::Unknown);
+            }
+        };
+
+        match tx.commit().await {
+            Ok(_) => Ok(Thread {
+                thread,
+                comments: vec![comment],
+            }),
+            Err(err) => {
+                error!(%err);
+                Err(CommentInsertError::Unknown)
+            }
+        }
+    }
+
+    #[tracing::instrument(skip(self, data))]
+    async fn insert_reply(
+        &self,
+        project_id: i32,
+        thread_id: i32,
+        author_id: i32,
+        data: &CommentData,
+    ) -> Result<Comment, CommentInsertError> {
+        let mut tx = match self.pool.begin().await {
+            Ok(tx) => tx,
+            Err(err) => {
+                error!(%err);
+                return Err(CommentInsertError::Unknown);
+            }
+        };
+
+        let check_thread_sql = "
+            SELECT thread_id
+            FROM comment_threads
+            WHERE project_id = $1 AND thread_id = $2
+        ";
+        let result = sqlx::query(check_thread_sql)
+            .bind(project_id)
+            .bind(thread_id)
+            .fetch_optional(&mut tx);
+
+        match result.await {
+            Ok(Some(_)) => (),
+            Ok(None) => return Err(CommentInsertError::Missing),
+            Err(err) => {
+                error!(%err);
+                return Err(CommentInsertError::Unknown);
+            }
+        }
+
+        let comment =
+            match Self::insert_comment(&mut tx, project_id, thread_id, author_id, data).await {
+                Ok(comment) => comment,
+                Err(err) => {
+                    error!(%err);
+                    return Err(CommentInsertError::Unknown);
+                }
+            };
+
+        match tx.commit().await {
+            Ok(_) => Ok(comment),
+            Err(err) => {
+                error!(%err);
+                Err(CommentInsertError::Unknown)
+            }
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn update_status(
+        &self,
+        project_id: i32,
+        thread_id: i32,
+        resolved: bool,
+    ) -> Result<(), CommentUpdateError> {
+        let result = sqlx::query(
+            "
+            UPDATE comment_threads
+            SET resolved = $1
+            WHERE project_id = $2 AND thread_id = $3
+        ",
+        )
+        .bind(resolved)
+        .bind(project_id)
+        .bind(thread_id)
+        .execute(&self.pool)
+        .await;
+
+        match result {
+            Ok(result) if result.rows_affected() > 0 => Ok(()),
+            Ok(_) => Err(CommentUpdateError::Missing),
+            Err(err) => {
+                error!(%err);
+                Err(CommentUpdateError::Unknown)
+            }
+        }
+    }
+
+    #[tracing::instrument(skip(self, edit))]
+    async fn reanchor(&self, document_id: i32, edit: &TextEdit) -> Result<(), CommentUpdateError> {
+        let mut tx = match self.pool.begin().await {
+            Ok(tx) => tx,
+            Err(err) => {
+                error!(%err);
+                return Err(CommentUpdateError::Unknown);
+            }
+        };
+
+        let get_anchors_sql = "
+            SELECT thread_id, anchor_start, anchor_end
+            FROM comment_threads
+            WHERE document_id = $1
+            FOR UPDATE
+        ";
+        let anchors = sqlx::query_as::<_, (i32, i32, i32)>(get_anchors_sql)
+            .bind(document_id)
+            .fetch_all(&mut tx)
+            .await;
+
+        let anchors = match anchors {
+            Ok(anchors) => anchors,
+            Err(err) => {
+                error!(%err);
+                return Err(CommentUpdateError::Unknown);
+            }
+        };
+
+        let update_anchor_sql = "
+            UPDATE comment_threads
+            SET anchor_start = $1, anchor_end = $2
+            WHERE thread_id = $3
+        ";
+        for (thread_id, start, end) in anchors {
+            let new_start = edit.map_start(start as usize) as i32;
+            let new_end = edit.map_end(end as usize) as i32;
+            if (new_start, new_end) == (start, end) {
+                continue;
+            }
+
+            let result = sqlx::query(update_anchor_sql)
+                .bind(new_start)
+                .bind(new_end)
+                .bind(thread_id)
+                .execute(&mut tx)
+                .await;
+
+            if let Err(err) = result {
+                error!(%err);
+                return Err(CommentUpdateError::Unknown);
+            }
+        }
+
+        tx.commit().await.map_err(|err| {
+            error!(%err);
+            CommentUpdateError::Unknown
+        })
+    }
+}
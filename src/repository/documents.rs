@@ -4,8 +4,13 @@ use sqlx::PgPool;
 use tracing::error;
 
 use crate::{
-    domain::documents::{Document, DocumentData},
-    filesystem::{get_document_path, read_file, write_file, FileReadError, FileWriteError},
+    constants::DOCUMENT_COMPRESSION,
+    domain::{
+        documents::{Document, DocumentData},
+        usage::QuotaExceeded,
+    },
+    repository::usage::{check_quota, QuotaError},
+    storage::{codec, document_key, BlobError, SharedBlobStore},
 };
 
 pub enum DocumentInsertError {
@@ -13,6 +18,8 @@ pub enum DocumentInsertError {
 }
 pub enum DocumentUpdateError {
     Missing,
+    NoSpace,
+    QuotaExceeded(QuotaExceeded),
     Unknown,
 }
 pub enum DocumentGetError {
@@ -47,11 +54,15 @@ pub trait DocumentRepository {
 #[derive(Debug, Clone)]
 pub struct PgDocumentRepository {
     pub pool: PgPool,
+    pub store: SharedBlobStore,
 }
 
 impl PgDocumentRepository {
-    pub fn new(pool: &PgPool) -> Self {
-        Self { pool: pool.clone() }
+    pub fn new(pool: &PgPool, store: &SharedBlobStore) -> Self {
+        Self {
+            pool: pool.clone(),
+            store: store.clone(),
+        }
     }
 }
 
@@ -60,7 +71,7 @@ impl DocumentRepository for PgDocumentRepository {
     async fn get(&self, project_id: i32) -> Result<Vec<Document>, DocumentGetError> {
         let documents = sqlx::query_as::<_, Document>(
             "
-            SELECT document_id, project_id, name
+            SELECT document_id, project_id, name, folder_id
             FROM documents
             WHERE documents.project_id = $1
         ",
@@ -85,7 +96,7 @@ impl DocumentRepository for PgDocumentRepository {
         document_id: i32,
     ) -> Result<Document, DocumentGetError> {
         let get_document_sql = "
-            SELECT document_id, project_id, name
+            SELECT document_id, project_id, name, folder_id
             FROM documents
             WHERE project_id = $1 AND document_id = $2
         ";
@@ -147,7 +158,7 @@ impl DocumentRepository for PgDocumentRepository {
         let insert_document_sql = "
             INSERT_INTO documents (project_id, name)
             VALUES ($1, $2)
-            RETURNING document_id, project_id, name
+            RETURNING document_id, project_id, name, folder_id
         ";
 
         let result = sqlx::query_as::<_, Document>(insert_document_sql)
@@ -163,10 +174,7 @@ impl DocumentRepository for PgDocumentRepository {
             }
         };
 
-        if write_file(get_document_path(&document), "", true)
-            .await
-            .is_err()
-        {
+        if self.store.put(&document_key(&document), b"").await.is_err() {
             return Err(DocumentInsertError::Unknown);
         }
 
@@ -178,12 +186,20 @@ impl DocumentRepository for PgDocumentRepository {
 
     #[tracing::instrument(skip(self))]
     async fn read_file(&self, document: &Document) -> Result<String, DocumentGetError> {
-        read_file(get_document_path(document))
+        let content = self
+            .store
+            .get(&document_key(document))
             .await
             .map_err(|err| match err {
-                FileReadError::Missing => DocumentGetError::Missing,
-                FileReadError::Unknown => DocumentGetError::Unknown,
-            })
+                BlobError::Missing => DocumentGetError::Missing,
+                BlobError::NoSpace | BlobError::Unknown => DocumentGetError::Unknown,
+            })?;
+        let content = codec::decode(content).map_err(|_| DocumentGetError::Unknown)?;
+
+        String::from_utf8(content).map_err(|err| {
+            error!(%err);
+            DocumentGetError::Unknown
+        })
     }
 
     #[tracing::instrument(skip(self, content))]
@@ -192,11 +208,72 @@ impl DocumentRepository for PgDocumentRepository {
         document: &Document,
         content: &str,
     ) -> Result<(), DocumentUpdateError> {
-        write_file(get_document_path(document), content, false)
+        let mut tx = match self.pool.begin().await {
+            Ok(tx) => tx,
+            Err(err) => {
+                error!(%err);
+                return Err(DocumentUpdateError::Unknown);
+            }
+        };
+
+        let previous_size = sqlx::query_scalar::<_, i64>(
+            "SELECT size FROM documents WHERE document_id = $1 FOR UPDATE",
+        )
+        .bind(document.document_id)
+        .fetch_optional(&mut tx)
+        .await;
+        let previous_size = match previous_size {
+            Ok(Some(previous_size)) => previous_size,
+            Ok(None) => return Err(DocumentUpdateError::Missing),
+            Err(err) => {
+                error!(%err);
+                return Err(DocumentUpdateError::Unknown);
+            }
+        };
+
+        let size = content.len() as i64;
+        if size > previous_size {
+            match check_quota(&mut tx, document.project_id, size - previous_size).await {
+                Ok(()) => (),
+                Err(QuotaError::Exceeded(exceeded)) => {
+                    return Err(DocumentUpdateError::QuotaExceeded(exceeded))
+                }
+                Err(QuotaError::Unknown) => return Err(DocumentUpdateError::Unknown),
+            }
+        }
+
+        let key = document_key(document);
+        match self.store.exists(&key).await {
+            Ok(true) => (),
+            Ok(false) => return Err(DocumentUpdateError::Missing),
+            Err(_) => return Err(DocumentUpdateError::Unknown),
+        }
+
+        let encoded = codec::encode(content.as_bytes(), *DOCUMENT_COMPRESSION)
+            .map_err(|_| DocumentUpdateError::Unknown)?;
+        self.store
+            .put(&key, &encoded)
             .await
             .map_err(|err| match err {
-                FileWriteError::Missing => DocumentUpdateError::Missing,
-                FileWriteError::Unknown => DocumentUpdateError::Unknown,
-            })
+                BlobError::Missing => DocumentUpdateError::Missing,
+                BlobError::NoSpace => DocumentUpdateError::NoSpace,
+                BlobError::Unknown => DocumentUpdateError::Unknown,
+            })?;
+
+        let result = sqlx::query("UPDATE documents SET size = $1 WHERE document_id = $2")
+            .bind(size)
+            .bind(document.document_id)
+            .execute(&mut tx)
+            .await;
+
+        if let Err(err) = result {
+            error!(%err);
+            return Err(DocumentUpdateError::Unknown);
+        }
+
+        tx.commit().await.map_err(|err| {
+            error!(%err);
+            DocumentUpdateError::Unknown
+        })
     }
 }
@@ -0,0 +1,129 @@
+use axum::async_trait;
+use mockall::automock;
+use sqlx::PgPool;
+use tracing::error;
+
+use crate::{
+    constants::EMAIL_VERIFICATION_TOKEN_LIFETIME_IN_SECONDS,
+    repository::password_resets::{generate_token, hash_token},
+};
+
+pub enum EmailVerificationInsertError {
+    /// The address of the user is verified already
+    Verified,
+    Unknown,
+}
+
+pub enum EmailVerificationRedeemError {
+    /// The token does not exist, expired or was already used
+    Invalid,
+    Unknown,
+}
+
+#[automock]
+#[async_trait]
+pub trait EmailVerificationRepository {
+    /// Issues a single-use token for an unverified user, only its hash is stored
+    async fn insert(&self, user_id: i32) -> Result<String, EmailVerificationInsertError>;
+    /// Marks the address of the token's user as verified, returning their id
+    async fn redeem(&self, token: &str) -> Result<i32, EmailVerificationRedeemError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct PgEmailVerificationRepository {
+    pub pool: PgPool,
+}
+
+impl PgEmailVerificationRepository {
+    pub fn new(pool: &PgPool) -> Self {
+        Self { pool: pool.clone() }
+    }
+}
+
+#[async_trait]
+impl EmailVerificationRepository for PgEmailVerificationRepository {
+    #[tracing::instrument(skip(self))]
+    async fn insert(&self, user_id: i32) -> Result<String, EmailVerificationInsertError> {
+        let insert_token_sql = "
+            INSERT INTO email_verifications (token_hash, user_id, expires_at)
+            SELECT $1, user_id, NOW() + $3 * INTERVAL '1 second'
+            FROM users
+            WHERE user_id = $2 AND verified_at IS NULL
+        ";
+
+        let token = generate_token();
+        match sqlx::query(insert_token_sql)
+            .bind(hash_token(&token))
+            .bind(user_id)
+            .bind(*EMAIL_VERIFICATION_TOKEN_LIFETIME_IN_SECONDS as f64)
+            .execute(&self.pool)
+            .await
+        {
+            Ok(result) if result.rows_affected() == 0 => {
+                Err(EmailVerificationInsertError::Verified)
+            }
+            Ok(_) => Ok(token),
+            Err(err) => {
+                error!(%err);
+                Err(EmailVerificationInsertError::Unknown)
+            }
+        }
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn redeem(&self, token: &str) -> Result<i32, EmailVerificationRedeemError> {
+        let mut tx = match self.pool.begin().await {
+            Ok(tx) => tx,
+            Err(err) => {
+                error!(%err);
+                return Err(EmailVerificationRedeemError::Unknown);
+            }
+        };
+
+        let use_token_sql = "
+            UPDATE email_verifications
+            SET used_at = NOW()
+            WHERE token_hash = $1 AND used_at IS NULL AND expires_at > NOW()
+            RETURNING user_id
+        ";
+        // tokens sent earlier are no longer needed
+        let use_other_tokens_sql = "
+            UPDATE email_verifications
+            SET used_at = NOW()
+            WHERE user_id = $1 AND used_at IS NULL
+        ";
+        let verify_user_sql = "
+            UPDATE users
+            SET verified_at = COALESCE(verified_at, NOW()), updated_at = NOW()
+            WHERE user_id = $1
+        ";
+
+        let user_id = match sqlx::query_scalar::<_, i32>(use_token_sql)
+            .bind(hash_token(token))
+            .fetch_optional(&mut tx)
+            .await
+        {
+            Ok(Some(user_id)) => user_id,
+            Ok(None) => return Err(EmailVerificationRedeemError::Invalid),
+            Err(err) => {
+                error!(%err);
+                return Err(EmailVerificationRedeemError::Unknown);
+            }
+        };
+
+        for sql in [use_other_tokens_sql, verify_user_sql] {
+            if let Err(err) = sqlx::query(sql).bind(user_id).execute(&mut tx).await {
+                error!(%err);
+                return Err(EmailVerificationRedeemError::Unknown);
+            }
+        }
+
+        match tx.commit().await {
+            Ok(()) => Ok(user_id),
+            Err(err) => {
+                error!(%err);
+                Err(EmailVerificationRedeemError::Unknown)
+            }
+        }
+    }
+}
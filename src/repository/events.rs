@@ -0,0 +1,125 @@
+use axum::async_trait;
+use mockall::automock;
+use sqlx::{Executor, PgPool, Postgres};
+use tracing::error;
+
+use crate::domain::events::{ActivityFilter, ProjectEvent, ProjectEventType};
+
+pub enum EventGetError {
+    Unknown,
+}
+pub enum EventInsertError {
+    Unknown,
+}
+
+#[automock]
+#[async_trait]
+pub trait EventRepository {
+    async fn get(
+        &self,
+        project_id: i32,
+        filter: &ActivityFilter,
+        limit: i64,
+    ) -> Result<Vec<ProjectEvent>, EventGetError>;
+    async fn insert(
+        &self,
+        project_id: i32,
+        actor_id: i32,
+        event_type: ProjectEventType,
+        target_id: Option<i32>,
+    ) -> Result<(), EventInsertError>;
+}
+
+/// Records an event on any executor, so changes made in a transaction can log themselves in it
+pub async fn insert_event<'e, X: Executor<'e, Database = Postgres>>(
+    executor: X,
+    project_id: i32,
+    actor_id: i32,
+    event_type: ProjectEventType,
+    target_id: Option<i32>,
+) -> Result<(), sqlx::Error> {
+    let insert_event_sql = "
+        INSERT INTO project_events (project_id, actor_id, event_type, target_id)
+        VALUES ($1, $2, $3, $4)
+    ";
+
+    sqlx::query(insert_event_sql)
+        .bind(project_id)
+        .bind(actor_id)
+        .bind(event_type)
+        .bind(target_id)
+        .execute(executor)
+        .await
+        .map(|_| ())
+}
+
+#[derive(Debug, Clone)]
+pub struct PgEventRepository {
+    pub pool: PgPool,
+}
+
+impl PgEventRepository {
+    pub fn new(pool: &PgPool) -> Self {
+        Self { pool: pool.clone() }
+    }
+}
+
+#[async_trait]
+impl EventRepository for PgEventRepository {
+    #[tracing::instrument(skip(self))]
+    async fn get(
+        &self,
+        project_id: i32,
+        filter: &ActivityFilter,
+        limit: i64,
+    ) -> Result<Vec<ProjectEvent>, EventGetError> {
+        let get_events_sql = "
+            SELECT e.event_id, e.project_id, e.actor_id, u.email, u.display_name, e.event_type, e.target_id, e.created_at
+            FROM project_events AS e
+            JOIN users AS u
+            ON e.actor_id = u.user_id
+            WHERE e.project_id = $1
+                AND ($2::INTEGER IS NULL OR e.actor_id = $2)
+                AND ($3::TEXT IS NULL OR e.event_type = $3)
+                AND ($4::INTEGER IS NULL OR e.event_id < $4)
+            ORDER BY e.event_id DESC
+            LIMIT $5
+        ";
+
+        let events = sqlx::query_as::<_, ProjectEvent>(get_events_sql)
+            .bind(project_id)
+            .bind(filter.actor_id)
+            .bind(filter.event_type)
+            .bind(filter.before)
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await;
+
+        match events {
+            Ok(events) => Ok(events),
+            Err(err) => {
+                error!(%err);
+                Err(EventGetError::Unknown)
+            }
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn insert(
+        &self,
+        project_id: i32,
+        actor_id: i32,
+        event_type: ProjectEventType,
+        target_id: Option<i32>,
+    ) -> Result<(), EventInsertError> {
+        let result = insert_event(&self.pool, project_id, actor_id, event_type, target_id).await;
+
+        match result {
+            Ok(_) => Ok(()),
+            Err(err) => {
+                error!(%err);
+                Err(EventInsertError::Unknown)
+            }
+        }
+    }
+}
@@ -0,0 +1,148 @@
+// the repository tests need a PostgreSQL database named by `DATABASE_URL`, each test runs in a
+// fresh database with the migrations applied, e.g. start one with
+// `docker run -p 5432:5432 -e POSTGRES_HOST_AUTH_METHOD=trust postgres`
+// and run `DATABASE_URL=postgres://postgres@localhost cargo test`
+use std::sync::Arc;
+
+use rand::{distributions::Alphanumeric, Rng};
+use sqlx::PgPool;
+
+use crate::{
+    constants::DOCUMENT_COMPRESSION,
+    domain::{
+        api_tokens::{ApiTokenData, ApiTokenScope},
+        documents::Document,
+        projects::ProjectMetadata,
+        snapshots::SnapshotData,
+    },
+    repository::{
+        api_tokens::{ApiTokenRepository, PgApiTokenRepository},
+        projects::{PgProjectRepository, ProjectRepository},
+        snapshots::{PgSnapshotRepository, SnapshotRepository},
+        users::{PgUserRepository, UserRepository},
+    },
+    storage::{codec, document_key, BlobError, MemoryBlobStore, SharedBlobStore},
+};
+
+pub fn random_name() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(16)
+        .map(char::from)
+        .collect()
+}
+
+pub fn test_store() -> SharedBlobStore {
+    Arc::new(MemoryBlobStore::default())
+}
+
+pub async fn create_user(pool: &PgPool) -> i32 {
+    PgUserRepository::new(pool)
+        .insert(&format!("{}@email.com", random_name()), "hash")
+        .await
+        .ok()
+        .unwrap()
+}
+
+/// Returns the main document of the new project
+pub async fn create_project(pool: &PgPool, store: &SharedBlobStore, owner_id: i32) -> Document {
+    let data = ProjectMetadata {
+        name: random_name(),
+    };
+    let project = PgProjectRepository::new(pool, store)
+        .insert(&data, owner_id)
+        .await
+        .ok()
+        .unwrap();
+    Document {
+        document_id: project.main_document_id,
+        project_id: project.project_id,
+        name: String::from("main.tex"),
+        folder_id: None,
+    }
+}
+
+pub async fn create_document(
+    pool: &PgPool,
+    store: &SharedBlobStore,
+    project_id: i32,
+    name: &str,
+) -> Document {
+    let document = sqlx::query_as::<_, Document>(
+        "
+            INSERT INTO documents (project_id, name)
+            VALUES ($1, $2)
+            RETURNING document_id, project_id, name, folder_id
+        ",
+    )
+    .bind(project_id)
+    .bind(name)
+    .fetch_one(pool)
+    .await
+    .unwrap();
+    write_document(store, &document, "").await;
+    document
+}
+
+pub async fn write_document(store: &SharedBlobStore, document: &Document, content: &str) {
+    let encoded = codec::encode(content.as_bytes(), *DOCUMENT_COMPRESSION).unwrap();
+    store.put(&document_key(document), &encoded).await.unwrap();
+}
+
+pub async fn read_document(
+    store: &SharedBlobStore,
+    document: &Document,
+) -> Result<String, BlobError> {
+    let stored = store.get(&document_key(document)).await?;
+    Ok(String::from_utf8(codec::decode(stored)?).unwrap())
+}
+
+pub async fn create_snapshot(
+    pool: &PgPool,
+    store: &SharedBlobStore,
+    project_id: i32,
+    author_id: i32,
+) -> i32 {
+    let data = SnapshotData {
+        name: random_name(),
+    };
+    PgSnapshotRepository::new(pool, store)
+        .insert(project_id, author_id, &data)
+        .await
+        .ok()
+        .unwrap()
+        .snapshot_id
+}
+
+pub async fn share(pool: &PgPool, project_id: i32, user_id: i32) {
+    sqlx::query("INSERT INTO sharing (project_id, friend_id) VALUES ($1, $2)")
+        .bind(project_id)
+        .bind(user_id)
+        .execute(pool)
+        .await
+        .unwrap();
+}
+
+pub async fn user_email(pool: &PgPool, user_id: i32) -> String {
+    sqlx::query_scalar::<_, String>("SELECT email FROM users WHERE user_id = $1")
+        .bind(user_id)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
+/// Returns the secret of a new unrestricted token
+pub async fn create_api_token(pool: &PgPool, user_id: i32) -> String {
+    let data = ApiTokenData {
+        name: random_name(),
+        scope: ApiTokenScope::Write,
+        project_id: None,
+        expires_in_days: 30,
+    };
+    PgApiTokenRepository::new(pool)
+        .insert(user_id, &data)
+        .await
+        .ok()
+        .unwrap()
+        .secret
+}
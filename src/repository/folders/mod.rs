@@ -0,0 +1,428 @@
+use std::collections::HashMap;
+
+use axum::async_trait;
+use mockall::automock;
+use sqlx::{PgExecutor, PgPool, Postgres, Transaction};
+use tracing::error;
+
+use crate::domain::folders::{Folder, FolderData};
+
+pub enum FolderGetError {
+    Unknown,
+}
+pub enum FolderInsertError {
+    Missing,
+    Duplicate,
+    Unknown,
+}
+pub enum FolderUpdateError {
+    Missing,
+    Duplicate,
+    Cycle,
+    Unknown,
+}
+pub enum FolderMoveError {
+    Missing,
+    Duplicate,
+    Unknown,
+}
+pub enum FolderDeleteError {
+    Missing,
+    NotEmpty,
+    Unknown,
+}
+
+const UNIQUE_VIOLATION: &str = "23505";
+const FOREIGN_KEY_VIOLATION: &str = "23503";
+
+fn is_violation(err: &sqlx::Error, code: &str) -> bool {
+    match err {
+        sqlx::Error::Database(err) => err.code().as_deref() == Some(code),
+        _ => false,
+    }
+}
+
+/// Checks that the folder belongs to the project, the project root always does
+pub async fn folder_in_project<'e, E: PgExecutor<'e>>(
+    executor: E,
+    project_id: i32,
+    folder_id: Option<i32>,
+) -> Result<bool, sqlx::Error> {
+    let Some(folder_id) = folder_id else {
+        return Ok(true);
+    };
+
+    sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS (SELECT 1 FROM folders WHERE project_id = $1 AND folder_id = $2)",
+    )
+    .bind(project_id)
+    .bind(folder_id)
+    .fetch_one(executor)
+    .await
+}
+
+/// Returns the `/`-separated path of every folder in the project
+pub async fn folder_paths<'e, E: PgExecutor<'e>>(
+    executor: E,
+    project_id: i32,
+) -> Result<HashMap<i32, String>, sqlx::Error> {
+    let folder_paths_sql = "
+        WITH RECURSIVE paths (folder_id, path) AS (
+            SELECT folder_id, name::TEXT
+            FROM folders
+            WHERE project_id = $1 AND parent_id IS NULL
+            UNION ALL
+            SELECT folders.folder_id, paths.path || '/' || folders.name
+            FROM folders
+            JOIN paths ON folders.parent_id = paths.folder_id
+        )
+        SELECT folder_id, path FROM paths
+    ";
+
+    let paths = sqlx::query_as::<_, (i32, String)>(folder_paths_sql)
+        .bind(project_id)
+        .fetch_all(executor)
+        .await?;
+
+    Ok(paths.into_iter().collect())
+}
+
+/// Path of a file inside the project, as it appears in exported archives
+pub fn file_path(paths: &HashMap<i32, String>, folder_id: Option<i32>, name: &str) -> String {
+    match folder_id.and_then(|folder_id| paths.get(&folder_id)) {
+        Some(folder_path) => format!("{}/{}", folder_path, name),
+        None => name.to_string(),
+    }
+}
+
+/// Finds the folder at the given path, creating the missing ones along the way
+pub async fn ensure_folders(
+    tx: &mut Transaction<'_, Postgres>,
+    project_id: i32,
+    folder_names: &[&str],
+) -> Result<Option<i32>, sqlx::Error> {
+    let ensure_folder_sql = "
+        WITH existing AS (
+            SELECT folder_id
+            FROM folders
+            WHERE project_id = $1 AND parent_id IS NOT DISTINCT FROM $2 AND name = $3
+        ), inserted AS (
+            INSERT INTO folders (project_id, parent_id, name)
+            SELECT $1, $2, $3
+            WHERE NOT EXISTS (SELECT 1 FROM existing)
+            RETURNING folder_id
+        )
+        SELECT folder_id FROM existing
+        UNION ALL
+        SELECT folder_id FROM inserted
+    ";
+
+    let mut parent_id = None;
+    for name in folder_names {
+        let folder_id = sqlx::query_scalar::<_, i32>(ensure_folder_sql)
+            .bind(project_id)
+            .bind(parent_id)
+            .bind(name)
+            .fetch_one(&mut *tx)
+            .await?;
+        parent_id = Some(folder_id);
+    }
+
+    Ok(parent_id)
+}
+
+#[automock]
+#[async_trait]
+pub trait FolderRepository {
+    async fn get(&self, project_id: i32) -> Result<Vec<Folder>, FolderGetError>;
+    async fn insert(&self, project_id: i32, data: &FolderData)
+        -> Result<Folder, FolderInsertError>;
+    async fn update(
+        &self,
+        project_id: i32,
+        folder_id: i32,
+        data: &FolderData,
+    ) -> Result<Folder, FolderUpdateError>;
+    async fn delete(&self, project_id: i32, folder_id: i32) -> Result<(), FolderDeleteError>;
+    async fn move_document(
+        &self,
+        project_id: i32,
+        document_id: i32,
+        folder_id: Option<i32>,
+    ) -> Result<(), FolderMoveError>;
+    async fn move_resource(
+        &self,
+        project_id: i32,
+        resource_id: i32,
+        folder_id: Option<i32>,
+    ) -> Result<(), FolderMoveError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct PgFolderRepository {
+    pub pool: PgPool,
+}
+
+impl PgFolderRepository {
+    pub fn new(pool: &PgPool) -> Self {
+        Self { pool: pool.clone() }
+    }
+
+    async fn move_file(
+        &self,
+        move_file_sql: &str,
+        project_id: i32,
+        file_id: i32,
+        folder_id: Option<i32>,
+    ) -> Result<(), FolderMoveError> {
+        let mut tx = match self.pool.begin().await {
+            Ok(tx) => tx,
+            Err(err) => {
+                error!(%err);
+                return Err(FolderMoveError::Unknown);
+            }
+        };
+
+        match folder_in_project(&mut tx, project_id, folder_id).await {
+            Ok(true) => (),
+            Ok(false) => return Err(FolderMoveError::Missing),
+            Err(err) => {
+                error!(%err);
+                return Err(FolderMoveError::Unknown);
+            }
+        }
+
+        let result = sqlx::query(move_file_sql)
+            .bind(project_id)
+            .bind(file_id)
+            .bind(folder_id)
+            .execute(&mut tx)
+            .await;
+
+        match result {
+            Ok(result) if result.rows_affected() > 0 => (),
+            Ok(_) => return Err(FolderMoveError::Missing),
+            Err(err) if is_violation(&err, UNIQUE_VIOLATION) => {
+                return Err(FolderMoveError::Duplicate)
+            }
+            Err(err) => {
+                error!(%err);
+                return Err(FolderMoveError::Unknown);
+            }
+        }
+
+        match tx.commit().await {
+            Ok(_) => Ok(()),
+            Err(err) => {
+                error!(%err);
+                Err(FolderMoveError::Unknown)
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl FolderRepository for PgFolderRepository {
+    #[tracing::instrument(skip(self))]
+    async fn get(&self, project_id: i32) -> Result<Vec<Folder>, FolderGetError> {
+        let get_folders_sql = "
+            SELECT folder_id, project_id, parent_id, name
+            FROM folders
+            WHERE project_id = $1
+            ORDER BY name
+        ";
+
+        let folders = sqlx::query_as::<_, Folder>(get_folders_sql)
+            .bind(project_id)
+            .fetch_all(&self.pool)
+            .await;
+
+        match folders {
+            Ok(folders) => Ok(folders),
+            Err(err) => {
+                error!(%err);
+                Err(FolderGetError::Unknown)
+            }
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn insert(
+        &self,
+        project_id: i32,
+        data: &FolderData,
+    ) -> Result<Folder, FolderInsertError> {
+        match folder_in_project(&self.pool, project_id, data.parent_id).await {
+            Ok(true) => (),
+            Ok(false) => return Err(FolderInsertError::Missing),
+            Err(err) => {
+                error!(%err);
+                return Err(FolderInsertError::Unknown);
+            }
+        }
+
+        let insert_folder_sql = "
+            INSERT INTO folders (project_id, parent_id, name)
+            VALUES ($1, $2, $3)
+            ON CONFLICT DO NOTHING
+            RETURNING folder_id, project_id, parent_id, name
+        ";
+
+        let folder = sqlx::query_as::<_, Folder>(insert_folder_sql)
+            .bind(project_id)
+            .bind(data.parent_id)
+            .bind(&data.name)
+            .fetch_optional(&self.pool)
+            .await;
+
+        match folder {
+            Ok(Some(folder)) => Ok(folder),
+            Ok(None) => Err(FolderInsertError::Duplicate),
+            Err(err) => {
+                error!(%err);
+                Err(FolderInsertError::Unknown)
+            }
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn update(
+        &self,
+        project_id: i32,
+        folder_id: i32,
+        data: &FolderData,
+    ) -> Result<Folder, FolderUpdateError> {
+        let mut tx = match self.pool.begin().await {
+            Ok(tx) => tx,
+            Err(err) => {
+                error!(%err);
+                return Err(FolderUpdateError::Unknown);
+            }
+        };
+
+        match folder_in_project(&mut tx, project_id, data.parent_id).await {
+            Ok(true) => (),
+            Ok(false) => return Err(FolderUpdateError::Missing),
+            Err(err) => {
+                error!(%err);
+                return Err(FolderUpdateError::Unknown);
+            }
+        }
+
+        // a folder cannot be moved into itself or any of its subfolders
+        let cycle_sql = "
+            WITH RECURSIVE descendants (folder_id) AS (
+                SELECT folder_id FROM folders WHERE folder_id = $1
+                UNION ALL
+                SELECT folders.folder_id
+                FROM folders
+                JOIN descendants ON folders.parent_id = descendants.folder_id
+            )
+            SELECT EXISTS (SELECT 1 FROM descendants WHERE folder_id = $2)
+        ";
+        if let Some(parent_id) = data.parent_id {
+            let cycle = sqlx::query_scalar::<_, bool>(cycle_sql)
+                .bind(folder_id)
+                .bind(parent_id)
+                .fetch_one(&mut tx)
+                .await;
+
+            match cycle {
+                Ok(false) => (),
+                Ok(true) => return Err(FolderUpdateError::Cycle),
+                Err(err) => {
+                    error!(%err);
+                    return Err(FolderUpdateError::Unknown);
+                }
+            }
+        }
+
+        let update_folder_sql = "
+            UPDATE folders
+            SET name = $3, parent_id = $4
+            WHERE project_id = $1 AND folder_id = $2
+            RETURNING folder_id, project_id, parent_id, name
+        ";
+        let folder = sqlx::query_as::<_, Folder>(update_folder_sql)
+            .bind(project_id)
+            .bind(folder_id)
+            .bind(&data.name)
+            .bind(data.parent_id)
+            .fetch_optional(&mut tx)
+            .await;
+
+        let folder = match folder {
+            Ok(Some(folder)) => folder,
+            Ok(None) => return Err(FolderUpdateError::Missing),
+            Err(err) if is_violation(&err, UNIQUE_VIOLATION) => {
+                return Err(FolderUpdateError::Duplicate)
+            }
+            Err(err) => {
+                error!(%err);
+                return Err(FolderUpdateError::Unknown);
+            }
+        };
+
+        match tx.commit().await {
+            Ok(_) => Ok(folder),
+            Err(err) => {
+                error!(%err);
+                Err(FolderUpdateError::Unknown)
+            }
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn delete(&self, project_id: i32, folder_id: i32) -> Result<(), FolderDeleteError> {
+        // contents keep their folder referenced, so only empty folders can be deleted
+        let result = sqlx::query("DELETE FROM folders WHERE project_id = $1 AND folder_id = $2")
+            .bind(project_id)
+            .bind(folder_id)
+            .execute(&self.pool)
+            .await;
+
+        match result {
+            Ok(result) if result.rows_affected() > 0 => Ok(()),
+            Ok(_) => Err(FolderDeleteError::Missing),
+            Err(err) if is_violation(&err, FOREIGN_KEY_VIOLATION) => {
+                Err(FolderDeleteError::NotEmpty)
+            }
+            Err(err) => {
+                error!(%err);
+                Err(FolderDeleteError::Unknown)
+            }
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn move_document(
+        &self,
+        project_id: i32,
+        document_id: i32,
+        folder_id: Option<i32>,
+    ) -> Result<(), FolderMoveError> {
+        let move_document_sql =
+            "UPDATE documents SET folder_id = $3 WHERE project_id = $1 AND document_id = $2";
+
+        self.move_file(move_document_sql, project_id, document_id, folder_id)
+            .await
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn move_resource(
+        &self,
+        project_id: i32,
+        resource_id: i32,
+        folder_id: Option<i32>,
+    ) -> Result<(), FolderMoveError> {
+        // resource names are unique within their folder
+        let move_resource_sql =
+            "UPDATE resources SET folder_id = $3 WHERE project_id = $1 AND resource_id = $2";
+
+        self.move_file(move_resource_sql, project_id, resource_id, folder_id)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests;
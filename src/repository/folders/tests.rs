@@ -0,0 +1,103 @@
+use sqlx::PgPool;
+
+use crate::repository::fixtures::{create_project, create_user, test_store};
+
+use super::*;
+
+async fn create_folder(pool: &PgPool, project_id: i32, name: &str) -> i32 {
+    sqlx::query_scalar("INSERT INTO folders (project_id, name) VALUES ($1, $2) RETURNING folder_id")
+        .bind(project_id)
+        .bind(name)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
+async fn create_resource(
+    pool: &PgPool,
+    project_id: i32,
+    folder_id: Option<i32>,
+    name: &str,
+) -> i32 {
+    sqlx::query_scalar(
+        "INSERT INTO resources (project_id, folder_id, name) VALUES ($1, $2, $3) RETURNING resource_id",
+    )
+    .bind(project_id)
+    .bind(folder_id)
+    .bind(name)
+    .fetch_one(pool)
+    .await
+    .unwrap()
+}
+
+#[sqlx::test]
+async fn move_document_into_folder_and_back(pool: PgPool) {
+    let store = test_store();
+    let owner_id = create_user(&pool).await;
+    let main = create_project(&pool, &store, owner_id).await;
+    let folder_id = create_folder(&pool, main.project_id, "chapters").await;
+    let repository = PgFolderRepository::new(&pool);
+
+    let folder_of = |pool: PgPool| async move {
+        sqlx::query_scalar::<_, Option<i32>>(
+            "SELECT folder_id FROM documents WHERE document_id = $1",
+        )
+        .bind(main.document_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap()
+    };
+
+    assert!(repository
+        .move_document(main.project_id, main.document_id, Some(folder_id))
+        .await
+        .is_ok());
+    assert_eq!(Some(folder_id), folder_of(pool.clone()).await);
+
+    assert!(repository
+        .move_document(main.project_id, main.document_id, None)
+        .await
+        .is_ok());
+    assert_eq!(None, folder_of(pool.clone()).await);
+}
+
+#[sqlx::test]
+async fn move_document_into_foreign_folder_error(pool: PgPool) {
+    let store = test_store();
+    let owner_id = create_user(&pool).await;
+    let main = create_project(&pool, &store, owner_id).await;
+    let other = create_project(&pool, &store, owner_id).await;
+    let foreign_folder_id = create_folder(&pool, other.project_id, "chapters").await;
+    let repository = PgFolderRepository::new(&pool);
+
+    assert!(matches!(
+        repository
+            .move_document(main.project_id, main.document_id, Some(foreign_folder_id))
+            .await,
+        Err(FolderMoveError::Missing)
+    ));
+    assert!(matches!(
+        repository
+            .move_document(other.project_id, main.document_id, None)
+            .await,
+        Err(FolderMoveError::Missing)
+    ));
+}
+
+#[sqlx::test]
+async fn move_resource_duplicate_name_error(pool: PgPool) {
+    let store = test_store();
+    let owner_id = create_user(&pool).await;
+    let project_id = create_project(&pool, &store, owner_id).await.project_id;
+    let folder_id = create_folder(&pool, project_id, "figures").await;
+    let resource_id = create_resource(&pool, project_id, None, "plot.png").await;
+    create_resource(&pool, project_id, Some(folder_id), "plot.png").await;
+    let repository = PgFolderRepository::new(&pool);
+
+    assert!(matches!(
+        repository
+            .move_resource(project_id, resource_id, Some(folder_id))
+            .await,
+        Err(FolderMoveError::Duplicate)
+    ));
+}
@@ -1,6 +1,21 @@
+pub mod accounts;
+pub mod api_tokens;
+pub mod blobs;
+pub mod comments;
 pub mod documents;
+pub mod email_verifications;
+pub mod events;
+pub mod folders;
+pub mod password_resets;
+pub mod profiles;
 pub mod projects;
 pub mod resources;
 pub mod sessions;
 pub mod sharing;
+pub mod snapshots;
+pub mod suggestions;
+pub mod usage;
 pub mod users;
+
+#[cfg(test)]
+mod fixtures;
@@ -0,0 +1,159 @@
+use axum::async_trait;
+use mockall::automock;
+use rand::{distributions::Alphanumeric, Rng};
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use tracing::error;
+
+use crate::constants::PASSWORD_RESET_TOKEN_LIFETIME_IN_SECONDS;
+
+const TOKEN_LENGTH: usize = 64;
+
+pub enum PasswordResetInsertError {
+    Unknown,
+}
+
+pub enum PasswordResetRedeemError {
+    /// The token does not exist, expired or was already used
+    Invalid,
+    Unknown,
+}
+
+pub(super) fn generate_token() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(TOKEN_LENGTH)
+        .map(char::from)
+        .collect()
+}
+
+pub(super) fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+#[automock]
+#[async_trait]
+pub trait PasswordResetRepository {
+    /// Issues a single-use token for the user, only its hash is stored
+    async fn insert(&self, user_id: i32) -> Result<String, PasswordResetInsertError>;
+    /// Sets the new password, ends every session of the user and revokes their access tokens,
+    /// returning their id
+    async fn redeem(
+        &self,
+        token: &str,
+        password_hash: &str,
+    ) -> Result<i32, PasswordResetRedeemError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct PgPasswordResetRepository {
+    pub pool: PgPool,
+}
+
+impl PgPasswordResetRepository {
+    pub fn new(pool: &PgPool) -> Self {
+        Self { pool: pool.clone() }
+    }
+}
+
+#[async_trait]
+impl PasswordResetRepository for PgPasswordResetRepository {
+    #[tracing::instrument(skip(self))]
+    async fn insert(&self, user_id: i32) -> Result<String, PasswordResetInsertError> {
+        let insert_token_sql = "
+            INSERT INTO password_resets (token_hash, user_id, expires_at)
+            VALUES ($1, $2, NOW() + $3 * INTERVAL '1 second')
+        ";
+
+        let token = generate_token();
+
+        match sqlx::query(insert_token_sql)
+            .bind(hash_token(&token))
+            .bind(user_id)
+            .bind(*PASSWORD_RESET_TOKEN_LIFETIME_IN_SECONDS as f64)
+            .execute(&self.pool)
+            .await
+        {
+            Ok(_) => Ok(token),
+            Err(err) => {
+                error!(%err);
+                Err(PasswordResetInsertError::Unknown)
+            }
+        }
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn redeem(
+        &self,
+        token: &str,
+        password_hash: &str,
+    ) -> Result<i32, PasswordResetRedeemError> {
+        let mut tx = match self.pool.begin().await {
+            Ok(tx) => tx,
+            Err(err) => {
+                error!(%err);
+                return Err(PasswordResetRedeemError::Unknown);
+            }
+        };
+
+        let use_token_sql = "
+            UPDATE password_resets
+            SET used_at = NOW()
+            WHERE token_hash = $1 AND used_at IS NULL AND expires_at > NOW()
+            RETURNING user_id
+        ";
+        // any other outstanding token of the user is spent as well
+        let use_other_tokens_sql = "
+            UPDATE password_resets
+            SET used_at = NOW()
+            WHERE user_id = $1 AND used_at IS NULL
+        ";
+        let update_password_sql = "
+            UPDATE users
+            SET password_hash = $2, updated_at = NOW()
+            WHERE user_id = $1
+        ";
+        let delete_sessions_sql = "DELETE FROM sessions WHERE user_id = $1";
+        let delete_tokens_sql = "DELETE FROM api_tokens WHERE user_id = $1";
+
+        let user_id = match sqlx::query_scalar::<_, i32>(use_token_sql)
+            .bind(hash_token(token))
+            .fetch_optional(&mut tx)
+            .await
+        {
+            Ok(Some(user_id)) => user_id,
+            Ok(None) => return Err(PasswordResetRedeemError::Invalid),
+            Err(err) => {
+                error!(%err);
+                return Err(PasswordResetRedeemError::Unknown);
+            }
+        };
+
+        for (sql, password_hash) in [
+            (use_other_tokens_sql, None),
+            (update_password_sql, Some(password_hash)),
+            (delete_sessions_sql, None),
+            (delete_tokens_sql, None),
+        ] {
+            let mut query = sqlx::query(sql).bind(user_id);
+            if let Some(password_hash) = password_hash {
+                query = query.bind(password_hash);
+            }
+            if let Err(err) = query.execute(&mut tx).await {
+                error!(%err);
+                return Err(PasswordResetRedeemError::Unknown);
+            }
+        }
+
+        match tx.commit().await {
+            Ok(()) => Ok(user_id),
+            Err(err) => {
+                error!(%err);
+                Err(PasswordResetRedeemError::Unknown)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,24 @@
+use sqlx::PgPool;
+
+use crate::repository::{
+    api_tokens::{ApiTokenGetError, ApiTokenRepository, PgApiTokenRepository},
+    fixtures::{create_api_token, create_user},
+};
+
+use super::*;
+
+#[sqlx::test]
+async fn redeem_revokes_api_tokens(pool: PgPool) {
+    let user_id = create_user(&pool).await;
+    let secret = create_api_token(&pool, user_id).await;
+    let repository = PgPasswordResetRepository::new(&pool);
+    let token = repository.insert(user_id).await.ok().unwrap();
+
+    let result = repository.redeem(&token, "new hash").await;
+
+    assert!(matches!(result, Ok(id) if id == user_id));
+    assert!(matches!(
+        PgApiTokenRepository::new(&pool).authenticate(&secret).await,
+        Err(ApiTokenGetError::Missing)
+    ));
+}
@@ -0,0 +1,202 @@
+use axum::async_trait;
+use mockall::automock;
+use sqlx::PgPool;
+use tracing::{error, warn};
+
+use crate::{
+    domain::users::{Profile, ProfileData},
+    storage::{avatar_key, BlobError, BlobStream, SharedBlobStore},
+};
+
+pub enum ProfileGetError {
+    Missing,
+    Unknown,
+}
+
+pub enum ProfileUpdateError {
+    Missing,
+    NoSpace,
+    Unknown,
+}
+
+#[automock]
+#[async_trait]
+pub trait ProfileRepository {
+    async fn get(&self, user_id: i32) -> Result<Profile, ProfileGetError>;
+    async fn update(&self, user_id: i32, data: &ProfileData)
+        -> Result<Profile, ProfileUpdateError>;
+    /// Returns the content type of the avatar along with its content
+    async fn get_avatar(&self, user_email: &str) -> Result<(String, BlobStream), ProfileGetError>;
+    async fn put_avatar(
+        &self,
+        user_id: i32,
+        content_type: &str,
+        content: &[u8],
+    ) -> Result<(), ProfileUpdateError>;
+    async fn delete_avatar(&self, user_id: i32) -> Result<(), ProfileUpdateError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct PgProfileRepository {
+    pub pool: PgPool,
+    pub store: SharedBlobStore,
+}
+
+impl PgProfileRepository {
+    pub fn new(pool: &PgPool, store: &SharedBlobStore) -> Self {
+        Self {
+            pool: pool.clone(),
+            store: store.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl ProfileRepository for PgProfileRepository {
+    #[tracing::instrument(skip(self))]
+    async fn get(&self, user_id: i32) -> Result<Profile, ProfileGetError> {
+        let profile_get_sql = "
+            SELECT display_name, affiliation, orcid, avatar_content_type IS NOT NULL AS has_avatar
+            FROM users
+            WHERE user_id = $1 AND deleted_at IS NULL
+        ";
+
+        let result = sqlx::query_as::<_, Profile>(profile_get_sql)
+            .bind(user_id)
+            .fetch_optional(&self.pool)
+            .await;
+        match result {
+            Ok(Some(profile)) => Ok(profile),
+            Ok(None) => Err(ProfileGetError::Missing),
+            Err(err) => {
+                error!(%err);
+                Err(ProfileGetError::Unknown)
+            }
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn update(
+        &self,
+        user_id: i32,
+        data: &ProfileData,
+    ) -> Result<Profile, ProfileUpdateError> {
+        let profile_update_sql = "
+            UPDATE users
+            SET display_name = $2, affiliation = $3, orcid = $4, updated_at = NOW()
+            WHERE user_id = $1 AND deleted_at IS NULL
+            RETURNING display_name, affiliation, orcid, avatar_content_type IS NOT NULL AS has_avatar
+        ";
+
+        let result = sqlx::query_as::<_, Profile>(profile_update_sql)
+            .bind(user_id)
+            .bind(&data.display_name)
+            .bind(&data.affiliation)
+            .bind(&data.orcid)
+            .fetch_optional(&self.pool)
+            .await;
+        match result {
+            Ok(Some(profile)) => Ok(profile),
+            Ok(None) => Err(ProfileUpdateError::Missing),
+            Err(err) => {
+                error!(%err);
+                Err(ProfileUpdateError::Unknown)
+            }
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn get_avatar(&self, user_email: &str) -> Result<(String, BlobStream), ProfileGetError> {
+        let avatar_get_sql = "
+            SELECT user_id, avatar_content_type
+            FROM users
+            WHERE email = $1 AND avatar_content_type IS NOT NULL
+        ";
+
+        let result = sqlx::query_as::<_, (i32, String)>(avatar_get_sql)
+            .bind(user_email)
+            .fetch_optional(&self.pool)
+            .await;
+        let (user_id, content_type) = match result {
+            Ok(Some(avatar)) => avatar,
+            Ok(None) => return Err(ProfileGetError::Missing),
+            Err(err) => {
+                error!(%err);
+                return Err(ProfileGetError::Unknown);
+            }
+        };
+
+        match self.store.stream(&avatar_key(user_id)).await {
+            Ok(stream) => Ok((content_type, stream)),
+            Err(BlobError::Missing) => Err(ProfileGetError::Missing),
+            Err(BlobError::NoSpace | BlobError::Unknown) => Err(ProfileGetError::Unknown),
+        }
+    }
+
+    #[tracing::instrument(skip(self, content))]
+    async fn put_avatar(
+        &self,
+        user_id: i32,
+        content_type: &str,
+        content: &[u8],
+    ) -> Result<(), ProfileUpdateError> {
+        // the user row is checked first, so no file is written for a missing user
+        if let Err(err) = self.get(user_id).await {
+            return Err(match err {
+                ProfileGetError::Missing => ProfileUpdateError::Missing,
+                ProfileGetError::Unknown => ProfileUpdateError::Unknown,
+            });
+        }
+
+        match self.store.put(&avatar_key(user_id), content).await {
+            Ok(()) => (),
+            Err(BlobError::NoSpace) => return Err(ProfileUpdateError::NoSpace),
+            Err(BlobError::Missing | BlobError::Unknown) => {
+                return Err(ProfileUpdateError::Unknown)
+            }
+        }
+
+        let result = sqlx::query(
+            "UPDATE users SET avatar_content_type = $2, updated_at = NOW() WHERE user_id = $1",
+        )
+        .bind(user_id)
+        .bind(content_type)
+        .execute(&self.pool)
+        .await;
+        match result {
+            Ok(_) => Ok(()),
+            Err(err) => {
+                error!(%err);
+                Err(ProfileUpdateError::Unknown)
+            }
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn delete_avatar(&self, user_id: i32) -> Result<(), ProfileUpdateError> {
+        let avatar_delete_sql = "
+            UPDATE users
+            SET avatar_content_type = NULL, updated_at = NOW()
+            WHERE user_id = $1 AND avatar_content_type IS NOT NULL
+        ";
+
+        let result = sqlx::query(avatar_delete_sql)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await;
+        match result {
+            Ok(result) if result.rows_affected() == 0 => return Err(ProfileUpdateError::Missing),
+            Ok(_) => (),
+            Err(err) => {
+                error!(%err);
+                return Err(ProfileUpdateError::Unknown);
+            }
+        }
+
+        // the file is no longer referenced, the collector removes it when this fails
+        if let Err(err) = self.store.delete(&avatar_key(user_id)).await {
+            warn!(?err, "Could not delete avatar");
+        }
+        Ok(())
+    }
+}
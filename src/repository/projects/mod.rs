@@ -0,0 +1,405 @@
+use axum::async_trait;
+use mockall::automock;
+use sqlx::{PgPool, Postgres, Transaction};
+use tracing::{error, info, warn};
+
+use crate::{
+    domain::{
+        crud::CrudInt,
+        documents::Document,
+        projects::{Project, ProjectMetadata, ProjectSettings},
+    },
+    storage::{document_key, SharedBlobStore},
+};
+
+pub enum ProjectInsertError {
+    Unknown,
+}
+pub enum ProjectUpdateError {
+    Unknown,
+}
+pub enum ProjectDeleteError {
+    Unknown,
+}
+pub enum ProjectGetError {
+    Missing,
+    Unknown,
+}
+
+/// Removes the projects with everything they contain. Their stored files are left
+/// for garbage collection, which picks them up once no project owns their directory.
+pub async fn delete_projects(
+    tx: &mut Transaction<'_, Postgres>,
+    project_ids: &[i32],
+) -> Result<(), sqlx::Error> {
+    // shared contents lose one reference per resource and snapshot file
+    let release_blobs_sql = "
+        UPDATE blobs
+        SET ref_count = ref_count - refs.count, updated_at = NOW()
+        FROM (
+            SELECT content_hash, COUNT(*) AS count
+            FROM (
+                SELECT sf.content_hash
+                FROM snapshot_files AS sf
+                JOIN snapshots AS s
+                ON sf.snapshot_id = s.snapshot_id
+                WHERE s.project_id = ANY($1)
+                UNION ALL
+                SELECT content_hash
+                FROM resources
+                WHERE project_id = ANY($1) AND content_hash IS NOT NULL
+            ) AS hashes
+            GROUP BY content_hash
+        ) AS refs
+        WHERE blobs.content_hash = refs.content_hash
+    ";
+    let statements = [
+        "UPDATE projects SET main_document_id = NULL WHERE project_id = ANY($1)",
+        "
+            DELETE FROM comment_mentions
+            WHERE comment_id IN (
+                SELECT c.comment_id
+                FROM comments AS c
+                JOIN comment_threads AS t
+                ON c.thread_id = t.thread_id
+                WHERE t.project_id = ANY($1)
+            )
+        ",
+        "
+            DELETE FROM comments
+            WHERE thread_id IN (SELECT thread_id FROM comment_threads WHERE project_id = ANY($1))
+        ",
+        "DELETE FROM comment_threads WHERE project_id = ANY($1)",
+        "DELETE FROM suggestions WHERE project_id = ANY($1)",
+        release_blobs_sql,
+        "
+            DELETE FROM snapshot_files
+            WHERE snapshot_id IN (SELECT snapshot_id FROM snapshots WHERE project_id = ANY($1))
+        ",
+        "DELETE FROM snapshots WHERE project_id = ANY($1)",
+        "DELETE FROM project_events WHERE project_id = ANY($1)",
+        "DELETE FROM sharing WHERE project_id = ANY($1)",
+        "DELETE FROM tokens WHERE project_id = ANY($1)",
+        "DELETE FROM api_tokens WHERE project_id = ANY($1)",
+        "
+            DELETE FROM encrypted_blobs
+            WHERE key_id IN (SELECT key_id FROM data_keys WHERE project_id = ANY($1))
+        ",
+        // without its data key, anything left of an encrypted project is unreadable
+        "DELETE FROM data_keys WHERE project_id = ANY($1)",
+        "DELETE FROM resources WHERE project_id = ANY($1)",
+        "DELETE FROM documents WHERE project_id = ANY($1)",
+        "DELETE FROM folders WHERE project_id = ANY($1)",
+        "DELETE FROM projects WHERE project_id = ANY($1)",
+    ];
+
+    for sql in statements {
+        sqlx::query(sql).bind(project_ids).execute(&mut *tx).await?;
+    }
+
+    Ok(())
+}
+
+#[automock]
+#[async_trait]
+pub trait ProjectRepository {
+    async fn get(&self, id: i32) -> Result<Vec<Project>, ProjectGetError>;
+    async fn get_meta(&self, project_id: i32) -> Result<Project, ProjectGetError>;
+    async fn has_access(&self, project_id: i32, user_id: i32) -> Result<bool, ProjectGetError>;
+    async fn insert(
+        &self,
+        data: &ProjectMetadata,
+        owner_id: i32,
+    ) -> Result<Project, ProjectInsertError>;
+    async fn update(&self, id: i32, data: &ProjectMetadata) -> Result<(), ProjectUpdateError>;
+    async fn update_settings(
+        &self,
+        id: i32,
+        data: &ProjectSettings,
+    ) -> Result<(), ProjectUpdateError>;
+    async fn delete(&self, id: i32) -> Result<(), ProjectDeleteError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct PgProjectRepository {
+    pub pool: PgPool,
+    pub store: SharedBlobStore,
+}
+
+impl PgProjectRepository {
+    pub fn new(pool: &PgPool, store: &SharedBlobStore) -> Self {
+        Self {
+            pool: pool.clone(),
+            store: store.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl ProjectRepository for PgProjectRepository {
+    #[tracing::instrument(skip(self))]
+    async fn get(&self, id: i32) -> Result<Vec<Project>, ProjectGetError> {
+        let projects = sqlx::query_as::<_, Project>(
+            "
+            SELECT p.project_id, p.project_name, p.main_document_id, p.created_at, p.last_modified, p.track_changes, p.owner_id, u.email, u.display_name, pu.used_bytes
+            FROM projects as p 
+            JOIN users as u
+            ON p.owner_id = u.user_id
+            JOIN project_usage as pu
+            ON p.project_id = pu.project_id
+            LEFT JOIN (
+                SELECT project_id, friend_id 
+                FROM sharing
+                WHERE friend_id = $1
+            ) As shared
+            ON p.project_id= shared.project_id
+            WHERE p.owner_id = $1 OR shared.friend_id = $1 
+        ",
+        )
+        .bind(id)
+        .fetch_all(&self.pool)
+        .await;
+
+        match projects {
+            Ok(projects) if !projects.is_empty() => Ok(projects),
+            Ok(projects) if projects.is_empty() => Ok(Vec::new()),
+            Ok(_projects) => Err(ProjectGetError::Missing),
+            Err(err) => {
+                error!(%err);
+                return Err(ProjectGetError::Unknown);
+            }
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn get_meta(&self, project_id: i32) -> Result<Project, ProjectGetError> {
+        let sql = "
+            SELECT p.project_id, p.project_name, p.main_document_id, p.created_at, p.last_modified, p.track_changes, p.owner_id, u.email, u.display_name, pu.used_bytes
+            FROM projects as p 
+            JOIN users as u
+            ON p.owner_id = u.user_id
+            JOIN project_usage as pu
+            ON p.project_id = pu.project_id
+            WHERE p.project_id = $1
+        ";
+
+        let project = sqlx::query_as::<_, Project>(sql)
+            .bind(project_id)
+            .fetch_optional(&self.pool)
+            .await;
+
+        match project {
+            Ok(Some(project)) => Ok(project),
+            Ok(None) => {
+                warn!("Missing project");
+                Err(ProjectGetError::Missing)
+            }
+            Err(err) => {
+                error!(%err);
+                Err(ProjectGetError::Unknown)
+            }
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn has_access(&self, project_id: i32, user_id: i32) -> Result<bool, ProjectGetError> {
+        // no row means no project, a false one means no access
+        let sql = "
+            SELECT owner_id = $2 OR EXISTS (
+                SELECT 1 FROM sharing WHERE project_id = $1 AND friend_id = $2
+            )
+            FROM projects
+            WHERE project_id = $1
+        ";
+
+        let result = sqlx::query_scalar::<_, bool>(sql)
+            .bind(project_id)
+            .bind(user_id)
+            .fetch_optional(&self.pool)
+            .await;
+
+        match result {
+            Ok(Some(access)) => Ok(access),
+            Ok(None) => Err(ProjectGetError::Missing),
+            Err(err) => {
+                error!(%err);
+                Err(ProjectGetError::Unknown)
+            }
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn update(
+        &self,
+        id: i32,
+        project_metadata: &ProjectMetadata,
+    ) -> Result<(), ProjectUpdateError> {
+        let result = sqlx::query(
+            "
+            UPDATE projects 
+            SET project_name = $1
+            WHERE project_id = $2
+        ",
+        )
+        .bind(&project_metadata.name)
+        .bind(id)
+        .execute(&self.pool)
+        .await;
+
+        match result {
+            Ok(_result) => Ok(()),
+            Err(err) => {
+                error!(%err);
+                return Err(ProjectUpdateError::Unknown);
+            }
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn update_settings(
+        &self,
+        id: i32,
+        project_settings: &ProjectSettings,
+    ) -> Result<(), ProjectUpdateError> {
+        let result = sqlx::query(
+            "
+            UPDATE projects
+            SET track_changes = $1
+            WHERE project_id = $2
+        ",
+        )
+        .bind(project_settings.track_changes)
+        .bind(id)
+        .execute(&self.pool)
+        .await;
+
+        match result {
+            Ok(_result) => Ok(()),
+            Err(err) => {
+                error!(%err);
+                Err(ProjectUpdateError::Unknown)
+            }
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn insert(
+        &self,
+        project_data: &ProjectMetadata,
+        owner_id: i32,
+    ) -> Result<Project, ProjectInsertError> {
+        let mut tx: sqlx::Transaction<'_, sqlx::Postgres> = match self.pool.begin().await {
+            Ok(tx) => tx,
+            Err(err) => {
+                error!(%err);
+                return Err(ProjectInsertError::Unknown);
+            }
+        };
+        info!("Transaction acquired");
+
+        let insert_document_sql = "
+            INSERT INTO documents (name) 
+            VALUES ('main.tex')
+            RETURNING document_id as id
+        ";
+        let insert_document_result =
+            sqlx::query_as::<_, CrudInt>(insert_document_sql).fetch_one(&mut tx);
+
+        let document_id = match insert_document_result.await {
+            Ok(document_id) => document_id.id,
+            Err(err) => {
+                error!(%err);
+                return Err(ProjectInsertError::Unknown);
+            }
+        };
+
+        info!("Created document {}", document_id);
+
+        let insert_project_sql = "
+            WITH inserted AS (
+                INSERT INTO projects (main_document_id, owner_id, project_name)
+                VALUES ($1, $2, $3)
+                RETURNING project_id, main_document_id, owner_id, project_name, created_at, last_modified, track_changes
+            )
+            SELECT inserted.*, users.email, users.display_name, 0::BIGINT AS used_bytes
+            FROM inserted
+            JOIN users
+            ON inserted.owner_id = users.user_id
+        ";
+        let insert_project_result = sqlx::query_as::<_, Project>(insert_project_sql)
+            .bind(document_id)
+            .bind(owner_id)
+            .bind(&project_data.name)
+            .fetch_one(&mut tx);
+
+        let project = match insert_project_result.await {
+            Ok(project) => project,
+            Err(err) => {
+                error!(%err);
+                return Err(ProjectInsertError::Unknown);
+            }
+        };
+
+        let update_document_sql = "
+            UPDATE documents
+            SET project_id = $1
+            WHERE document_id = $2
+        ";
+        let update_document_result = sqlx::query(update_document_sql)
+            .bind(project.project_id)
+            .bind(document_id)
+            .execute(&mut tx);
+
+        if let Err(err) = update_document_result.await {
+            error!(%err);
+            return Err(ProjectInsertError::Unknown);
+        }
+
+        let document = Document {
+            document_id,
+            project_id: project.project_id,
+            name: String::from("main.tex"),
+            folder_id: None,
+        };
+
+        if self.store.put(&document_key(&document), b"").await.is_err() {
+            return Err(ProjectInsertError::Unknown);
+        }
+
+        match tx.commit().await {
+            Ok(_) => Ok(project),
+            Err(err) => {
+                error!(%err);
+                Err(ProjectInsertError::Unknown)
+            }
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn delete(&self, id: i32) -> Result<(), ProjectDeleteError> {
+        let mut tx = match self.pool.begin().await {
+            Ok(tx) => tx,
+            Err(err) => {
+                error!(%err);
+                return Err(ProjectDeleteError::Unknown);
+            }
+        };
+
+        // the stored files are left to the garbage collector
+        if let Err(err) = delete_projects(&mut tx, &[id]).await {
+            error!(%err);
+            return Err(ProjectDeleteError::Unknown);
+        }
+
+        match tx.commit().await {
+            Ok(_) => Ok(()),
+            Err(err) => {
+                error!(%err);
+                Err(ProjectDeleteError::Unknown)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,55 @@
+use sqlx::PgPool;
+
+use crate::repository::fixtures::{create_project, create_user, share, test_store};
+
+use super::*;
+
+#[sqlx::test]
+async fn has_access_tells_owners_collaborators_and_others_apart(pool: PgPool) {
+    let store = test_store();
+    let owner_id = create_user(&pool).await;
+    let collaborator_id = create_user(&pool).await;
+    let stranger_id = create_user(&pool).await;
+    let project_id = create_project(&pool, &store, owner_id).await.project_id;
+    share(&pool, project_id, collaborator_id).await;
+    let repository = PgProjectRepository::new(&pool, &store);
+
+    assert!(matches!(
+        repository.has_access(project_id, owner_id).await,
+        Ok(true)
+    ));
+    assert!(matches!(
+        repository.has_access(project_id, collaborator_id).await,
+        Ok(true)
+    ));
+    assert!(matches!(
+        repository.has_access(project_id, stranger_id).await,
+        Ok(false)
+    ));
+}
+
+#[sqlx::test]
+async fn has_access_missing_project_error(pool: PgPool) {
+    let store = test_store();
+    let user_id = create_user(&pool).await;
+    let repository = PgProjectRepository::new(&pool, &store);
+
+    assert!(matches!(
+        repository.has_access(i32::MAX, user_id).await,
+        Err(ProjectGetError::Missing)
+    ));
+}
+
+#[sqlx::test]
+async fn delete_project_removes_it(pool: PgPool) {
+    let store = test_store();
+    let owner_id = create_user(&pool).await;
+    let main = create_project(&pool, &store, owner_id).await;
+    let repository = PgProjectRepository::new(&pool, &store);
+
+    assert!(repository.delete(main.project_id).await.is_ok());
+    assert!(matches!(
+        repository.has_access(main.project_id, owner_id).await,
+        Err(ProjectGetError::Missing)
+    ));
+}
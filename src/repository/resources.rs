@@ -1,25 +1,43 @@
 use axum::async_trait;
 use mockall::automock;
 use sqlx::PgPool;
-use tracing::error;
+use tracing::{error, warn};
 
 use crate::{
-    domain::resources::{Resource, ResourceMetadata},
-    filesystem::{get_resource_path, write_file, FileWriteError},
+    domain::{
+        resources::{Resource, ResourceMetadata},
+        usage::QuotaExceeded,
+    },
+    repository::{
+        blobs::{acquire_blob, reference_blob, release_blob},
+        folders::folder_in_project,
+        usage::{check_quota, QuotaError},
+    },
+    storage::{resource_key, BlobError, BlobStream, SharedBlobStore},
 };
 
 pub enum ResourceInsertError {
+    Missing,
     Duplicate,
+    QuotaExceeded(QuotaExceeded),
     Unknown,
 }
 pub enum ResourceUpdateError {
     Missing,
+    NoSpace,
+    QuotaExceeded(QuotaExceeded),
     Unknown,
 }
 pub enum ResourceGetError {
     Missing,
     Unknown,
 }
+pub enum ResourceCopyError {
+    Missing,
+    Duplicate,
+    QuotaExceeded(QuotaExceeded),
+    Unknown,
+}
 
 #[automock]
 #[async_trait]
@@ -41,16 +59,28 @@ pub trait ResourceRepository {
         resource_id: i32,
         content: &[u8],
     ) -> Result<(), ResourceUpdateError>;
+    async fn copy(
+        &self,
+        resource: &Resource,
+        target_project_id: i32,
+        target: &ResourceMetadata,
+    ) -> Result<Resource, ResourceCopyError>;
+    async fn read_stream(&self, resource: &Resource) -> Result<BlobStream, ResourceGetError>;
+    async fn download_url(&self, resource: &Resource) -> Result<Option<String>, ResourceGetError>;
 }
 
 #[derive(Debug, Clone)]
 pub struct PgResourceRepository {
     pub pool: PgPool,
+    pub store: SharedBlobStore,
 }
 
 impl PgResourceRepository {
-    pub fn new(pool: &PgPool) -> Self {
-        Self { pool: pool.clone() }
+    pub fn new(pool: &PgPool, store: &SharedBlobStore) -> Self {
+        Self {
+            pool: pool.clone(),
+            store: store.clone(),
+        }
     }
 }
 
@@ -59,7 +89,7 @@ impl ResourceRepository for PgResourceRepository {
     #[tracing::instrument(skip(self))]
     async fn get(&self, project_id: i32) -> Result<Vec<Resource>, ResourceGetError> {
         let resource_get_sql = "
-            SELECT resource_id, project_id, name
+            SELECT resource_id, project_id, name, folder_id, content_hash
             FROM resources
             WHERE project_id = $1
         ";
@@ -85,7 +115,7 @@ impl ResourceRepository for PgResourceRepository {
         resource_id: i32,
     ) -> Result<Resource, ResourceGetError> {
         let resource_get_sql = "
-            SELECT resource_id, project_id, name
+            SELECT resource_id, project_id, name, folder_id, content_hash
             FROM resources
             WHERE project_id = $1 AND resource_id = $2
         ";
@@ -112,17 +142,205 @@ impl ResourceRepository for PgResourceRepository {
         resource_id: i32,
         content: &[u8],
     ) -> Result<(), ResourceUpdateError> {
-        let resource = match self.get_meta(project_id, resource_id).await {
-            Ok(resource) => resource,
-            Err(ResourceGetError::Missing) => return Err(ResourceUpdateError::Missing),
-            Err(ResourceGetError::Unknown) => return Err(ResourceUpdateError::Unknown),
+        let mut tx = match self.pool.begin().await {
+            Ok(tx) => tx,
+            Err(err) => {
+                error!(%err);
+                return Err(ResourceUpdateError::Unknown);
+            }
+        };
+
+        let resource_lock_sql = "
+            SELECT resource_id, project_id, name, folder_id, content_hash
+            FROM resources
+            WHERE project_id = $1 AND resource_id = $2
+            FOR UPDATE
+        ";
+        let resource = sqlx::query_as::<_, Resource>(resource_lock_sql)
+            .bind(project_id)
+            .bind(resource_id)
+            .fetch_optional(&mut tx)
+            .await;
+
+        let resource = match resource {
+            Ok(Some(resource)) => resource,
+            Ok(None) => return Err(ResourceUpdateError::Missing),
+            Err(err) => {
+                error!(%err);
+                return Err(ResourceUpdateError::Unknown);
+            }
+        };
+
+        let previous_size =
+            sqlx::query_scalar::<_, i64>("SELECT size FROM resources WHERE resource_id = $1")
+                .bind(resource_id)
+                .fetch_one(&mut tx)
+                .await;
+        let previous_size = match previous_size {
+            Ok(previous_size) => previous_size,
+            Err(err) => {
+                error!(%err);
+                return Err(ResourceUpdateError::Unknown);
+            }
+        };
+
+        let size = content.len() as i64;
+        if size > previous_size {
+            match check_quota(&mut tx, project_id, size - previous_size).await {
+                Ok(()) => (),
+                Err(QuotaError::Exceeded(exceeded)) => {
+                    return Err(ResourceUpdateError::QuotaExceeded(exceeded))
+                }
+                Err(QuotaError::Unknown) => return Err(ResourceUpdateError::Unknown),
+            }
+        }
+
+        let content_hash = match acquire_blob(&mut tx, &self.store, content).await {
+            Ok(content_hash) => content_hash,
+            Err(BlobError::NoSpace) => return Err(ResourceUpdateError::NoSpace),
+            Err(_) => return Err(ResourceUpdateError::Unknown),
+        };
+
+        let result =
+            sqlx::query("UPDATE resources SET content_hash = $1, size = $2 WHERE resource_id = $3")
+                .bind(&content_hash)
+                .bind(size)
+                .bind(resource_id)
+                .execute(&mut tx)
+                .await;
+
+        if let Err(err) = result {
+            error!(%err);
+            return Err(ResourceUpdateError::Unknown);
+        }
+
+        if let Some(previous_hash) = &resource.content_hash {
+            if release_blob(&mut tx, previous_hash).await.is_err() {
+                return Err(ResourceUpdateError::Unknown);
+            }
+        }
+
+        if let Err(err) = tx.commit().await {
+            error!(%err);
+            return Err(ResourceUpdateError::Unknown);
+        }
+
+        if resource.content_hash.is_none() {
+            // content moved from the legacy per-project location into the shared objects
+            if self.store.delete(&resource_key(&resource)).await.is_err() {
+                warn!(
+                    resource.resource_id,
+                    "Could not remove legacy resource content"
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn copy(
+        &self,
+        resource: &Resource,
+        target_project_id: i32,
+        target: &ResourceMetadata,
+    ) -> Result<Resource, ResourceCopyError> {
+        let mut tx = match self.pool.begin().await {
+            Ok(tx) => tx,
+            Err(err) => {
+                error!(%err);
+                return Err(ResourceCopyError::Unknown);
+            }
         };
 
-        write_file(get_resource_path(&resource), content, false)
+        match folder_in_project(&mut tx, target_project_id, target.folder_id).await {
+            Ok(true) => (),
+            Ok(false) => return Err(ResourceCopyError::Missing),
+            Err(err) => {
+                error!(%err);
+                return Err(ResourceCopyError::Unknown);
+            }
+        }
+
+        let size =
+            sqlx::query_scalar::<_, i64>("SELECT size FROM resources WHERE resource_id = $1")
+                .bind(resource.resource_id)
+                .fetch_optional(&mut tx)
+                .await;
+        let size = match size {
+            Ok(Some(size)) => size,
+            Ok(None) => return Err(ResourceCopyError::Missing),
+            Err(err) => {
+                error!(%err);
+                return Err(ResourceCopyError::Unknown);
+            }
+        };
+
+        match check_quota(&mut tx, target_project_id, size).await {
+            Ok(()) => (),
+            Err(QuotaError::Exceeded(exceeded)) => {
+                return Err(ResourceCopyError::QuotaExceeded(exceeded))
+            }
+            Err(QuotaError::Unknown) => return Err(ResourceCopyError::Unknown),
+        }
+
+        let content_hash = match &resource.content_hash {
+            Some(content_hash) => reference_blob(&mut tx, content_hash)
+                .await
+                .map(|_| content_hash.clone()),
+            None => match self.store.get(&resource_key(resource)).await {
+                Ok(content) => acquire_blob(&mut tx, &self.store, &content).await,
+                Err(err) => Err(err),
+            },
+        };
+
+        let content_hash = match content_hash {
+            Ok(content_hash) => content_hash,
+            Err(BlobError::Missing) => return Err(ResourceCopyError::Missing),
+            Err(BlobError::NoSpace | BlobError::Unknown) => return Err(ResourceCopyError::Unknown),
+        };
+
+        let insert_resource_sql = "
+            INSERT INTO resources (project_id, name, folder_id, content_hash, size)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT DO NOTHING
+            RETURNING resource_id, project_id, name, folder_id, content_hash
+        ";
+        let copy = sqlx::query_as::<_, Resource>(insert_resource_sql)
+            .bind(target_project_id)
+            .bind(&target.name)
+            .bind(target.folder_id)
+            .bind(&content_hash)
+            .bind(size)
+            .fetch_optional(&mut tx)
+            .await;
+
+        let copy = match copy {
+            Ok(Some(copy)) => copy,
+            Ok(None) => return Err(ResourceCopyError::Duplicate),
+            Err(err) => {
+                error!(%err);
+                return Err(ResourceCopyError::Unknown);
+            }
+        };
+
+        match tx.commit().await {
+            Ok(_) => Ok(copy),
+            Err(err) => {
+                error!(%err);
+                Err(ResourceCopyError::Unknown)
+            }
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn read_stream(&self, resource: &Resource) -> Result<BlobStream, ResourceGetError> {
+        self.store
+            .stream(&resource_key(resource))
             .await
             .map_err(|err| match err {
-                FileWriteError::Missing => ResourceUpdateError::Missing,
-                FileWriteError::Unknown => ResourceUpdateError::Unknown,
+                BlobError::Missing => ResourceGetError::Missing,
+                BlobError::NoSpace | BlobError::Unknown => ResourceGetError::Unknown,
             })
     }
 
@@ -140,16 +358,40 @@ impl ResourceRepository for PgResourceRepository {
             }
         };
 
+        match folder_in_project(&mut tx, project_id, resource_data.folder_id).await {
+            Ok(true) => (),
+            Ok(false) => return Err(ResourceInsertError::Missing),
+            Err(err) => {
+                error!(%err);
+                return Err(ResourceInsertError::Unknown);
+            }
+        }
+
+        // new files are refused once the project or its owner is over quota
+        match check_quota(&mut tx, project_id, 0).await {
+            Ok(()) => (),
+            Err(QuotaError::Exceeded(exceeded)) => {
+                return Err(ResourceInsertError::QuotaExceeded(exceeded))
+            }
+            Err(QuotaError::Unknown) => return Err(ResourceInsertError::Unknown),
+        }
+
+        let Ok(content_hash) = acquire_blob(&mut tx, &self.store, b"").await else {
+            return Err(ResourceInsertError::Unknown);
+        };
+
         let insert_resource_sql = r#"
-            INSERT INTO resources (project_id, name)
-            VALUES ($1, $2)
+            INSERT INTO resources (project_id, name, folder_id, content_hash)
+            VALUES ($1, $2, $3, $4)
             ON CONFLICT DO NOTHING
-            RETURNING resource_id, project_id, name
+            RETURNING resource_id, project_id, name, folder_id, content_hash
         "#;
 
         let result = sqlx::query_as::<_, Resource>(insert_resource_sql)
             .bind(project_id)
             .bind(&resource_data.name)
+            .bind(resource_data.folder_id)
+            .bind(&content_hash)
             .fetch_optional(&mut tx);
 
         let resource = match result.await {
@@ -161,13 +403,6 @@ impl ResourceRepository for PgResourceRepository {
             }
         };
 
-        if write_file(get_resource_path(&resource), b"", true)
-            .await
-            .is_err()
-        {
-            return Err(ResourceInsertError::Unknown);
-        }
-
         match tx.commit().await {
             Ok(_) => Ok(resource),
             Err(err) => {
@@ -176,4 +411,15 @@ impl ResourceRepository for PgResourceRepository {
             }
         }
     }
+
+    #[tracing::instrument(skip(self))]
+    async fn download_url(&self, resource: &Resource) -> Result<Option<String>, ResourceGetError> {
+        self.store
+            .presign(&resource_key(resource))
+            .await
+            .map_err(|err| match err {
+                BlobError::Missing => ResourceGetError::Missing,
+                BlobError::NoSpace | BlobError::Unknown => ResourceGetError::Unknown,
+            })
+    }
 }
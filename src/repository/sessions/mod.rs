@@ -0,0 +1,217 @@
+use axum::async_trait;
+use mockall::automock;
+use sqlx::{types::chrono::Utc, PgPool};
+use tracing::error;
+
+use crate::{
+    constants::{SESSION_LIFETIME_IN_SECONDS, SESSION_RENEWAL_WINDOW_IN_SECONDS},
+    domain::sessions::{Session, SessionData, SessionInfo},
+};
+
+pub enum SessionGetError {
+    Missing,
+    Unknown,
+}
+
+pub enum SessionDeleteError {
+    Unknown,
+}
+
+pub enum SessionInsertError {
+    Duplicate,
+    Unknown,
+}
+
+#[automock]
+#[async_trait]
+pub trait SessionRepository {
+    async fn insert(&self, session: &SessionData) -> Result<(), SessionInsertError>;
+    async fn get(&self, id: &str) -> Result<Session, SessionGetError>;
+    async fn delete(&self, id: &str) -> Result<(), SessionDeleteError>;
+    async fn purge_expired(&self) -> Result<u64, SessionDeleteError>;
+    async fn list_by_user(
+        &self,
+        user_id: i32,
+        current_id: &str,
+    ) -> Result<Vec<SessionInfo>, SessionGetError>;
+    async fn delete_all_for_user(
+        &self,
+        user_id: i32,
+        keep_id: Option<String>,
+    ) -> Result<u64, SessionDeleteError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct PgSessionRepository {
+    pub pool: PgPool,
+}
+
+impl PgSessionRepository {
+    pub fn new(pool: &PgPool) -> Self {
+        Self { pool: pool.clone() }
+    }
+}
+
+#[async_trait]
+impl SessionRepository for PgSessionRepository {
+    #[tracing::instrument(skip_all, fields(user_id = session_data.user_id))]
+    async fn insert(&self, session_data: &SessionData) -> Result<(), SessionInsertError> {
+        let result = sqlx::query(
+            "
+            INSERT INTO sessions (session_id, user_id, expires, ip, user_agent)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT DO NOTHING
+        ",
+        )
+        .bind(&session_data.id)
+        .bind(session_data.user_id)
+        // the caller cannot hand out sessions longer than the configured lifetime
+        .bind(
+            session_data
+                .expires
+                .min(Utc::now().timestamp() + *SESSION_LIFETIME_IN_SECONDS),
+        )
+        .bind(&session_data.ip)
+        .bind(&session_data.user_agent)
+        .execute(&self.pool)
+        .await;
+
+        match result {
+            Ok(result) => {
+                if result.rows_affected() > 0 {
+                    Ok(())
+                } else {
+                    Err(SessionInsertError::Duplicate)
+                }
+            }
+            Err(err) => {
+                error!(%err);
+                Err(SessionInsertError::Unknown)
+            }
+        }
+    }
+
+    /// Expired sessions are reported as missing, sessions close to expiry are renewed
+    #[tracing::instrument(skip_all)]
+    async fn get(&self, id: &str) -> Result<Session, SessionGetError> {
+        let session = sqlx::query_as::<_, Session>(
+            "
+            WITH renewed AS (
+                UPDATE sessions
+                SET expires = CASE
+                    WHEN expires < $2 + $3 THEN GREATEST(expires, $2 + $4)
+                    ELSE expires
+                END,
+                last_seen = NOW()
+                WHERE session_id = $1 AND expires > $2
+                RETURNING session_id, user_id, expires
+            )
+            SELECT session_id, users.user_id, expires, email, display_name, password_hash, verified_at
+            FROM renewed JOIN users
+            ON renewed.user_id = users.user_id
+        ",
+        )
+        .bind(id)
+        .bind(Utc::now().timestamp())
+        .bind(*SESSION_RENEWAL_WINDOW_IN_SECONDS)
+        .bind(*SESSION_LIFETIME_IN_SECONDS)
+        .fetch_optional(&self.pool)
+        .await;
+
+        match session {
+            Ok(Some(session)) => Ok(session),
+            Ok(None) => Err(SessionGetError::Missing),
+            Err(err) => {
+                error!(%err);
+                Err(SessionGetError::Unknown)
+            }
+        }
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn delete(&self, id: &str) -> Result<(), SessionDeleteError> {
+        match sqlx::query("DELETE FROM sessions WHERE session_id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(err) => {
+                error!(%err);
+                Err(SessionDeleteError::Unknown)
+            }
+        }
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn purge_expired(&self) -> Result<u64, SessionDeleteError> {
+        match sqlx::query("DELETE FROM sessions WHERE expires <= $1")
+            .bind(Utc::now().timestamp())
+            .execute(&self.pool)
+            .await
+        {
+            Ok(result) => Ok(result.rows_affected()),
+            Err(err) => {
+                error!(%err);
+                Err(SessionDeleteError::Unknown)
+            }
+        }
+    }
+
+    #[tracing::instrument(skip(self, current_id))]
+    async fn list_by_user(
+        &self,
+        user_id: i32,
+        current_id: &str,
+    ) -> Result<Vec<SessionInfo>, SessionGetError> {
+        let sessions = sqlx::query_as::<_, SessionInfo>(
+            "
+            SELECT session_id = $2 AS current, created_at, last_seen, expires, ip, user_agent
+            FROM sessions
+            WHERE user_id = $1 AND expires > $3
+            ORDER BY last_seen DESC
+        ",
+        )
+        .bind(user_id)
+        .bind(current_id)
+        .bind(Utc::now().timestamp())
+        .fetch_all(&self.pool)
+        .await;
+
+        match sessions {
+            Ok(sessions) => Ok(sessions),
+            Err(err) => {
+                error!(%err);
+                Err(SessionGetError::Unknown)
+            }
+        }
+    }
+
+    #[tracing::instrument(skip(self, keep_id))]
+    async fn delete_all_for_user(
+        &self,
+        user_id: i32,
+        keep_id: Option<String>,
+    ) -> Result<u64, SessionDeleteError> {
+        match sqlx::query(
+            "
+            DELETE FROM sessions
+            WHERE user_id = $1 AND session_id IS DISTINCT FROM $2
+        ",
+        )
+        .bind(user_id)
+        .bind(keep_id)
+        .execute(&self.pool)
+        .await
+        {
+            Ok(result) => Ok(result.rows_affected()),
+            Err(err) => {
+                error!(%err);
+                Err(SessionDeleteError::Unknown)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
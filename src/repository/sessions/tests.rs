@@ -0,0 +1,89 @@
+use sqlx::PgPool;
+
+use crate::repository::fixtures::{create_user, random_name};
+
+use super::*;
+
+async fn create_session(pool: &PgPool, user_id: i32, expires: i64) -> String {
+    let data = SessionData {
+        id: random_name(),
+        user_id,
+        expires,
+        ip: None,
+        user_agent: None,
+    };
+    PgSessionRepository::new(pool)
+        .insert(&data)
+        .await
+        .ok()
+        .unwrap();
+    data.id
+}
+
+#[sqlx::test]
+async fn get_session_expired_missing(pool: PgPool) {
+    let user_id = create_user(&pool).await;
+    let session_id = create_session(&pool, user_id, Utc::now().timestamp() - 1).await;
+    let repository = PgSessionRepository::new(&pool);
+
+    assert!(matches!(
+        repository.get(&session_id).await,
+        Err(SessionGetError::Missing)
+    ));
+    assert_eq!(repository.purge_expired().await.ok(), Some(1));
+    let remaining =
+        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM sessions WHERE session_id = $1")
+            .bind(&session_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(remaining, 0);
+}
+
+#[sqlx::test]
+async fn get_session_renewal_window_renewed(pool: PgPool) {
+    let user_id = create_user(&pool).await;
+    let now = Utc::now().timestamp();
+    let expires = now + *SESSION_RENEWAL_WINDOW_IN_SECONDS / 2;
+    let session_id = create_session(&pool, user_id, expires).await;
+
+    let session = PgSessionRepository::new(&pool)
+        .get(&session_id)
+        .await
+        .ok()
+        .unwrap();
+
+    assert_eq!(session.user.id, user_id);
+    assert!(session.expires >= now + *SESSION_LIFETIME_IN_SECONDS);
+}
+
+#[sqlx::test]
+async fn get_session_outside_renewal_window_unchanged(pool: PgPool) {
+    let user_id = create_user(&pool).await;
+    let expires = Utc::now().timestamp() + *SESSION_RENEWAL_WINDOW_IN_SECONDS * 2;
+    let session_id = create_session(&pool, user_id, expires).await;
+
+    let session = PgSessionRepository::new(&pool)
+        .get(&session_id)
+        .await
+        .ok()
+        .unwrap();
+
+    assert_eq!(session.expires, expires);
+}
+
+#[sqlx::test]
+async fn insert_session_clamps_expiry_to_lifetime(pool: PgPool) {
+    let user_id = create_user(&pool).await;
+    let now = Utc::now().timestamp();
+    let session_id = create_session(&pool, user_id, i64::MAX).await;
+
+    let session = PgSessionRepository::new(&pool)
+        .get(&session_id)
+        .await
+        .ok()
+        .unwrap();
+
+    assert!(session.expires >= now + *SESSION_LIFETIME_IN_SECONDS);
+    assert!(session.expires <= Utc::now().timestamp() + *SESSION_LIFETIME_IN_SECONDS);
+}
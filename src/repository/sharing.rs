@@ -22,7 +22,8 @@ pub trait ProjectSharingRepository {
         project_id: i32,
         user_id: i32,
     ) -> Result<String, ProjectSharingCreateError>;
-    async fn update(&self, token: String, user_id: i32) -> Result<(), ProjectSharingUpdateError>;
+    /// Adds the user as a collaborator of the project the token was issued for, returning its id
+    async fn update(&self, token: String, user_id: i32) -> Result<i32, ProjectSharingUpdateError>;
 }
 
 #[derive(Debug, Clone)]
@@ -99,7 +100,7 @@ impl ProjectSharingRepository for PgProjectSharingRepository {
         }
     }
 
-    async fn update(&self, token: String, user_id: i32) -> Result<(), ProjectSharingUpdateError> {
+    async fn update(&self, token: String, user_id: i32) -> Result<i32, ProjectSharingUpdateError> {
         let mut tx: sqlx::Transaction<'_, sqlx::Postgres> = match self.pool.begin().await {
             Ok(tx) => tx,
             Err(err) => {
@@ -144,7 +145,7 @@ impl ProjectSharingRepository for PgProjectSharingRepository {
         }
 
         match tx.commit().await {
-            Ok(_) => Ok(()),
+            Ok(_) => Ok(project_id.id),
             Err(err) => {
                 error!(%err);
                 Err(ProjectSharingUpdateError::Unknown)
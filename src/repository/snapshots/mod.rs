@@ -0,0 +1,669 @@
+use axum::async_trait;
+use mockall::automock;
+use sqlx::{PgPool, Postgres, Transaction};
+use tracing::{error, info, warn};
+
+use crate::{
+    constants::DOCUMENT_COMPRESSION,
+    domain::{
+        documents::Document,
+        resources::Resource,
+        snapshots::{Snapshot, SnapshotData, SnapshotFile, SnapshotFileKind},
+    },
+    repository::{
+        blobs::{acquire_blob, reference_blob, release_blob},
+        folders::{ensure_folders, file_path, folder_paths},
+        usage::{check_quota, project_used_bytes, QuotaError},
+    },
+    storage::{codec, document_key, object_key, resource_key, BlobError, SharedBlobStore},
+};
+
+pub enum SnapshotGetError {
+    Missing,
+    Unknown,
+}
+pub enum SnapshotInsertError {
+    Unknown,
+}
+pub enum SnapshotRestoreError {
+    Missing,
+    QuotaExceeded,
+    Unknown,
+}
+
+#[automock]
+#[async_trait]
+pub trait SnapshotRepository {
+    async fn get(&self, project_id: i32) -> Result<Vec<Snapshot>, SnapshotGetError>;
+    async fn insert(
+        &self,
+        project_id: i32,
+        author_id: i32,
+        data: &SnapshotData,
+    ) -> Result<Snapshot, SnapshotInsertError>;
+    async fn archive(&self, project_id: i32, snapshot_id: i32)
+        -> Result<Vec<u8>, SnapshotGetError>;
+    async fn restore(
+        &self,
+        project_id: i32,
+        snapshot_id: i32,
+        target_project_id: i32,
+    ) -> Result<(), SnapshotRestoreError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct PgSnapshotRepository {
+    pub pool: PgPool,
+    pub store: SharedBlobStore,
+}
+
+impl PgSnapshotRepository {
+    pub fn new(pool: &PgPool, store: &SharedBlobStore) -> Self {
+        Self {
+            pool: pool.clone(),
+            store: store.clone(),
+        }
+    }
+
+    async fn get_files(
+        tx: &mut Transaction<'_, Postgres>,
+        project_id: i32,
+        snapshot_id: i32,
+    ) -> Result<Option<(Snapshot, Vec<SnapshotFile>)>, sqlx::Error> {
+        let get_snapshot_sql = "
+            SELECT snapshot_id, project_id, author_id, name, created_at
+            FROM snapshots
+            WHERE project_id = $1 AND snapshot_id = $2
+        ";
+        let snapshot = sqlx::query_as::<_, Snapshot>(get_snapshot_sql)
+            .bind(project_id)
+            .bind(snapshot_id)
+            .fetch_optional(&mut *tx)
+            .await?;
+
+        let Some(snapshot) = snapshot else {
+            return Ok(None);
+        };
+
+        let get_files_sql = "
+            SELECT kind, name, content_hash, size
+            FROM snapshot_files
+            WHERE snapshot_id = $1
+            ORDER BY kind, name
+        ";
+        let files = sqlx::query_as::<_, SnapshotFile>(get_files_sql)
+            .bind(snapshot_id)
+            .fetch_all(&mut *tx)
+            .await?;
+
+        Ok(Some((snapshot, files)))
+    }
+
+    async fn revert_writes(&self, written: Vec<(String, Option<Vec<u8>>)>) {
+        for (key, previous) in written {
+            let result = match previous {
+                Some(previous) => self.store.put(&key, &previous).await,
+                None => self.store.delete(&key).await,
+            };
+            if result.is_err() {
+                warn!(key, "Could not revert restored content");
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl SnapshotRepository for PgSnapshotRepository {
+    #[tracing::instrument(skip(self))]
+    async fn get(&self, project_id: i32) -> Result<Vec<Snapshot>, SnapshotGetError> {
+        let get_snapshots_sql = "
+            SELECT snapshot_id, project_id, author_id, name, created_at
+            FROM snapshots
+            WHERE project_id = $1
+            ORDER BY created_at DESC
+        ";
+
+        let snapshots = sqlx::query_as::<_, Snapshot>(get_snapshots_sql)
+            .bind(project_id)
+            .fetch_all(&self.pool)
+            .await;
+
+        match snapshots {
+            Ok(snapshots) => Ok(snapshots),
+            Err(err) => {
+                error!(%err);
+                Err(SnapshotGetError::Unknown)
+            }
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn insert(
+        &self,
+        project_id: i32,
+        author_id: i32,
+        data: &SnapshotData,
+    ) -> Result<Snapshot, SnapshotInsertError> {
+        let mut tx = match self.pool.begin().await {
+            Ok(tx) => tx,
+            Err(err) => {
+                error!(%err);
+                return Err(SnapshotInsertError::Unknown);
+            }
+        };
+
+        let insert_snapshot_sql = "
+            INSERT INTO snapshots (project_id, author_id, name)
+            VALUES ($1, $2, $3)
+            RETURNING snapshot_id, project_id, author_id, name, created_at
+        ";
+        let snapshot = sqlx::query_as::<_, Snapshot>(insert_snapshot_sql)
+            .bind(project_id)
+            .bind(author_id)
+            .bind(&data.name)
+            .fetch_one(&mut tx)
+            .await;
+
+        let snapshot = match snapshot {
+            Ok(snapshot) => snapshot,
+            Err(err) => {
+                error!(%err);
+                return Err(SnapshotInsertError::Unknown);
+            }
+        };
+
+        let documents = sqlx::query_as::<_, Document>(
+            "SELECT document_id, project_id, name, folder_id FROM documents WHERE project_id = $1",
+        )
+        .bind(project_id)
+        .fetch_all(&mut tx)
+        .await;
+        let resources = sqlx::query_as::<_, Resource>(
+            "SELECT resource_id, project_id, name, folder_id, content_hash FROM resources WHERE project_id = $1",
+        )
+        .bind(project_id)
+        .fetch_all(&mut tx)
+        .await;
+
+        let paths = folder_paths(&mut tx, project_id).await;
+
+        // files are recorded by their path, so the folder structure can be restored
+        let files = match (documents, resources, paths) {
+            (Ok(documents), Ok(resources), Ok(paths)) => documents
+                .iter()
+                .map(|document| {
+                    (
+                        SnapshotFileKind::Document,
+                        file_path(&paths, document.folder_id, &document.name),
+                        document_key(document),
+                    )
+                })
+                .chain(resources.iter().map(|resource| {
+                    (
+                        SnapshotFileKind::Resource,
+                        file_path(&paths, resource.folder_id, &resource.name),
+                        resource_key(resource),
+                    )
+                }))
+                .collect::<Vec<_>>(),
+            (Err(err), _, _) | (_, Err(err), _) | (_, _, Err(err)) => {
+                error!(%err);
+                return Err(SnapshotInsertError::Unknown);
+            }
+        };
+
+        let insert_file_sql = "
+            INSERT INTO snapshot_files (snapshot_id, kind, name, content_hash, size)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT DO NOTHING
+        ";
+        for (kind, name, key) in files {
+            let content = match self.store.get(&key).await {
+                Ok(content) => content,
+                Err(BlobError::Missing) => {
+                    warn!(name, "Skipping file missing from storage");
+                    continue;
+                }
+                Err(BlobError::NoSpace | BlobError::Unknown) => {
+                    return Err(SnapshotInsertError::Unknown)
+                }
+            };
+            // snapshots keep documents decoded, so archives hold the plain text
+            let content = match kind {
+                SnapshotFileKind::Document => match codec::decode(content) {
+                    Ok(content) => content,
+                    Err(_) => return Err(SnapshotInsertError::Unknown),
+                },
+                SnapshotFileKind::Resource => content,
+            };
+
+            let Ok(content_hash) = acquire_blob(&mut tx, &self.store, &content).await else {
+                return Err(SnapshotInsertError::Unknown);
+            };
+
+            let result = sqlx::query(insert_file_sql)
+                .bind(snapshot.snapshot_id)
+                .bind(kind)
+                .bind(&name)
+                .bind(&content_hash)
+                .bind(content.len() as i64)
+                .execute(&mut tx)
+                .await;
+
+            if let Err(err) = result {
+                error!(%err);
+                return Err(SnapshotInsertError::Unknown);
+            }
+        }
+
+        info!("Created snapshot {}", snapshot.snapshot_id);
+
+        match tx.commit().await {
+            Ok(_) => Ok(snapshot),
+            Err(err) => {
+                error!(%err);
+                Err(SnapshotInsertError::Unknown)
+            }
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn archive(
+        &self,
+        project_id: i32,
+        snapshot_id: i32,
+    ) -> Result<Vec<u8>, SnapshotGetError> {
+        let mut tx = match self.pool.begin().await {
+            Ok(tx) => tx,
+            Err(err) => {
+                error!(%err);
+                return Err(SnapshotGetError::Unknown);
+            }
+        };
+
+        let (snapshot, files) = match Self::get_files(&mut tx, project_id, snapshot_id).await {
+            Ok(Some(files)) => files,
+            Ok(None) => return Err(SnapshotGetError::Missing),
+            Err(err) => {
+                error!(%err);
+                return Err(SnapshotGetError::Unknown);
+            }
+        };
+
+        let mut archive = tar::Builder::new(Vec::new());
+        for file in files {
+            let content = match self.store.get(&object_key(&file.content_hash)).await {
+                Ok(content) => content,
+                Err(_) => return Err(SnapshotGetError::Unknown),
+            };
+
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_mtime(snapshot.created_at.timestamp() as u64);
+
+            if let Err(err) = archive.append_data(&mut header, &file.name, content.as_slice()) {
+                error!(%err);
+                return Err(SnapshotGetError::Unknown);
+            }
+        }
+
+        archive.into_inner().map_err(|err| {
+            error!(%err);
+            SnapshotGetError::Unknown
+        })
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn restore(
+        &self,
+        project_id: i32,
+        snapshot_id: i32,
+        target_project_id: i32,
+    ) -> Result<(), SnapshotRestoreError> {
+        let mut tx = match self.pool.begin().await {
+            Ok(tx) => tx,
+            Err(err) => {
+                error!(%err);
+                return Err(SnapshotRestoreError::Unknown);
+            }
+        };
+
+        let files = match Self::get_files(&mut tx, project_id, snapshot_id).await {
+            Ok(Some((_, files))) => files,
+            Ok(None) => return Err(SnapshotRestoreError::Missing),
+            Err(err) => {
+                error!(%err);
+                return Err(SnapshotRestoreError::Unknown);
+            }
+        };
+
+        let used_bytes = match project_used_bytes(&mut tx, target_project_id).await {
+            Ok(used_bytes) => used_bytes,
+            Err(err) => {
+                error!(%err);
+                return Err(SnapshotRestoreError::Unknown);
+            }
+        };
+
+        let snapshot_paths = |kind: SnapshotFileKind| -> Vec<&str> {
+            files
+                .iter()
+                .filter(|file| file.kind == kind)
+                .map(|file| file.name.as_str())
+                .collect()
+        };
+        let document_paths = snapshot_paths(SnapshotFileKind::Document);
+        let resource_paths = snapshot_paths(SnapshotFileKind::Resource);
+
+        let main_document_id = sqlx::query_scalar::<_, i32>(
+            "SELECT main_document_id FROM projects WHERE project_id = $1",
+        )
+        .bind(target_project_id)
+        .fetch_one(&mut tx)
+        .await;
+        let current_documents = sqlx::query_as::<_, Document>(
+            "SELECT document_id, project_id, name, folder_id FROM documents WHERE project_id = $1",
+        )
+        .bind(target_project_id)
+        .fetch_all(&mut tx)
+        .await;
+        let current_resources = sqlx::query_as::<_, Resource>(
+            "SELECT resource_id, project_id, name, folder_id, content_hash FROM resources WHERE project_id = $1",
+        )
+        .bind(target_project_id)
+        .fetch_all(&mut tx)
+        .await;
+        let paths = folder_paths(&mut tx, target_project_id).await;
+
+        // files created after the snapshot are dropped from the project,
+        // except for the main document every project needs
+        let (removed_document_ids, removed_resource_ids): (Vec<i32>, Vec<i32>) = match (
+            main_document_id,
+            current_documents,
+            current_resources,
+            paths,
+        ) {
+            (Ok(main_document_id), Ok(documents), Ok(resources), Ok(paths)) => (
+                documents
+                    .iter()
+                    .filter(|document| {
+                        let path = file_path(&paths, document.folder_id, &document.name);
+                        document.document_id != main_document_id
+                            && !document_paths.contains(&path.as_str())
+                    })
+                    .map(|document| document.document_id)
+                    .collect(),
+                resources
+                    .iter()
+                    .filter(|resource| {
+                        let path = file_path(&paths, resource.folder_id, &resource.name);
+                        !resource_paths.contains(&path.as_str())
+                    })
+                    .map(|resource| resource.resource_id)
+                    .collect(),
+            ),
+            (Err(err), _, _, _)
+            | (_, Err(err), _, _)
+            | (_, _, Err(err), _)
+            | (_, _, _, Err(err)) => {
+                error!(%err);
+                return Err(SnapshotRestoreError::Unknown);
+            }
+        };
+
+        // threads and suggestions cannot outlive the document they point at
+        let delete_document_dependents = [
+            "
+                DELETE FROM comment_mentions
+                WHERE comment_id IN (
+                    SELECT c.comment_id
+                    FROM comments AS c
+                    JOIN comment_threads AS t
+                    ON c.thread_id = t.thread_id
+                    WHERE t.document_id = ANY($1)
+                )
+            ",
+            "
+                DELETE FROM comments
+                WHERE thread_id IN (SELECT thread_id FROM comment_threads WHERE document_id = ANY($1))
+            ",
+            "DELETE FROM comment_threads WHERE document_id = ANY($1)",
+            "DELETE FROM suggestions WHERE document_id = ANY($1)",
+        ];
+        for sql in delete_document_dependents {
+            if let Err(err) = sqlx::query(sql)
+                .bind(&removed_document_ids)
+                .execute(&mut tx)
+                .await
+            {
+                error!(%err);
+                return Err(SnapshotRestoreError::Unknown);
+            }
+        }
+
+        let delete_documents_sql = "
+            DELETE FROM documents
+            WHERE document_id = ANY($1)
+            RETURNING document_id, project_id, name, folder_id
+        ";
+        let removed_documents = sqlx::query_as::<_, Document>(delete_documents_sql)
+            .bind(&removed_document_ids)
+            .fetch_all(&mut tx)
+            .await;
+
+        let removed_documents = match removed_documents {
+            Ok(removed_documents) => removed_documents,
+            Err(err) => {
+                error!(%err);
+                return Err(SnapshotRestoreError::Unknown);
+            }
+        };
+
+        let delete_resources_sql = "
+            DELETE FROM resources
+            WHERE resource_id = ANY($1)
+            RETURNING resource_id, project_id, name, folder_id, content_hash
+        ";
+        let removed = sqlx::query_as::<_, Resource>(delete_resources_sql)
+            .bind(&removed_resource_ids)
+            .fetch_all(&mut tx)
+            .await;
+
+        let removed = match removed {
+            Ok(removed) => removed,
+            Err(err) => {
+                error!(%err);
+                return Err(SnapshotRestoreError::Unknown);
+            }
+        };
+
+        let upsert_document_sql = "
+            WITH existing AS (
+                SELECT document_id, project_id, name, folder_id
+                FROM documents
+                WHERE project_id = $1 AND folder_id IS NOT DISTINCT FROM $2 AND name = $3
+            ), inserted AS (
+                INSERT INTO documents (project_id, folder_id, name)
+                SELECT $1, $2, $3
+                WHERE NOT EXISTS (SELECT 1 FROM existing)
+                RETURNING document_id, project_id, name, folder_id
+            )
+            SELECT * FROM existing
+            UNION ALL
+            SELECT * FROM inserted
+        ";
+        // the previous content is returned for its reference to be released
+        let upsert_resource_sql = "
+            WITH previous AS (
+                SELECT resource_id, content_hash
+                FROM resources
+                WHERE project_id = $1 AND folder_id IS NOT DISTINCT FROM $2 AND name = $3
+                FOR UPDATE
+            ), updated AS (
+                UPDATE resources
+                SET content_hash = $4, size = $5
+                WHERE resource_id IN (SELECT resource_id FROM previous)
+            ), inserted AS (
+                INSERT INTO resources (project_id, folder_id, name, content_hash, size)
+                SELECT $1, $2, $3, $4, $5
+                WHERE NOT EXISTS (SELECT 1 FROM previous)
+            )
+            SELECT content_hash FROM previous
+        ";
+        // document contents live under mutable keys, they are written once every check passed
+        let mut document_writes = Vec::new();
+        for file in &files {
+            let (folder_names, name) = match file.name.rsplit_once('/') {
+                Some((folder_path, name)) => (folder_path.split('/').collect(), name),
+                None => (Vec::new(), file.name.as_str()),
+            };
+            let folder_id = match ensure_folders(&mut tx, target_project_id, &folder_names).await {
+                Ok(folder_id) => folder_id,
+                Err(err) => {
+                    error!(%err);
+                    return Err(SnapshotRestoreError::Unknown);
+                }
+            };
+
+            if file.kind == SnapshotFileKind::Resource {
+                // resources point at the snapshot content, no bytes are copied
+                if reference_blob(&mut tx, &file.content_hash).await.is_err() {
+                    return Err(SnapshotRestoreError::Unknown);
+                }
+
+                let previous = sqlx::query_scalar::<_, Option<String>>(upsert_resource_sql)
+                    .bind(target_project_id)
+                    .bind(folder_id)
+                    .bind(name)
+                    .bind(&file.content_hash)
+                    .bind(file.size)
+                    .fetch_optional(&mut tx)
+                    .await;
+
+                match previous {
+                    Ok(Some(Some(previous_hash))) => {
+                        if release_blob(&mut tx, &previous_hash).await.is_err() {
+                            return Err(SnapshotRestoreError::Unknown);
+                        }
+                    }
+                    Ok(_) => (),
+                    Err(err) => {
+                        error!(%err);
+                        return Err(SnapshotRestoreError::Unknown);
+                    }
+                }
+                continue;
+            }
+
+            let document = sqlx::query_as::<_, Document>(upsert_document_sql)
+                .bind(target_project_id)
+                .bind(folder_id)
+                .bind(name)
+                .fetch_one(&mut tx)
+                .await;
+
+            let document = match document {
+                Ok(document) => document,
+                Err(err) => {
+                    error!(%err);
+                    return Err(SnapshotRestoreError::Unknown);
+                }
+            };
+
+            let result = sqlx::query("UPDATE documents SET size = $1 WHERE document_id = $2")
+                .bind(file.size)
+                .bind(document.document_id)
+                .execute(&mut tx)
+                .await;
+
+            if let Err(err) = result {
+                error!(%err);
+                return Err(SnapshotRestoreError::Unknown);
+            }
+
+            document_writes.push((document_key(&document), &file.content_hash));
+        }
+
+        // a restore may only grow the project while it stays within its quotas
+        match project_used_bytes(&mut tx, target_project_id).await {
+            Ok(restored_bytes) if restored_bytes > used_bytes => {
+                match check_quota(&mut tx, target_project_id, 0).await {
+                    Ok(()) => (),
+                    Err(QuotaError::Exceeded(_)) => {
+                        return Err(SnapshotRestoreError::QuotaExceeded)
+                    }
+                    Err(QuotaError::Unknown) => return Err(SnapshotRestoreError::Unknown),
+                }
+            }
+            Ok(_) => (),
+            Err(err) => {
+                error!(%err);
+                return Err(SnapshotRestoreError::Unknown);
+            }
+        }
+
+        for resource in &removed {
+            if let Some(content_hash) = &resource.content_hash {
+                if release_blob(&mut tx, content_hash).await.is_err() {
+                    return Err(SnapshotRestoreError::Unknown);
+                }
+            }
+        }
+
+        let mut contents = Vec::with_capacity(document_writes.len());
+        for (key, content_hash) in document_writes {
+            let content = match self.store.get(&object_key(content_hash)).await {
+                Ok(content) => content,
+                Err(_) => return Err(SnapshotRestoreError::Unknown),
+            };
+            let Ok(encoded) = codec::encode(&content, *DOCUMENT_COMPRESSION) else {
+                return Err(SnapshotRestoreError::Unknown);
+            };
+            let previous = match self.store.get(&key).await {
+                Ok(previous) => Some(previous),
+                Err(BlobError::Missing) => None,
+                Err(BlobError::NoSpace | BlobError::Unknown) => {
+                    return Err(SnapshotRestoreError::Unknown)
+                }
+            };
+            contents.push((key, encoded, previous));
+        }
+
+        // the previous contents are put back if the restore fails halfway
+        let mut written = Vec::with_capacity(contents.len());
+        for (key, encoded, previous) in contents {
+            if self.store.put(&key, &encoded).await.is_err() {
+                self.revert_writes(written).await;
+                return Err(SnapshotRestoreError::Unknown);
+            }
+            written.push((key, previous));
+        }
+
+        if let Err(err) = tx.commit().await {
+            error!(%err);
+            self.revert_writes(written).await;
+            return Err(SnapshotRestoreError::Unknown);
+        }
+
+        for document in &removed_documents {
+            if self.store.delete(&document_key(document)).await.is_err() {
+                warn!(document.document_id, "Could not remove document content");
+            }
+        }
+
+        let legacy = removed
+            .iter()
+            .filter(|resource| resource.content_hash.is_none());
+        for resource in legacy {
+            if self.store.delete(&resource_key(resource)).await.is_err() {
+                warn!(resource.resource_id, "Could not remove resource content");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,131 @@
+use std::sync::{Arc, Mutex};
+
+use axum::async_trait;
+use sqlx::PgPool;
+
+use crate::{
+    repository::fixtures::{
+        create_document, create_project, create_snapshot, create_user, read_document, test_store,
+        write_document,
+    },
+    storage::{document_key, BlobError, BlobStore, BlobStream, MemoryBlobStore, SharedBlobStore},
+};
+
+use super::*;
+
+/// Fails writes to one key, to interrupt a restore halfway
+#[derive(Debug, Default)]
+struct FailingBlobStore {
+    inner: MemoryBlobStore,
+    failing_key: Mutex<Option<String>>,
+}
+
+#[async_trait]
+impl BlobStore for FailingBlobStore {
+    async fn put(&self, key: &str, content: &[u8]) -> Result<(), BlobError> {
+        if self.failing_key.lock().unwrap().as_deref() == Some(key) {
+            return Err(BlobError::Unknown);
+        }
+        self.inner.put(key, content).await
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>, BlobError> {
+        self.inner.get(key).await
+    }
+
+    async fn stream(&self, key: &str) -> Result<BlobStream, BlobError> {
+        self.inner.stream(key).await
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), BlobError> {
+        self.inner.delete(key).await
+    }
+
+    async fn rename(&self, from: &str, to: &str) -> Result<(), BlobError> {
+        self.inner.rename(from, to).await
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>, BlobError> {
+        self.inner.list(prefix).await
+    }
+
+    async fn exists(&self, key: &str) -> Result<bool, BlobError> {
+        self.inner.exists(key).await
+    }
+}
+
+#[sqlx::test]
+async fn restore_drops_documents_created_after_snapshot(pool: PgPool) {
+    let store = test_store();
+    let owner_id = create_user(&pool).await;
+    let main = create_project(&pool, &store, owner_id).await;
+    write_document(&store, &main, "first").await;
+    let snapshot_id = create_snapshot(&pool, &store, main.project_id, owner_id).await;
+
+    write_document(&store, &main, "second").await;
+    let extra = create_document(&pool, &store, main.project_id, "extra.tex").await;
+    sqlx::query(
+        "
+            INSERT INTO comment_threads (project_id, document_id, author_id, anchor_start, anchor_end)
+            VALUES ($1, $2, $3, 0, 0)
+        ",
+    )
+    .bind(main.project_id)
+    .bind(extra.document_id)
+    .bind(owner_id)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let result = PgSnapshotRepository::new(&pool, &store)
+        .restore(main.project_id, snapshot_id, main.project_id)
+        .await;
+
+    assert!(result.is_ok());
+    assert_eq!(read_document(&store, &main).await.unwrap(), "first");
+    assert!(matches!(
+        read_document(&store, &extra).await,
+        Err(BlobError::Missing)
+    ));
+    let remaining =
+        sqlx::query_scalar::<_, i32>("SELECT document_id FROM documents WHERE project_id = $1")
+            .bind(main.project_id)
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+    assert_eq!(remaining, vec![main.document_id]);
+}
+
+#[sqlx::test]
+async fn restore_failed_write_keeps_contents(pool: PgPool) {
+    let failing_store = Arc::new(FailingBlobStore::default());
+    let store: SharedBlobStore = failing_store.clone();
+    let owner_id = create_user(&pool).await;
+    let main = create_project(&pool, &store, owner_id).await;
+    let other = create_document(&pool, &store, main.project_id, "section.tex").await;
+    write_document(&store, &main, "first").await;
+    write_document(&store, &other, "first").await;
+    let snapshot_id = create_snapshot(&pool, &store, main.project_id, owner_id).await;
+
+    write_document(&store, &main, "second").await;
+    write_document(&store, &other, "second").await;
+    let extra = create_document(&pool, &store, main.project_id, "extra.tex").await;
+    // documents are written in path order, so main.tex is already restored by then
+    *failing_store.failing_key.lock().unwrap() = Some(document_key(&other));
+
+    let result = PgSnapshotRepository::new(&pool, &store)
+        .restore(main.project_id, snapshot_id, main.project_id)
+        .await;
+
+    assert!(matches!(result, Err(SnapshotRestoreError::Unknown)));
+    assert_eq!(read_document(&store, &main).await.unwrap(), "second");
+    assert_eq!(read_document(&store, &other).await.unwrap(), "second");
+    assert_eq!(read_document(&store, &extra).await.unwrap(), "");
+    let remaining =
+        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM documents WHERE project_id = $1")
+            .bind(main.project_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(remaining, 3);
+}
@@ -0,0 +1,275 @@
+use axum::async_trait;
+use mockall::automock;
+use sqlx::PgPool;
+use tracing::{error, info};
+
+use crate::{
+    diff::TextEdit,
+    domain::suggestions::{Suggestion, SuggestionData, SuggestionStatus},
+};
+
+pub enum SuggestionGetError {
+    Missing,
+    Unknown,
+}
+pub enum SuggestionInsertError {
+    Unknown,
+}
+pub enum SuggestionUpdateError {
+    Missing,
+    Unknown,
+}
+
+#[automock]
+#[async_trait]
+pub trait SuggestionRepository {
+    async fn get(&self, project_id: i32) -> Result<Vec<Suggestion>, SuggestionGetError>;
+    async fn get_one(
+        &self,
+        project_id: i32,
+        suggestion_id: i32,
+    ) -> Result<Suggestion, SuggestionGetError>;
+    /// Replaces the pending suggestions of the author on the document,
+    /// every save carries all of their changes to the stored text
+    async fn replace_pending(
+        &self,
+        project_id: i32,
+        document_id: i32,
+        author_id: i32,
+        data: &[SuggestionData],
+    ) -> Result<(), SuggestionInsertError>;
+    async fn update_status(
+        &self,
+        project_id: i32,
+        suggestion_id: i32,
+        status: SuggestionStatus,
+    ) -> Result<(), SuggestionUpdateError>;
+    async fn rebase(&self, document_id: i32, edit: &TextEdit) -> Result<(), SuggestionUpdateError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct PgSuggestionRepository {
+    pub pool: PgPool,
+}
+
+impl PgSuggestionRepository {
+    pub fn new(pool: &PgPool) -> Self {
+        Self { pool: pool.clone() }
+    }
+}
+
+#[async_trait]
+impl SuggestionRepository for PgSuggestionRepository {
+    #[tracing::instrument(skip(self))]
+    async fn get(&self, project_id: i32) -> Result<Vec<Suggestion>, SuggestionGetError> {
+        let get_suggestions_sql = "
+            SELECT s.suggestion_id, s.project_id, s.document_id, s.author_id, u.email, u.display_name, s.kind, s.position, s.content, s.status, s.created_at
+            FROM suggestions AS s
+            JOIN users AS u
+            ON s.author_id = u.user_id
+            WHERE s.project_id = $1 AND s.status = 'pending'
+            ORDER BY s.suggestion_id
+        ";
+
+        let suggestions = sqlx::query_as::<_, Suggestion>(get_suggestions_sql)
+            .bind(project_id)
+            .fetch_all(&self.pool)
+            .await;
+
+        match suggestions {
+            Ok(suggestions) => Ok(suggestions),
+            Err(err) => {
+                error!(%err);
+                Err(SuggestionGetError::Unknown)
+            }
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn get_one(
+        &self,
+        project_id: i32,
+        suggestion_id: i32,
+    ) -> Result<Suggestion, SuggestionGetError> {
+        let get_suggestion_sql = "
+            SELECT s.suggestion_id, s.project_id, s.document_id, s.author_id, u.email, u.display_name, s.kind, s.position, s.content, s.status, s.created_at
+            FROM suggestions AS s
+            JOIN users AS u
+            ON s.author_id = u.user_id
+            WHERE s.project_id = $1 AND s.suggestion_id = $2
+        ";
+
+        let suggestion = sqlx::query_as::<_, Suggestion>(get_suggestion_sql)
+            .bind(project_id)
+            .bind(suggestion_id)
+            .fetch_optional(&self.pool)
+            .await;
+
+        match suggestion {
+            Ok(Some(suggestion)) => Ok(suggestion),
+            Ok(None) => Err(SuggestionGetError::Missing),
+            Err(err) => {
+                error!(%err);
+                Err(SuggestionGetError::Unknown)
+            }
+        }
+    }
+
+    #[tracing::instrument(skip(self, data))]
+    async fn replace_pending(
+        &self,
+        project_id: i32,
+        document_id: i32,
+        author_id: i32,
+        data: &[SuggestionData],
+    ) -> Result<(), SuggestionInsertError> {
+        let mut tx = match self.pool.begin().await {
+            Ok(tx) => tx,
+            Err(err) => {
+                error!(%err);
+                return Err(SuggestionInsertError::Unknown);
+            }
+        };
+
+        let delete_pending_sql = "
+            DELETE FROM suggestions
+            WHERE document_id = $1 AND author_id = $2 AND status = 'pending'
+        ";
+        let superseded = match sqlx::query(delete_pending_sql)
+            .bind(document_id)
+            .bind(author_id)
+            .execute(&mut tx)
+            .await
+        {
+            Ok(result) => result.rows_affected(),
+            Err(err) => {
+                error!(%err);
+                return Err(SuggestionInsertError::Unknown);
+            }
+        };
+
+        let insert_suggestion_sql = "
+            INSERT INTO suggestions (project_id, document_id, author_id, kind, position, content)
+            VALUES ($1, $2, $3, $4, $5, $6)
+        ";
+        for suggestion in data {
+            let result = sqlx::query(insert_suggestion_sql)
+                .bind(project_id)
+                .bind(document_id)
+                .bind(author_id)
+                .bind(suggestion.kind)
+                .bind(suggestion.position)
+                .bind(&suggestion.content)
+                .execute(&mut tx)
+                .await;
+
+            if let Err(err) = result {
+                error!(%err);
+                return Err(SuggestionInsertError::Unknown);
+            }
+        }
+
+        info!(superseded, "Stored {} suggestions", data.len());
+
+        tx.commit().await.map_err(|err| {
+            error!(%err);
+            SuggestionInsertError::Unknown
+        })
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn update_status(
+        &self,
+        project_id: i32,
+        suggestion_id: i32,
+        status: SuggestionStatus,
+    ) -> Result<(), SuggestionUpdateError> {
+        let result = sqlx::query(
+            "
+            UPDATE suggestions
+            SET status = $1
+            WHERE project_id = $2 AND suggestion_id = $3 AND status = 'pending'
+        ",
+        )
+        .bind(status)
+        .bind(project_id)
+        .bind(suggestion_id)
+        .execute(&self.pool)
+        .await;
+
+        match result {
+            Ok(result) if result.rows_affected() > 0 => Ok(()),
+            Ok(_) => Err(SuggestionUpdateError::Missing),
+            Err(err) => {
+                error!(%err);
+                Err(SuggestionUpdateError::Unknown)
+            }
+        }
+    }
+
+    #[tracing::instrument(skip(self, edit))]
+    async fn rebase(&self, document_id: i32, edit: &TextEdit) -> Result<(), SuggestionUpdateError> {
+        let mut tx = match self.pool.begin().await {
+            Ok(tx) => tx,
+            Err(err) => {
+                error!(%err);
+                return Err(SuggestionUpdateError::Unknown);
+            }
+        };
+
+        let get_pending_sql = "
+            SELECT s.suggestion_id, s.project_id, s.document_id, s.author_id, u.email, u.display_name, s.kind, s.position, s.content, s.status, s.created_at
+            FROM suggestions AS s
+            JOIN users AS u
+            ON s.author_id = u.user_id
+            WHERE s.document_id = $1 AND s.status = 'pending'
+            FOR UPDATE OF s
+        ";
+        let pending = sqlx::query_as::<_, Suggestion>(get_pending_sql)
+            .bind(document_id)
+            .fetch_all(&mut tx)
+            .await;
+
+        let pending = match pending {
+            Ok(pending) => pending,
+            Err(err) => {
+                error!(%err);
+                return Err(SuggestionUpdateError::Unknown);
+            }
+        };
+
+        // suggestions overlapping the edit no longer describe the text and are retired
+        let update_suggestion_sql = "
+            UPDATE suggestions
+            SET position = $1, status = $2
+            WHERE suggestion_id = $3
+        ";
+        for suggestion in pending {
+            let own = suggestion.as_edit();
+            let (position, status) = match edit.rebase_range(own.start, own.start + own.removed) {
+                Some((start, _)) => (start as i32, SuggestionStatus::Pending),
+                None => (suggestion.position, SuggestionStatus::Outdated),
+            };
+            if (position, status) == (suggestion.position, suggestion.status) {
+                continue;
+            }
+
+            let result = sqlx::query(update_suggestion_sql)
+                .bind(position)
+                .bind(status)
+                .bind(suggestion.suggestion_id)
+                .execute(&mut tx)
+                .await;
+
+            if let Err(err) = result {
+                error!(%err);
+                return Err(SuggestionUpdateError::Unknown);
+            }
+        }
+
+        tx.commit().await.map_err(|err| {
+            error!(%err);
+            SuggestionUpdateError::Unknown
+        })
+    }
+}
@@ -0,0 +1,158 @@
+use axum::async_trait;
+use mockall::automock;
+use sqlx::{PgExecutor, PgPool, Postgres, Transaction};
+use tracing::error;
+
+use crate::{
+    constants::{PROJECT_QUOTA_IN_BYTES, USER_QUOTA_IN_BYTES},
+    domain::usage::{ProjectUsage, QuotaExceeded, QuotaScope, Usage},
+};
+
+pub enum UsageGetError {
+    Unknown,
+}
+pub enum QuotaError {
+    Exceeded(QuotaExceeded),
+    Unknown,
+}
+
+fn quota(bytes: u64) -> Option<i64> {
+    (bytes > 0).then_some(bytes as i64)
+}
+
+pub async fn project_used_bytes<'e, E: PgExecutor<'e>>(
+    executor: E,
+    project_id: i32,
+) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar::<_, i64>("SELECT used_bytes FROM project_usage WHERE project_id = $1")
+        .bind(project_id)
+        .fetch_optional(executor)
+        .await
+        .map(|used_bytes| used_bytes.unwrap_or_default())
+}
+
+/// Checks that `added_bytes` more keep the project and its owner within their quotas.
+/// The owner stays locked until the transaction ends, so concurrent writes to any
+/// of their projects are checked one after another.
+pub async fn check_quota(
+    tx: &mut Transaction<'_, Postgres>,
+    project_id: i32,
+    added_bytes: i64,
+) -> Result<(), QuotaError> {
+    let lock_owner_sql = "
+        SELECT u.user_id
+        FROM users AS u
+        JOIN projects AS p
+        ON p.owner_id = u.user_id
+        WHERE p.project_id = $1
+        FOR UPDATE OF u
+    ";
+    let usage_sql = "
+        SELECT
+            used_bytes,
+            (SELECT SUM(used_bytes) FROM project_usage WHERE owner_id = $2)::BIGINT
+        FROM project_usage
+        WHERE project_id = $1
+    ";
+
+    let owner_id = sqlx::query_scalar::<_, i32>(lock_owner_sql)
+        .bind(project_id)
+        .fetch_optional(&mut *tx)
+        .await;
+    let owner_id = match owner_id {
+        Ok(Some(owner_id)) => owner_id,
+        // a missing project is reported by the write itself
+        Ok(None) => return Ok(()),
+        Err(err) => {
+            error!(%err);
+            return Err(QuotaError::Unknown);
+        }
+    };
+
+    let usage = sqlx::query_as::<_, (i64, i64)>(usage_sql)
+        .bind(project_id)
+        .bind(owner_id)
+        .fetch_one(&mut *tx)
+        .await;
+    let (project_bytes, owner_bytes) = match usage {
+        Ok(usage) => usage,
+        Err(err) => {
+            error!(%err);
+            return Err(QuotaError::Unknown);
+        }
+    };
+
+    let limits = [
+        (
+            QuotaScope::Project,
+            quota(*PROJECT_QUOTA_IN_BYTES),
+            project_bytes,
+        ),
+        (QuotaScope::Owner, quota(*USER_QUOTA_IN_BYTES), owner_bytes),
+    ];
+    for (scope, quota_bytes, used_bytes) in limits {
+        match quota_bytes {
+            Some(quota_bytes) if used_bytes + added_bytes > quota_bytes => {
+                return Err(QuotaError::Exceeded(QuotaExceeded {
+                    scope,
+                    quota_bytes,
+                    used_bytes,
+                    requested_bytes: added_bytes,
+                }))
+            }
+            _ => (),
+        }
+    }
+
+    Ok(())
+}
+
+#[automock]
+#[async_trait]
+pub trait UsageRepository {
+    async fn get(&self, user_id: i32) -> Result<Usage, UsageGetError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct PgUsageRepository {
+    pub pool: PgPool,
+}
+
+impl PgUsageRepository {
+    pub fn new(pool: &PgPool) -> Self {
+        Self { pool: pool.clone() }
+    }
+}
+
+#[async_trait]
+impl UsageRepository for PgUsageRepository {
+    #[tracing::instrument(skip(self))]
+    async fn get(&self, user_id: i32) -> Result<Usage, UsageGetError> {
+        let get_usage_sql = "
+            SELECT p.project_id, p.project_name, pu.used_bytes
+            FROM projects AS p
+            JOIN project_usage AS pu
+            ON p.project_id = pu.project_id
+            WHERE p.owner_id = $1
+            ORDER BY p.project_id
+        ";
+
+        let projects = sqlx::query_as::<_, ProjectUsage>(get_usage_sql)
+            .bind(user_id)
+            .fetch_all(&self.pool)
+            .await;
+
+        match projects {
+            Ok(projects) => Ok(Usage {
+                used_bytes: projects.iter().map(|project| project.used_bytes).sum(),
+                quota_bytes: quota(*USER_QUOTA_IN_BYTES),
+                project_quota_bytes: quota(*PROJECT_QUOTA_IN_BYTES),
+                projects,
+            }),
+            Err(err) => {
+                error!(%err);
+                Err(UsageGetError::Unknown)
+            }
+        }
+    }
+}
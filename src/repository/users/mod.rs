@@ -0,0 +1,214 @@
+use axum::async_trait;
+use mockall::automock;
+use sqlx::PgPool;
+use tracing::error;
+
+use crate::domain::users::User;
+
+pub enum UserGetError {
+    Missing,
+    Unknown,
+}
+
+pub enum UserInsertError {
+    Duplicate,
+    Unknown,
+}
+
+pub enum UserUpdateError {
+    Missing,
+    Unknown,
+}
+
+#[automock]
+#[async_trait]
+pub trait UserRepository {
+    async fn get_by_email(&self, email: &str) -> Result<User, UserGetError>;
+    async fn get_by_id(&self, id: i32) -> Result<User, UserGetError>;
+    /// Returns the id of the new, still unverified, user
+    async fn insert(&self, email: &str, password_hash: &str) -> Result<i32, UserInsertError>;
+    /// Replaces the password, ends every session of the user except the kept one
+    /// and revokes their access tokens
+    async fn update_password(
+        &self,
+        id: i32,
+        password_hash: &str,
+        keep_session_id: Option<String>,
+    ) -> Result<(), UserUpdateError>;
+    /// Swaps the hash of an unchanged password, unless the password changed in the meantime
+    async fn update_password_hash(
+        &self,
+        id: i32,
+        old_password_hash: &str,
+        new_password_hash: &str,
+    ) -> Result<(), UserUpdateError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct PgUserRepository {
+    pub pool: PgPool,
+}
+
+impl PgUserRepository {
+    pub fn new(pool: &PgPool) -> Self {
+        Self { pool: pool.clone() }
+    }
+}
+
+#[async_trait]
+impl UserRepository for PgUserRepository {
+    #[tracing::instrument(skip(self))]
+    async fn get_by_email(&self, email: &str) -> Result<User, UserGetError> {
+        let result = sqlx::query_as::<_, User>("SELECT * FROM users WHERE email = $1")
+            .bind(email)
+            .fetch_optional(&self.pool)
+            .await;
+        match result {
+            Ok(Some(user)) => Ok(user),
+            Ok(None) => Err(UserGetError::Missing),
+            Err(err) => {
+                error!(%err);
+                Err(UserGetError::Unknown)
+            }
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn get_by_id(&self, id: i32) -> Result<User, UserGetError> {
+        let result = sqlx::query_as::<_, User>("SELECT * FROM users WHERE user_id = $1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await;
+        match result {
+            Ok(Some(user)) => Ok(user),
+            Ok(None) => Err(UserGetError::Missing),
+            Err(err) => {
+                error!(%err);
+                Err(UserGetError::Unknown)
+            }
+        }
+    }
+
+    #[tracing::instrument(skip(self, password_hash))]
+    async fn insert(&self, email: &str, password_hash: &str) -> Result<i32, UserInsertError> {
+        let result = sqlx::query_scalar::<_, i32>(
+            "INSERT INTO users (email, password_hash)
+            VALUES ($1, $2)
+            ON CONFLICT DO NOTHING
+            RETURNING user_id
+        ",
+        )
+        .bind(email)
+        .bind(password_hash)
+        .fetch_optional(&self.pool)
+        .await;
+
+        match result {
+            Ok(Some(user_id)) => Ok(user_id),
+            Ok(None) => Err(UserInsertError::Duplicate),
+            Err(err) => {
+                error!(%err);
+                Err(UserInsertError::Unknown)
+            }
+        }
+    }
+
+    #[tracing::instrument(skip(self, password_hash, keep_session_id))]
+    async fn update_password(
+        &self,
+        id: i32,
+        password_hash: &str,
+        keep_session_id: Option<String>,
+    ) -> Result<(), UserUpdateError> {
+        let mut tx = match self.pool.begin().await {
+            Ok(tx) => tx,
+            Err(err) => {
+                error!(%err);
+                return Err(UserUpdateError::Unknown);
+            }
+        };
+
+        let update_password_sql = "
+            UPDATE users
+            SET password_hash = $2, updated_at = NOW()
+            WHERE user_id = $1
+        ";
+        let delete_sessions_sql = "
+            DELETE FROM sessions
+            WHERE user_id = $1 AND session_id IS DISTINCT FROM $2
+        ";
+        // access tokens may have leaked along with the password
+        let delete_tokens_sql = "DELETE FROM api_tokens WHERE user_id = $1";
+
+        match sqlx::query(update_password_sql)
+            .bind(id)
+            .bind(password_hash)
+            .execute(&mut tx)
+            .await
+        {
+            Ok(result) if result.rows_affected() == 0 => return Err(UserUpdateError::Missing),
+            Ok(_) => (),
+            Err(err) => {
+                error!(%err);
+                return Err(UserUpdateError::Unknown);
+            }
+        }
+
+        let result = sqlx::query(delete_sessions_sql)
+            .bind(id)
+            .bind(keep_session_id)
+            .execute(&mut tx)
+            .await;
+        if let Err(err) = result {
+            error!(%err);
+            return Err(UserUpdateError::Unknown);
+        }
+
+        let result = sqlx::query(delete_tokens_sql)
+            .bind(id)
+            .execute(&mut tx)
+            .await;
+        if let Err(err) = result {
+            error!(%err);
+            return Err(UserUpdateError::Unknown);
+        }
+
+        tx.commit().await.map_err(|err| {
+            error!(%err);
+            UserUpdateError::Unknown
+        })
+    }
+
+    #[tracing::instrument(skip(self, old_password_hash, new_password_hash))]
+    async fn update_password_hash(
+        &self,
+        id: i32,
+        old_password_hash: &str,
+        new_password_hash: &str,
+    ) -> Result<(), UserUpdateError> {
+        let result = sqlx::query(
+            "
+            UPDATE users
+            SET password_hash = $3
+            WHERE user_id = $1 AND password_hash = $2
+        ",
+        )
+        .bind(id)
+        .bind(old_password_hash)
+        .bind(new_password_hash)
+        .execute(&self.pool)
+        .await;
+
+        match result {
+            Ok(result) if result.rows_affected() == 0 => Err(UserUpdateError::Missing),
+            Ok(_) => Ok(()),
+            Err(err) => {
+                error!(%err);
+                Err(UserUpdateError::Unknown)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
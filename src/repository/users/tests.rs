@@ -0,0 +1,26 @@
+use sqlx::PgPool;
+
+use crate::repository::{
+    api_tokens::{ApiTokenGetError, ApiTokenRepository, PgApiTokenRepository},
+    fixtures::{create_api_token, create_user},
+};
+
+use super::*;
+
+#[sqlx::test]
+async fn update_password_revokes_api_tokens(pool: PgPool) {
+    let user_id = create_user(&pool).await;
+    let secret = create_api_token(&pool, user_id).await;
+    let api_tokens = PgApiTokenRepository::new(&pool);
+    assert!(api_tokens.authenticate(&secret).await.is_ok());
+
+    let result = PgUserRepository::new(&pool)
+        .update_password(user_id, "new hash", None)
+        .await;
+
+    assert!(result.is_ok());
+    assert!(matches!(
+        api_tokens.authenticate(&secret).await,
+        Err(ApiTokenGetError::Missing)
+    ));
+}
@@ -0,0 +1,12 @@
+use axum::{routing, Extension, Router};
+
+use crate::{
+    control::admin::{get_admin_gc, post_admin_gc},
+    gc::GarbageCollector,
+};
+
+pub fn admin_router(collector: GarbageCollector) -> Router {
+    Router::new()
+        .route("/gc", routing::get(get_admin_gc).post(post_admin_gc))
+        .layer(Extension(collector))
+}
@@ -0,0 +1,55 @@
+use axum::{routing, Extension, Router};
+
+use crate::{
+    control::folders::{
+        delete_projects_folders, post_projects_folders, put_projects_folders,
+        put_projects_folders_documents, put_projects_folders_resources,
+    },
+    repository::{
+        events::PgEventRepository, folders::PgFolderRepository, projects::PgProjectRepository,
+    },
+};
+
+pub fn folders_router(folders_repository: PgFolderRepository) -> Router {
+    Router::new()
+        .route(
+            "/",
+            routing::post(
+                post_projects_folders::<PgProjectRepository, PgFolderRepository, PgEventRepository>,
+            ),
+        )
+        .route(
+            "/:folder_id",
+            routing::put(
+                put_projects_folders::<PgProjectRepository, PgFolderRepository, PgEventRepository>,
+            )
+            .delete(
+                delete_projects_folders::<
+                    PgProjectRepository,
+                    PgFolderRepository,
+                    PgEventRepository,
+                >,
+            ),
+        )
+        .route(
+            "/documents/:document_id",
+            routing::put(
+                put_projects_folders_documents::<
+                    PgProjectRepository,
+                    PgFolderRepository,
+                    PgEventRepository,
+                >,
+            ),
+        )
+        .route(
+            "/resources/:resource_id",
+            routing::put(
+                put_projects_folders_resources::<
+                    PgProjectRepository,
+                    PgFolderRepository,
+                    PgEventRepository,
+                >,
+            ),
+        )
+        .layer(Extension(folders_repository))
+}
@@ -1,30 +1,88 @@
+mod admin;
 mod documents;
+mod folders;
+mod presence;
 mod projects;
 mod resources;
 mod sessions;
+mod snapshots;
+mod suggestions;
 mod users;
 
-use axum::Router;
+use std::sync::Arc;
+
+use axum::{middleware, Extension, Router};
 use sqlx::PgPool;
 
-use crate::repository::{
-    documents::PgDocumentRepository, projects::PgProjectRepository,
-    resources::PgResourceRepository, sessions::PgSessionRepository,
-    sharing::PgProjectSharingRepository, users::PgUserRepository,
+use crate::{
+    auth::{authenticate, AuthState, Authenticator},
+    gc::GarbageCollector,
+    notify::SharedNotifier,
+    presence::PresenceHub,
+    repository::{
+        accounts::PgAccountRepository, api_tokens::PgApiTokenRepository,
+        comments::PgCommentRepository, documents::PgDocumentRepository,
+        email_verifications::PgEmailVerificationRepository, events::PgEventRepository,
+        folders::PgFolderRepository, password_resets::PgPasswordResetRepository,
+        profiles::PgProfileRepository, projects::PgProjectRepository,
+        resources::PgResourceRepository, sessions::PgSessionRepository,
+        sharing::PgProjectSharingRepository, snapshots::PgSnapshotRepository,
+        suggestions::PgSuggestionRepository, usage::PgUsageRepository, users::PgUserRepository,
+    },
+    storage::SharedBlobStore,
 };
 
-use self::{projects::projects_router, sessions::sessions_router, users::users_router};
+use self::{
+    admin::admin_router, projects::projects_router, sessions::sessions_router, users::users_router,
+};
 
-pub fn main_router(pool: &PgPool) -> Router {
+pub fn main_router(
+    pool: &PgPool,
+    store: &SharedBlobStore,
+    presence_hub: &PresenceHub,
+    collector: &GarbageCollector,
+    notifier: &SharedNotifier,
+    authenticator: &Authenticator,
+) -> Router {
     let users_repository = PgUserRepository::new(pool);
     let sessions_repository = PgSessionRepository::new(pool);
-    let projects_repository = PgProjectRepository::new(pool);
-    let documents_repository = PgDocumentRepository::new(pool);
-    let resources_repository = PgResourceRepository::new(pool);
+    let projects_repository = PgProjectRepository::new(pool, store);
+    let documents_repository = PgDocumentRepository::new(pool, store);
+    let resources_repository = PgResourceRepository::new(pool, store);
+    let folders_repository = PgFolderRepository::new(pool);
     let sharing_repository = PgProjectSharingRepository::new(pool);
+    let comments_repository = PgCommentRepository::new(pool);
+    let suggestions_repository = PgSuggestionRepository::new(pool);
+    let snapshots_repository = PgSnapshotRepository::new(pool, store);
+    let events_repository = PgEventRepository::new(pool);
+    let usage_repository = PgUsageRepository::new(pool);
+    let password_resets_repository = PgPasswordResetRepository::new(pool);
+    let accounts_repository = PgAccountRepository::new(pool);
+    let profiles_repository = PgProfileRepository::new(pool, store);
+    let email_verifications_repository = PgEmailVerificationRepository::new(pool);
+    let api_tokens_repository = PgApiTokenRepository::new(pool);
+    let auth_state = AuthState {
+        authenticator: Arc::new(authenticator.clone()),
+        sessions: sessions_repository.clone(),
+        api_tokens: api_tokens_repository.clone(),
+    };
 
     Router::new()
-        .nest("/users", users_router(users_repository))
+        .nest("/admin", admin_router(collector.clone()))
+        .nest(
+            "/users",
+            users_router(
+                users_repository.clone(),
+                usage_repository,
+                password_resets_repository,
+                notifier.clone(),
+                accounts_repository,
+                profiles_repository,
+                email_verifications_repository,
+                api_tokens_repository.clone(),
+                projects_repository.clone(),
+            ),
+        )
         .nest("/sessions", sessions_router(sessions_repository))
         .nest(
             "/projects",
@@ -32,7 +90,18 @@ pub fn main_router(pool: &PgPool) -> Router {
                 projects_repository,
                 documents_repository,
                 resources_repository,
+                folders_repository,
                 sharing_repository,
+                presence_hub.clone(),
             ),
         )
+        .layer(Extension(users_repository))
+        .layer(Extension(comments_repository))
+        .layer(Extension(suggestions_repository))
+        .layer(Extension(snapshots_repository))
+        .layer(Extension(events_repository))
+        .layer(middleware::from_fn_with_state(
+            auth_state,
+            authenticate::<PgSessionRepository, PgApiTokenRepository, _>,
+        ))
 }
@@ -0,0 +1,20 @@
+use axum::{routing, Extension, Router};
+
+use crate::{
+    control::presence::{get_projects_presence, get_projects_presence_socket},
+    presence::PresenceHub,
+    repository::{projects::PgProjectRepository, users::PgUserRepository},
+};
+
+pub fn presence_router(presence_hub: PresenceHub) -> Router {
+    Router::new()
+        .route(
+            "/",
+            routing::get(get_projects_presence::<PgProjectRepository>),
+        )
+        .route(
+            "/socket",
+            routing::get(get_projects_presence_socket::<PgProjectRepository, PgUserRepository>),
+        )
+        .layer(Extension(presence_hub))
+}
@@ -2,52 +2,153 @@ use axum::{routing, Extension, Router};
 
 use crate::{
     control::{
+        activity::get_projects_activity,
+        comments::{
+            get_projects_comments, post_projects_comments, post_projects_comments_replies,
+            put_projects_comments_status,
+        },
         documents::{get_projects_documents, put_projects_documents},
-        projects::{get_projects, get_projects_metadata, post_projects, put_projects_metadata},
+        folders::get_projects_tree,
+        projects::{
+            get_projects, get_projects_metadata, post_projects, put_projects_metadata,
+            put_projects_settings,
+        },
         sharing::{post_projects_sharing, put_projects_sharing},
     },
+    presence::PresenceHub,
     repository::documents::PgDocumentRepository,
     repository::resources::PgResourceRepository,
-    repository::{projects::PgProjectRepository, sharing::PgProjectSharingRepository},
+    repository::{
+        comments::PgCommentRepository, events::PgEventRepository, folders::PgFolderRepository,
+        projects::PgProjectRepository, sharing::PgProjectSharingRepository,
+        suggestions::PgSuggestionRepository, users::PgUserRepository,
+    },
 };
 
-use super::resources::resources_router;
+use super::{
+    folders::folders_router, presence::presence_router, resources::resources_router,
+    snapshots::snapshots_router, suggestions::suggestions_router,
+};
 
 pub fn projects_router(
     projects_repository: PgProjectRepository,
     documents_repository: PgDocumentRepository,
     resources_repository: PgResourceRepository,
+    folders_repository: PgFolderRepository,
     sharing_repository: PgProjectSharingRepository,
+    presence_hub: PresenceHub,
 ) -> Router {
     let root_handler = routing::get(get_projects::<PgProjectRepository>)
-        .post(post_projects::<PgProjectRepository>);
+        .post(post_projects::<PgProjectRepository, PgEventRepository>);
 
     let documents_router =
         routing::get(get_projects_documents::<PgProjectRepository, PgDocumentRepository>)
-            .put(put_projects_documents::<PgProjectRepository, PgDocumentRepository>)
-            .layer(Extension(documents_repository));
+            .put(
+                put_projects_documents::<
+                    PgProjectRepository,
+                    PgDocumentRepository,
+                    PgCommentRepository,
+                    PgSuggestionRepository,
+                    PgEventRepository,
+                >,
+            )
+            .layer(Extension(documents_repository.clone()));
     let sharing_router = Router::new()
         .route(
             "/sharing/:token",
-            routing::post(post_projects_sharing::<PgProjectSharingRepository>),
+            routing::post(
+                post_projects_sharing::<
+                    PgProjectSharingRepository,
+                    PgEventRepository,
+                    PgUserRepository,
+                >,
+            ),
         )
         .route(
             "/:project_id/sharing",
-            routing::put(put_projects_sharing::<PgProjectSharingRepository>),
+            routing::put(put_projects_sharing::<PgProjectSharingRepository, PgEventRepository>),
         )
         .layer(Extension(sharing_repository));
 
-    let metadata_handler = routing::put(put_projects_metadata::<PgProjectRepository>)
-        .get(get_projects_metadata::<PgProjectRepository>);
+    let comments_router =
+        Router::new()
+            .route(
+                "/:project_id/comments",
+                routing::get(get_projects_comments::<PgProjectRepository, PgCommentRepository>)
+                    .post(
+                        post_projects_comments::<
+                            PgProjectRepository,
+                            PgCommentRepository,
+                            PgEventRepository,
+                        >,
+                    ),
+            )
+            .route(
+                "/:project_id/comments/:thread_id/replies",
+                routing::post(
+                    post_projects_comments_replies::<
+                        PgProjectRepository,
+                        PgCommentRepository,
+                        PgEventRepository,
+                    >,
+                ),
+            )
+            .route(
+                "/:project_id/comments/:thread_id/status",
+                routing::put(
+                    put_projects_comments_status::<
+                        PgProjectRepository,
+                        PgCommentRepository,
+                        PgEventRepository,
+                    >,
+                ),
+            );
+
+    let tree_router = Router::new()
+        .route(
+            "/:project_id/tree",
+            routing::get(
+                get_projects_tree::<
+                    PgProjectRepository,
+                    PgFolderRepository,
+                    PgDocumentRepository,
+                    PgResourceRepository,
+                >,
+            ),
+        )
+        .layer(Extension(folders_repository.clone()))
+        .layer(Extension(documents_repository.clone()))
+        .layer(Extension(resources_repository.clone()));
+
+    let metadata_handler =
+        routing::put(put_projects_metadata::<PgProjectRepository, PgEventRepository>)
+            .get(get_projects_metadata::<PgProjectRepository>);
 
     Router::new()
         .route("/", root_handler)
         .merge(sharing_router)
+        .merge(comments_router)
+        .merge(tree_router)
         .route("/:project_id", documents_router)
         .route("/:project_id/metadata", metadata_handler)
+        .route(
+            "/:project_id/settings",
+            routing::put(put_projects_settings::<PgProjectRepository, PgEventRepository>),
+        )
+        .route(
+            "/:project_id/activity",
+            routing::get(get_projects_activity::<PgProjectRepository, PgEventRepository>),
+        )
+        .nest("/:project_id/folders", folders_router(folders_repository))
         .nest(
             "/:project_id/resources",
             resources_router(resources_repository),
         )
+        .nest(
+            "/:project_id/suggestions",
+            suggestions_router(documents_repository),
+        )
+        .nest("/:project_id/snapshots", snapshots_router())
+        .nest("/:project_id/presence", presence_router(presence_hub))
         .layer(Extension(projects_repository))
 }
@@ -2,20 +2,39 @@ use axum::{extract::DefaultBodyLimit, routing, Extension, Router};
 
 use crate::{
     constants::RESOURCE_SIZE_LIMIT_IN_BYTES,
-    control::resources::{get_projects_resources, post_projects_resources, put_projects_resources},
-    repository::{projects::PgProjectRepository, resources::PgResourceRepository},
+    control::resources::{
+        get_projects_resources, get_projects_resources_content, post_projects_resources,
+        post_projects_resources_copy, put_projects_resources,
+    },
+    repository::{
+        events::PgEventRepository, projects::PgProjectRepository, resources::PgResourceRepository,
+    },
 };
 
 pub fn resources_router(resources_repository: PgResourceRepository) -> Router {
-    let root_handler =
-        routing::post(post_projects_resources::<PgProjectRepository, PgResourceRepository>)
-            .get(get_projects_resources::<PgProjectRepository, PgResourceRepository>);
+    let root_handler = routing::post(
+        post_projects_resources::<PgProjectRepository, PgResourceRepository, PgEventRepository>,
+    )
+    .get(get_projects_resources::<PgProjectRepository, PgResourceRepository>);
 
-    let resource_id_handler = routing::put(put_projects_resources::<PgResourceRepository>)
-        .layer(DefaultBodyLimit::max(*RESOURCE_SIZE_LIMIT_IN_BYTES));
+    let resource_id_handler = routing::put(
+        put_projects_resources::<PgProjectRepository, PgResourceRepository, PgEventRepository>,
+    )
+    .layer(DefaultBodyLimit::max(*RESOURCE_SIZE_LIMIT_IN_BYTES))
+    .get(get_projects_resources_content::<PgProjectRepository, PgResourceRepository>);
 
     Router::new()
         .route("/", root_handler)
         .route("/:resource_id", resource_id_handler)
+        .route(
+            "/:resource_id/copy",
+            routing::post(
+                post_projects_resources_copy::<
+                    PgProjectRepository,
+                    PgResourceRepository,
+                    PgEventRepository,
+                >,
+            ),
+        )
         .layer(Extension(resources_repository))
 }
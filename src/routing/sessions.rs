@@ -1,7 +1,9 @@
 use axum::{routing, Extension, Router};
 
 use crate::{
-    control::sessions::{delete_sessions, get_sessions, post_sessions},
+    control::sessions::{
+        delete_sessions, delete_sessions_all, get_sessions, get_sessions_all, post_sessions,
+    },
     repository::sessions::PgSessionRepository,
 };
 
@@ -9,8 +11,11 @@ pub fn sessions_router(sessions_repository: PgSessionRepository) -> Router {
     let root_handler = routing::get(get_sessions::<PgSessionRepository>)
         .post(post_sessions::<PgSessionRepository>)
         .delete(delete_sessions::<PgSessionRepository>);
+    let all_handler = routing::get(get_sessions_all::<PgSessionRepository>)
+        .delete(delete_sessions_all::<PgSessionRepository>);
 
     Router::new()
         .route("/", root_handler)
+        .route("/all", all_handler)
         .layer(Extension(sessions_repository))
 }
@@ -0,0 +1,51 @@
+use axum::{routing, Router};
+
+use crate::{
+    control::snapshots::{
+        get_projects_snapshots, get_projects_snapshots_archive, post_projects_snapshots,
+        post_projects_snapshots_copy, post_projects_snapshots_restore,
+    },
+    repository::{
+        events::PgEventRepository, projects::PgProjectRepository, snapshots::PgSnapshotRepository,
+    },
+};
+
+pub fn snapshots_router() -> Router {
+    Router::new()
+        .route(
+            "/",
+            routing::get(get_projects_snapshots::<PgProjectRepository, PgSnapshotRepository>).post(
+                post_projects_snapshots::<
+                    PgProjectRepository,
+                    PgSnapshotRepository,
+                    PgEventRepository,
+                >,
+            ),
+        )
+        .route(
+            "/:snapshot_id/archive",
+            routing::get(
+                get_projects_snapshots_archive::<PgProjectRepository, PgSnapshotRepository>,
+            ),
+        )
+        .route(
+            "/:snapshot_id/restore",
+            routing::post(
+                post_projects_snapshots_restore::<
+                    PgProjectRepository,
+                    PgSnapshotRepository,
+                    PgEventRepository,
+                >,
+            ),
+        )
+        .route(
+            "/:snapshot_id/copy",
+            routing::post(
+                post_projects_snapshots_copy::<
+                    PgProjectRepository,
+                    PgSnapshotRepository,
+                    PgEventRepository,
+                >,
+            ),
+        )
+}
@@ -0,0 +1,66 @@
+use axum::{routing, Extension, Router};
+
+use crate::{
+    control::suggestions::{
+        get_projects_suggestions, post_projects_suggestions_accept,
+        post_projects_suggestions_accept_all, post_projects_suggestions_reject,
+        post_projects_suggestions_reject_all,
+    },
+    repository::{
+        comments::PgCommentRepository, documents::PgDocumentRepository, events::PgEventRepository,
+        projects::PgProjectRepository, suggestions::PgSuggestionRepository,
+    },
+};
+
+pub fn suggestions_router(documents_repository: PgDocumentRepository) -> Router {
+    Router::new()
+        .route(
+            "/",
+            routing::get(get_projects_suggestions::<PgProjectRepository, PgSuggestionRepository>),
+        )
+        .route(
+            "/accept",
+            routing::post(
+                post_projects_suggestions_accept_all::<
+                    PgProjectRepository,
+                    PgDocumentRepository,
+                    PgCommentRepository,
+                    PgSuggestionRepository,
+                    PgEventRepository,
+                >,
+            ),
+        )
+        .route(
+            "/reject",
+            routing::post(
+                post_projects_suggestions_reject_all::<
+                    PgProjectRepository,
+                    PgSuggestionRepository,
+                    PgEventRepository,
+                >,
+            ),
+        )
+        .route(
+            "/:suggestion_id/accept",
+            routing::post(
+                post_projects_suggestions_accept::<
+                    PgProjectRepository,
+                    PgDocumentRepository,
+                    PgCommentRepository,
+                    PgSuggestionRepository,
+                    PgEventRepository,
+                >,
+            ),
+        )
+        .route(
+            "/:suggestion_id/reject",
+            routing::post(
+                post_projects_suggestions_reject::<
+                    PgProjectRepository,
+                    PgSuggestionRepository,
+                    PgEventRepository,
+                >,
+            ),
+        )
+        .layer(Extension(documents_repository))
+}
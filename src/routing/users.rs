@@ -1,13 +1,132 @@
-use axum::{routing, Extension, Router};
+use axum::{extract::DefaultBodyLimit, routing, Extension, Router};
 
 use crate::{
-    control::users::{get_users, post_users},
-    repository::users::PgUserRepository,
+    constants::AVATAR_SIZE_LIMIT_IN_BYTES,
+    control::{
+        accounts::post_users_me_deletion,
+        api_tokens::{delete_users_me_tokens, get_users_me_tokens, post_users_me_tokens},
+        passwords::{
+            post_users_password_reset, post_users_password_reset_confirm, post_users_verify,
+            put_users_me_password,
+        },
+        profiles::{
+            delete_users_me_avatar, get_users_avatar, get_users_me_profile, put_users_me_avatar,
+            put_users_me_profile,
+        },
+        usage::get_users_me_usage,
+        users::{get_users, post_users},
+        verifications::{post_users_me_verification, post_users_verification_confirm},
+    },
+    notify::SharedNotifier,
+    repository::{
+        accounts::PgAccountRepository, api_tokens::PgApiTokenRepository,
+        email_verifications::PgEmailVerificationRepository,
+        password_resets::PgPasswordResetRepository, profiles::PgProfileRepository,
+        projects::PgProjectRepository, usage::PgUsageRepository, users::PgUserRepository,
+    },
 };
 
-pub fn users_router(users_repository: PgUserRepository) -> Router {
+#[allow(clippy::too_many_arguments)]
+pub fn users_router(
+    users_repository: PgUserRepository,
+    usage_repository: PgUsageRepository,
+    password_resets_repository: PgPasswordResetRepository,
+    notifier: SharedNotifier,
+    accounts_repository: PgAccountRepository,
+    profiles_repository: PgProfileRepository,
+    email_verifications_repository: PgEmailVerificationRepository,
+    api_tokens_repository: PgApiTokenRepository,
+    projects_repository: PgProjectRepository,
+) -> Router {
+    let usage_router = Router::new()
+        .route(
+            "/me/usage",
+            routing::get(get_users_me_usage::<PgUsageRepository>),
+        )
+        .layer(Extension(usage_repository));
+
+    let password_reset_router = Router::new()
+        .route(
+            "/password-reset",
+            routing::post(post_users_password_reset::<PgUserRepository, PgPasswordResetRepository>),
+        )
+        .route(
+            "/password-reset/confirm",
+            routing::post(post_users_password_reset_confirm::<PgPasswordResetRepository>),
+        )
+        .layer(Extension(password_resets_repository));
+
+    let verification_router = Router::new()
+        .route(
+            "/me/verification",
+            routing::post(
+                post_users_me_verification::<PgUserRepository, PgEmailVerificationRepository>,
+            ),
+        )
+        .route(
+            "/verification/confirm",
+            routing::post(post_users_verification_confirm::<PgEmailVerificationRepository>),
+        )
+        .route(
+            "/",
+            routing::post(post_users::<PgUserRepository, PgEmailVerificationRepository>),
+        )
+        .layer(Extension(email_verifications_repository));
+
+    let deletion_router = Router::new()
+        .route(
+            "/me/deletion",
+            routing::post(post_users_me_deletion::<PgAccountRepository>),
+        )
+        .layer(Extension(accounts_repository));
+
+    let profile_router = Router::new()
+        .route(
+            "/me/profile",
+            routing::get(get_users_me_profile::<PgProfileRepository>)
+                .put(put_users_me_profile::<PgProfileRepository>),
+        )
+        .route(
+            "/me/avatar",
+            routing::put(put_users_me_avatar::<PgProfileRepository>)
+                .delete(delete_users_me_avatar::<PgProfileRepository>)
+                .layer(DefaultBodyLimit::max(*AVATAR_SIZE_LIMIT_IN_BYTES)),
+        )
+        .route(
+            "/:user_email/avatar",
+            routing::get(get_users_avatar::<PgProfileRepository>),
+        )
+        .layer(Extension(profiles_repository));
+
+    let api_token_router = Router::new()
+        .route(
+            "/me/tokens",
+            routing::get(get_users_me_tokens::<PgApiTokenRepository>)
+                .post(post_users_me_tokens::<PgProjectRepository, PgApiTokenRepository>),
+        )
+        .route(
+            "/me/tokens/:token_id",
+            routing::delete(delete_users_me_tokens::<PgApiTokenRepository>),
+        )
+        .layer(Extension(api_tokens_repository))
+        .layer(Extension(projects_repository));
+
     Router::new()
+        .merge(usage_router)
+        .merge(api_token_router)
+        .merge(profile_router)
+        .merge(deletion_router)
+        .merge(password_reset_router)
+        .merge(verification_router)
+        .route(
+            "/verify",
+            routing::post(post_users_verify::<PgUserRepository>),
+        )
+        .route(
+            "/me/password",
+            routing::put(put_users_me_password::<PgUserRepository>),
+        )
         .route("/:user_email", routing::get(get_users::<PgUserRepository>))
-        .route("/", routing::post(post_users::<PgUserRepository>))
         .layer(Extension(users_repository))
+        .layer(Extension(notifier))
 }
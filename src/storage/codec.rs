@@ -0,0 +1,76 @@
+use std::{
+    io::{Read, Write},
+    str::FromStr,
+};
+
+use flate2::{read::GzDecoder, write::GzEncoder};
+use tracing::error;
+
+use super::BlobError;
+
+/// Prefix of encoded blobs followed by the codec id, text documents never start with a NUL byte
+pub(super) const MAGIC: &[u8] = b"\0agartex";
+
+const GZIP_CODEC: u8 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Compression {
+    None,
+    Gzip,
+}
+
+impl FromStr for Compression {
+    type Err = String;
+
+    fn from_str(compression: &str) -> Result<Self, Self::Err> {
+        match compression {
+            "none" => Ok(Self::None),
+            "gzip" => Ok(Self::Gzip),
+            _ => Err(format!("Unknown compression {}", compression)),
+        }
+    }
+}
+
+/// Prepares content for storage, uncompressed content is stored as is
+pub fn encode(content: &[u8], compression: Compression) -> Result<Vec<u8>, BlobError> {
+    match compression {
+        Compression::None => Ok(content.to_vec()),
+        Compression::Gzip => {
+            let mut encoded = MAGIC.to_vec();
+            encoded.push(GZIP_CODEC);
+
+            let mut encoder = GzEncoder::new(encoded, flate2::Compression::default());
+            encoder
+                .write_all(content)
+                .and_then(|_| encoder.finish())
+                .map_err(|err| {
+                    error!(%err);
+                    BlobError::Unknown
+                })
+        }
+    }
+}
+
+/// Restores stored content, blobs without the prefix were written before compression existed
+pub fn decode(stored: Vec<u8>) -> Result<Vec<u8>, BlobError> {
+    let Some(encoded) = stored.strip_prefix(MAGIC) else {
+        return Ok(stored);
+    };
+
+    match encoded.split_first() {
+        Some((&GZIP_CODEC, compressed)) => {
+            let mut content = Vec::new();
+            GzDecoder::new(compressed)
+                .read_to_end(&mut content)
+                .map_err(|err| {
+                    error!(%err);
+                    BlobError::Unknown
+                })?;
+            Ok(content)
+        }
+        _ => {
+            error!(codec = ?encoded.first(), "Unknown blob codec");
+            Err(BlobError::Unknown)
+        }
+    }
+}
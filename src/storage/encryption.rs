@@ -0,0 +1,473 @@
+use std::{
+    collections::HashMap,
+    fmt::{self, Debug},
+    fs,
+    sync::{Arc, Mutex},
+};
+
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm, Key, Nonce,
+};
+use anyhow::anyhow;
+use axum::{async_trait, body::Bytes};
+use sqlx::PgPool;
+use tracing::{error, info, warn};
+
+use crate::{
+    constants::{MASTER_KEY, MASTER_KEY_FILE},
+    database,
+};
+
+use super::{
+    codec::MAGIC,
+    layout::{is_applied, mark_applied},
+    BlobError, BlobStore, BlobStream, SharedBlobStore, OBJECTS_PREFIX, PROJECTS_PREFIX,
+    USERS_PREFIX,
+};
+
+/// Codec id following the magic prefix, then the data key id and the nonce
+pub(super) const ENCRYPTED_CODEC: u8 = 2;
+
+const KEY_ID_LENGTH: usize = 4;
+const NONCE_LENGTH: usize = 12;
+
+const ENCRYPTED_BLOBS_MIGRATION: &str = "encrypted_blobs";
+
+/// Key encrypting the data keys, never used on file contents directly
+#[derive(Clone)]
+pub struct MasterKey(Aes256Gcm);
+
+impl Debug for MasterKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("MasterKey(..)")
+    }
+}
+
+impl MasterKey {
+    pub fn from_hex(hex_key: &str) -> anyhow::Result<Self> {
+        let bytes = hex::decode(hex_key.trim())?;
+        if bytes.len() != 32 {
+            return Err(anyhow!("Master key must be 32 bytes long"));
+        }
+
+        Ok(Self(Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&bytes))))
+    }
+
+    /// Reads the key from `MASTER_KEY` or `MASTER_KEY_FILE`, encryption is disabled without either
+    pub fn from_config() -> anyhow::Result<Option<Self>> {
+        if !MASTER_KEY.is_empty() {
+            return Self::from_hex(&MASTER_KEY).map(Some);
+        }
+        if !MASTER_KEY_FILE.is_empty() {
+            return Self::from_hex(&fs::read_to_string(MASTER_KEY_FILE.as_str())?).map(Some);
+        }
+
+        Ok(None)
+    }
+
+    pub fn wrap(&self, data_key: &Key<Aes256Gcm>) -> Vec<u8> {
+        seal(&self.0, &[], data_key)
+    }
+
+    pub fn unwrap(&self, wrapped_key: &[u8]) -> Option<Key<Aes256Gcm>> {
+        let nonce = wrapped_key.get(..NONCE_LENGTH)?;
+        let ciphertext = wrapped_key.get(NONCE_LENGTH..)?;
+        let data_key = self.0.decrypt(Nonce::from_slice(nonce), ciphertext).ok()?;
+
+        (data_key.len() == 32).then(|| *Key::<Aes256Gcm>::from_slice(&data_key))
+    }
+}
+
+fn seal(cipher: &Aes256Gcm, header: &[u8], content: &[u8]) -> Vec<u8> {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    // encryption only fails for inputs larger than the GCM limit of 64 GiB
+    let ciphertext = cipher.encrypt(&nonce, content).expect("content too large");
+
+    let mut sealed = header.to_vec();
+    sealed.extend_from_slice(&nonce);
+    sealed.extend_from_slice(&ciphertext);
+    sealed
+}
+
+/// Encrypts content with a data key, recording the key id so any project sharing the blob can read it
+pub fn encrypt(key_id: i32, data_key: &Key<Aes256Gcm>, content: &[u8]) -> Vec<u8> {
+    let mut header = MAGIC.to_vec();
+    header.push(ENCRYPTED_CODEC);
+    header.extend_from_slice(&key_id.to_be_bytes());
+
+    seal(&Aes256Gcm::new(data_key), &header, content)
+}
+
+/// Returns the data key id named by an encryption header. Plain content may start with the
+/// same bytes, so whether a blob is encrypted is recorded in `encrypted_blobs` instead.
+pub fn encrypted_with(stored: &[u8]) -> Option<i32> {
+    let header = stored
+        .strip_prefix(MAGIC)?
+        .strip_prefix(&[ENCRYPTED_CODEC])?;
+    let key_id = header.get(..KEY_ID_LENGTH)?;
+
+    Some(i32::from_be_bytes(key_id.try_into().ok()?))
+}
+
+pub fn decrypt(data_key: &Key<Aes256Gcm>, stored: &[u8]) -> Result<Vec<u8>, BlobError> {
+    let start = MAGIC.len() + 1 + KEY_ID_LENGTH;
+    let (Some(nonce), Some(ciphertext)) = (
+        stored.get(start..start + NONCE_LENGTH),
+        stored.get(start + NONCE_LENGTH..),
+    ) else {
+        error!("Truncated encrypted blob");
+        return Err(BlobError::Unknown);
+    };
+
+    Aes256Gcm::new(data_key)
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| {
+            error!("Could not decrypt blob");
+            BlobError::Unknown
+        })
+}
+
+/// Files of a project use its data key, everything else uses the shared key.
+/// Content-addressed objects are deduplicated across projects, so one object may belong to
+/// several of them. They are removed by the collector once no project references them.
+pub fn key_owner(key: &str) -> Option<i32> {
+    match key.split('/').collect::<Vec<_>>().as_slice() {
+        [PROJECTS_PREFIX, project_id, _, ..] => project_id.parse().ok(),
+        _ => None,
+    }
+}
+
+#[derive(Default)]
+struct KeyCache {
+    by_id: HashMap<i32, Key<Aes256Gcm>>,
+    by_owner: HashMap<Option<i32>, i32>,
+}
+
+/// Encrypts everything written through it with envelope encryption.
+/// Blobs without a recorded data key were written before encryption was enabled and are returned as they are.
+pub struct EncryptedBlobStore {
+    inner: SharedBlobStore,
+    pool: PgPool,
+    master_key: MasterKey,
+    keys: Mutex<KeyCache>,
+}
+
+impl Debug for EncryptedBlobStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EncryptedBlobStore")
+            .field("inner", &self.inner)
+            .finish_non_exhaustive()
+    }
+}
+
+impl EncryptedBlobStore {
+    pub fn new(inner: SharedBlobStore, pool: &PgPool, master_key: MasterKey) -> Self {
+        Self {
+            inner,
+            pool: pool.clone(),
+            master_key,
+            keys: Mutex::default(),
+        }
+    }
+
+    fn unwrap_key(&self, wrapped_key: &[u8]) -> Result<Key<Aes256Gcm>, BlobError> {
+        self.master_key.unwrap(wrapped_key).ok_or_else(|| {
+            error!("Could not unwrap data key, is the master key right?");
+            BlobError::Unknown
+        })
+    }
+
+    async fn key_by_id(&self, key_id: i32) -> Result<Key<Aes256Gcm>, BlobError> {
+        if let Some(data_key) = self.keys.lock().unwrap().by_id.get(&key_id) {
+            return Ok(*data_key);
+        }
+
+        let wrapped_key =
+            sqlx::query_scalar::<_, Vec<u8>>("SELECT wrapped_key FROM data_keys WHERE key_id = $1")
+                .bind(key_id)
+                .fetch_one(&self.pool)
+                .await
+                .map_err(|err| {
+                    error!(%err);
+                    BlobError::Unknown
+                })?;
+
+        let data_key = self.unwrap_key(&wrapped_key)?;
+        self.keys.lock().unwrap().by_id.insert(key_id, data_key);
+        Ok(data_key)
+    }
+
+    async fn key_for(&self, owner: Option<i32>) -> Result<(i32, Key<Aes256Gcm>), BlobError> {
+        let cached = self.keys.lock().unwrap().by_owner.get(&owner).copied();
+        if let Some(key_id) = cached {
+            return Ok((key_id, self.key_by_id(key_id).await?));
+        }
+
+        // concurrent writers may both create a key, only the first one is kept
+        let create_key_sql = "
+            INSERT INTO data_keys (project_id, wrapped_key)
+            VALUES ($1, $2)
+            ON CONFLICT DO NOTHING
+        ";
+        let get_key_sql = "
+            SELECT key_id, wrapped_key
+            FROM data_keys
+            WHERE project_id IS NOT DISTINCT FROM $1
+        ";
+
+        let wrapped_key = self.master_key.wrap(&Aes256Gcm::generate_key(OsRng));
+        let result = sqlx::query(create_key_sql)
+            .bind(owner)
+            .bind(&wrapped_key)
+            .execute(&self.pool)
+            .await;
+        let stored = match result {
+            Ok(_) => {
+                sqlx::query_as::<_, (i32, Vec<u8>)>(get_key_sql)
+                    .bind(owner)
+                    .fetch_one(&self.pool)
+                    .await
+            }
+            Err(err) => Err(err),
+        };
+
+        let (key_id, wrapped_key) = stored.map_err(|err| {
+            error!(%err);
+            BlobError::Unknown
+        })?;
+        let data_key = self.unwrap_key(&wrapped_key)?;
+
+        let mut keys = self.keys.lock().unwrap();
+        keys.by_id.insert(key_id, data_key);
+        keys.by_owner.insert(owner, key_id);
+        Ok((key_id, data_key))
+    }
+
+    async fn recorded_key(&self, key: &str) -> Result<Option<i32>, BlobError> {
+        sqlx::query_scalar::<_, i32>("SELECT key_id FROM encrypted_blobs WHERE blob_key = $1")
+            .bind(key)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|err| {
+                error!(%err);
+                BlobError::Unknown
+            })
+    }
+
+    async fn record_key(&self, key: &str, key_id: Option<i32>) -> Result<(), BlobError> {
+        let result = match key_id {
+            Some(key_id) => {
+                sqlx::query(
+                    "
+                    INSERT INTO encrypted_blobs (blob_key, key_id)
+                    VALUES ($1, $2)
+                    ON CONFLICT (blob_key) DO UPDATE SET key_id = EXCLUDED.key_id
+                ",
+                )
+                .bind(key)
+                .bind(key_id)
+                .execute(&self.pool)
+                .await
+            }
+            None => {
+                sqlx::query("DELETE FROM encrypted_blobs WHERE blob_key = $1")
+                    .bind(key)
+                    .execute(&self.pool)
+                    .await
+            }
+        };
+
+        result.map(|_| ()).map_err(|err| {
+            error!(%err);
+            BlobError::Unknown
+        })
+    }
+
+    async fn move_key(&self, from: &str, to: &str) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM encrypted_blobs WHERE blob_key = $1")
+            .bind(to)
+            .execute(&mut tx)
+            .await?;
+        sqlx::query("UPDATE encrypted_blobs SET blob_key = $2 WHERE blob_key = $1")
+            .bind(from)
+            .bind(to)
+            .execute(&mut tx)
+            .await?;
+        tx.commit().await
+    }
+
+    /// Records the data keys of blobs encrypted before they were kept in the database.
+    /// A header alone may be plain content, so only blobs that decrypt with the named key count.
+    pub(super) async fn record_encrypted_blobs(&self) -> anyhow::Result<()> {
+        info!("Recording encrypted blobs");
+
+        let mut recorded = 0;
+        for prefix in [PROJECTS_PREFIX, OBJECTS_PREFIX, USERS_PREFIX] {
+            let keys = self
+                .inner
+                .list(prefix)
+                .await
+                .map_err(|err| anyhow!("Could not list {}: {:?}", prefix, err))?;
+
+            for key in keys {
+                let stored = match self.inner.get(&key).await {
+                    Ok(stored) => stored,
+                    Err(err) => {
+                        warn!(key, ?err, "Could not read blob");
+                        continue;
+                    }
+                };
+                let Some(key_id) = encrypted_with(&stored) else {
+                    continue;
+                };
+                let Ok(data_key) = self.key_by_id(key_id).await else {
+                    continue;
+                };
+                if decrypt(&data_key, &stored).is_err() {
+                    continue;
+                }
+
+                self.record_key(&key, Some(key_id))
+                    .await
+                    .map_err(|err| anyhow!("Could not record {}: {:?}", key, err))?;
+                recorded += 1;
+            }
+        }
+
+        info!(recorded, "Recorded encrypted blobs");
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl BlobStore for EncryptedBlobStore {
+    async fn put(&self, key: &str, content: &[u8]) -> Result<(), BlobError> {
+        let (key_id, data_key) = self.key_for(key_owner(key)).await?;
+
+        // the key is recorded first, so an interrupted write is reported as unreadable
+        // instead of serving the previous plain content as ciphertext or the other way round
+        let previous = self.recorded_key(key).await?;
+        if previous != Some(key_id) {
+            self.record_key(key, Some(key_id)).await?;
+        }
+
+        let result = self
+            .inner
+            .put(key, &encrypt(key_id, &data_key, content))
+            .await;
+        if result.is_err()
+            && previous != Some(key_id)
+            && self.record_key(key, previous).await.is_err()
+        {
+            warn!(key, "Could not restore the recorded data key");
+        }
+        result
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>, BlobError> {
+        let stored = self.inner.get(key).await?;
+        match self.recorded_key(key).await? {
+            Some(key_id) => decrypt(&self.key_by_id(key_id).await?, &stored),
+            None => Ok(stored),
+        }
+    }
+
+    async fn stream(&self, key: &str) -> Result<BlobStream, BlobError> {
+        // authentication covers the whole blob, so it is decrypted before anything is sent
+        let content = self.get(key).await?;
+        Ok(Box::pin(futures::stream::once(async move {
+            Ok(Bytes::from(content))
+        })))
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), BlobError> {
+        let result = self.inner.delete(key).await;
+        if let Ok(()) | Err(BlobError::Missing) = result {
+            self.record_key(key, None).await?;
+        }
+        result
+    }
+
+    async fn rename(&self, from: &str, to: &str) -> Result<(), BlobError> {
+        self.inner.rename(from, to).await?;
+        self.move_key(from, to).await.map_err(|err| {
+            error!(%err);
+            BlobError::Unknown
+        })
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>, BlobError> {
+        self.inner.list(prefix).await
+    }
+
+    async fn exists(&self, key: &str) -> Result<bool, BlobError> {
+        self.inner.exists(key).await
+    }
+
+    // presigned downloads would hand out ciphertext, so they always go through the service
+}
+
+/// Wraps the store in envelope encryption when a master key is configured
+pub async fn with_encryption(
+    store: SharedBlobStore,
+    pool: &PgPool,
+) -> anyhow::Result<SharedBlobStore> {
+    let Some(master_key) = MasterKey::from_config()? else {
+        return Ok(store);
+    };
+
+    info!("Encrypting stored files");
+    let store = EncryptedBlobStore::new(store, pool, master_key);
+    if !is_applied(pool, ENCRYPTED_BLOBS_MIGRATION).await? {
+        store.record_encrypted_blobs().await?;
+        mark_applied(pool, ENCRYPTED_BLOBS_MIGRATION).await?;
+    }
+
+    Ok(Arc::new(store))
+}
+
+/// Re-wraps every data key with a new master key, file contents are left untouched.
+/// Keys already wrapped with the new master key are skipped, so the rotation can be restarted.
+#[tracing::instrument]
+pub async fn rotate_master_key(new_key_file: &str) -> anyhow::Result<()> {
+    tracing_subscriber::fmt().init();
+
+    let Some(current_key) = MasterKey::from_config()? else {
+        return Err(anyhow!("No master key is configured"));
+    };
+    let new_key = MasterKey::from_hex(&fs::read_to_string(new_key_file)?)?;
+
+    let pool = database::create_conn_pool().await?;
+    let data_keys =
+        sqlx::query_as::<_, (i32, Vec<u8>)>("SELECT key_id, wrapped_key FROM data_keys")
+            .fetch_all(&pool)
+            .await?;
+
+    let mut rotated = 0;
+    for (key_id, wrapped_key) in data_keys {
+        let Some(data_key) = current_key.unwrap(&wrapped_key) else {
+            match new_key.unwrap(&wrapped_key) {
+                Some(_) => continue,
+                None => return Err(anyhow!("Data key {} matches neither master key", key_id)),
+            }
+        };
+
+        sqlx::query("UPDATE data_keys SET wrapped_key = $1 WHERE key_id = $2")
+            .bind(new_key.wrap(&data_key))
+            .bind(key_id)
+            .execute(&pool)
+            .await?;
+        rotated += 1;
+    }
+
+    if rotated == 0 {
+        warn!("No data key needed rotation");
+    }
+    info!(
+        rotated,
+        "Rotated data keys, configure the new master key before restarting"
+    );
+    Ok(())
+}
@@ -0,0 +1,175 @@
+use sqlx::PgPool;
+use tracing::{info, warn};
+
+use crate::domain::{documents::Document, resources::Resource};
+
+use super::{codec, document_key, object_key, resource_key, BlobError, BlobStore};
+
+const ID_KEYS_MIGRATION: &str = "id_keys";
+const FILE_SIZES_MIGRATION: &str = "file_sizes";
+
+/// Key used before files were stored by id, built from the user-supplied name
+pub(super) fn legacy_key(project_id: i32, name: &str) -> String {
+    format!("{}/{}", project_id, name)
+}
+
+/// Moves one file to its id-based key, files already moved or never written are skipped
+pub(super) async fn relocate(
+    store: &dyn BlobStore,
+    from: &str,
+    to: &str,
+) -> Result<bool, BlobError> {
+    if !store.exists(from).await? {
+        return Ok(false);
+    }
+
+    store.rename(from, to).await?;
+    Ok(true)
+}
+
+pub(super) async fn is_applied(pool: &PgPool, name: &str) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar::<_, String>("SELECT name FROM storage_migrations WHERE name = $1")
+        .bind(name)
+        .fetch_optional(pool)
+        .await
+        .map(|applied| applied.is_some())
+}
+
+pub(super) async fn mark_applied(pool: &PgPool, name: &str) -> Result<(), sqlx::Error> {
+    sqlx::query("INSERT INTO storage_migrations (name) VALUES ($1)")
+        .bind(name)
+        .execute(pool)
+        .await
+        .map(|_| ())
+}
+
+/// Relocates documents and resources stored under their names to the id-based layout.
+/// Every move is idempotent so an interrupted migration can simply be restarted.
+async fn relocate_files(pool: &PgPool, store: &dyn BlobStore) -> anyhow::Result<()> {
+    info!("Relocating stored files to id-based keys");
+
+    let documents = sqlx::query_as::<_, Document>(
+        "SELECT document_id, project_id, name, folder_id FROM documents",
+    )
+    .fetch_all(pool)
+    .await?;
+    let resources = sqlx::query_as::<_, Resource>(
+        "
+        SELECT resource_id, project_id, name, folder_id, content_hash
+        FROM resources
+        WHERE content_hash IS NULL
+    ",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let moves = documents
+        .iter()
+        .map(|document| {
+            (
+                legacy_key(document.project_id, &document.name),
+                document_key(document),
+            )
+        })
+        .chain(resources.iter().map(|resource| {
+            (
+                legacy_key(resource.project_id, &resource.name),
+                resource_key(resource),
+            )
+        }));
+
+    let mut relocated = 0;
+    for (from, to) in moves {
+        match relocate(store, &from, &to).await {
+            Ok(true) => relocated += 1,
+            Ok(false) => (),
+            // names the old layout could not store safely are left for fsck to report
+            Err(err) => warn!(from, ?err, "Could not relocate file"),
+        }
+    }
+
+    info!(relocated, "Relocated stored files");
+    Ok(())
+}
+
+/// Sizes are those of the plain content, as every write records them,
+/// so compressed documents are decoded before they are measured
+pub(super) async fn measure(store: &dyn BlobStore, key: &str, encoded: bool) -> Option<i64> {
+    let content = match encoded {
+        true => store.get(key).await.and_then(codec::decode),
+        false => store.get(key).await,
+    };
+    match content {
+        Ok(content) => Some(content.len() as i64),
+        // files missing from storage are left for fsck to report
+        Err(err) => {
+            warn!(key, ?err, "Could not measure file");
+            None
+        }
+    }
+}
+
+/// Records the size of files written before usage was tracked
+async fn measure_files(pool: &PgPool, store: &dyn BlobStore) -> anyhow::Result<()> {
+    info!("Measuring stored files");
+
+    let documents = sqlx::query_as::<_, Document>(
+        "SELECT document_id, project_id, name, folder_id FROM documents",
+    )
+    .fetch_all(pool)
+    .await?;
+    let resources = sqlx::query_as::<_, Resource>(
+        "SELECT resource_id, project_id, name, folder_id, content_hash FROM resources",
+    )
+    .fetch_all(pool)
+    .await?;
+    let object_hashes =
+        sqlx::query_scalar::<_, String>("SELECT DISTINCT content_hash FROM snapshot_files")
+            .fetch_all(pool)
+            .await?;
+
+    for document in &documents {
+        if let Some(size) = measure(store, &document_key(document), true).await {
+            sqlx::query("UPDATE documents SET size = $1 WHERE document_id = $2")
+                .bind(size)
+                .bind(document.document_id)
+                .execute(pool)
+                .await?;
+        }
+    }
+    for resource in &resources {
+        if let Some(size) = measure(store, &resource_key(resource), false).await {
+            sqlx::query("UPDATE resources SET size = $1 WHERE resource_id = $2")
+                .bind(size)
+                .bind(resource.resource_id)
+                .execute(pool)
+                .await?;
+        }
+    }
+    for content_hash in &object_hashes {
+        if let Some(size) = measure(store, &object_key(content_hash), false).await {
+            sqlx::query("UPDATE snapshot_files SET size = $1 WHERE content_hash = $2")
+                .bind(size)
+                .bind(content_hash)
+                .execute(pool)
+                .await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Applies the storage migrations that have not run yet, in order
+pub async fn migrate_storage(pool: &PgPool, store: &dyn BlobStore) -> anyhow::Result<()> {
+    if !is_applied(pool, ID_KEYS_MIGRATION).await? {
+        relocate_files(pool, store).await?;
+        mark_applied(pool, ID_KEYS_MIGRATION).await?;
+    }
+
+    if !is_applied(pool, FILE_SIZES_MIGRATION).await? {
+        measure_files(pool, store).await?;
+        mark_applied(pool, FILE_SIZES_MIGRATION).await?;
+    }
+
+    Ok(())
+}
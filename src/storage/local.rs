@@ -0,0 +1,190 @@
+use std::{
+    io::{self, ErrorKind},
+    path::{Component, Path, PathBuf},
+    process,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use axum::async_trait;
+use tokio::{fs, io::AsyncWriteExt};
+use tokio_util::io::ReaderStream;
+use tracing::{error, warn};
+
+use super::{BlobError, BlobStore, BlobStream};
+
+/// Marks partially written files, which are never reported as blobs
+const TEMP_SUFFIX: &str = ".tmp";
+
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone)]
+pub struct LocalBlobStore {
+    root: PathBuf,
+}
+
+impl LocalBlobStore {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    /// Maps a key to a path below the root, refusing keys that could escape it
+    fn path(&self, key: &str) -> Result<PathBuf, BlobError> {
+        let relative = Path::new(key);
+        if relative
+            .components()
+            .any(|component| !matches!(component, Component::Normal(_)))
+        {
+            warn!(key, "Rejected blob key");
+            return Err(BlobError::Unknown);
+        }
+
+        Ok(self.root.join(relative))
+    }
+
+    fn key(&self, path: &Path) -> Option<String> {
+        let relative = path.strip_prefix(&self.root).ok()?;
+        let parts = relative
+            .components()
+            .map(|component| component.as_os_str().to_str())
+            .collect::<Option<Vec<_>>>()?;
+        Some(parts.join("/"))
+    }
+
+    async fn create_parent(path: &Path) -> Result<(), BlobError> {
+        match path.parent() {
+            Some(parent) => fs::create_dir_all(parent).await.map_err(map_io_error),
+            None => Ok(()),
+        }
+    }
+}
+
+fn is_temp_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.starts_with('.') && name.ends_with(TEMP_SUFFIX))
+}
+
+/// Temporary files live next to their target, so the final rename never crosses filesystems
+fn temp_path(path: &Path) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let counter = TEMP_COUNTER.fetch_add(1, Ordering::Relaxed);
+    path.with_file_name(format!(
+        ".{}.{}-{}{}",
+        name,
+        process::id(),
+        counter,
+        TEMP_SUFFIX
+    ))
+}
+
+async fn sync_parent(path: &Path) -> io::Result<()> {
+    match path.parent() {
+        Some(parent) => fs::File::open(parent).await?.sync_all().await,
+        None => Ok(()),
+    }
+}
+
+/// Replaces the file so that readers and crashes only ever see the old or the new content
+async fn write_atomic(path: &Path, content: &[u8]) -> io::Result<()> {
+    let temp = temp_path(path);
+    let result = async {
+        let mut file = fs::File::create(&temp).await?;
+        file.write_all(content).await?;
+        file.sync_all().await?;
+        fs::rename(&temp, path).await?;
+        sync_parent(path).await
+    }
+    .await;
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp).await;
+    }
+    result
+}
+
+pub(super) fn map_io_error(err: io::Error) -> BlobError {
+    match err.kind() {
+        ErrorKind::NotFound => BlobError::Missing,
+        ErrorKind::StorageFull | ErrorKind::QuotaExceeded => {
+            error!(%err);
+            BlobError::NoSpace
+        }
+        _ => {
+            error!(%err);
+            BlobError::Unknown
+        }
+    }
+}
+
+#[async_trait]
+impl BlobStore for LocalBlobStore {
+    #[tracing::instrument(skip(self, content))]
+    async fn put(&self, key: &str, content: &[u8]) -> Result<(), BlobError> {
+        let path = self.path(key)?;
+        Self::create_parent(&path).await?;
+        write_atomic(&path, content).await.map_err(map_io_error)
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn get(&self, key: &str) -> Result<Vec<u8>, BlobError> {
+        fs::read(self.path(key)?).await.map_err(map_io_error)
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn stream(&self, key: &str) -> Result<BlobStream, BlobError> {
+        let file = fs::File::open(self.path(key)?)
+            .await
+            .map_err(map_io_error)?;
+        Ok(Box::pin(ReaderStream::new(file)))
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn delete(&self, key: &str) -> Result<(), BlobError> {
+        fs::remove_file(self.path(key)?).await.map_err(map_io_error)
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn rename(&self, from: &str, to: &str) -> Result<(), BlobError> {
+        let from = self.path(from)?;
+        let to = self.path(to)?;
+        Self::create_parent(&to).await?;
+        fs::rename(from, &to).await.map_err(map_io_error)?;
+        sync_parent(&to).await.map_err(map_io_error)
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn list(&self, prefix: &str) -> Result<Vec<String>, BlobError> {
+        let start = match prefix {
+            "" => self.root.clone(),
+            prefix => self.path(prefix.trim_end_matches('/'))?,
+        };
+
+        let mut keys = Vec::new();
+        let mut directories = vec![start];
+        while let Some(directory) = directories.pop() {
+            let mut entries = match fs::read_dir(&directory).await {
+                Ok(entries) => entries,
+                Err(err) if err.kind() == ErrorKind::NotFound => continue,
+                Err(err) => return Err(map_io_error(err)),
+            };
+
+            while let Some(entry) = entries.next_entry().await.map_err(map_io_error)? {
+                let file_type = entry.file_type().await.map_err(map_io_error)?;
+                if file_type.is_dir() {
+                    directories.push(entry.path());
+                } else if is_temp_file(&entry.path()) {
+                    continue;
+                } else if let Some(key) = self.key(&entry.path()) {
+                    keys.push(key);
+                }
+            }
+        }
+
+        Ok(keys)
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn exists(&self, key: &str) -> Result<bool, BlobError> {
+        Ok(self.path(key)?.is_file())
+    }
+}
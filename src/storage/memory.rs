@@ -0,0 +1,74 @@
+use std::{
+    collections::BTreeMap,
+    io::Cursor,
+    sync::{Arc, Mutex},
+};
+
+use axum::async_trait;
+use tokio_util::io::ReaderStream;
+
+use super::{BlobError, BlobStore, BlobStream};
+
+/// Keeps every blob in process memory, contents are lost on restart
+#[derive(Debug, Clone, Default)]
+pub struct MemoryBlobStore {
+    blobs: Arc<Mutex<BTreeMap<String, Vec<u8>>>>,
+}
+
+impl MemoryBlobStore {
+    fn blobs(&self) -> std::sync::MutexGuard<'_, BTreeMap<String, Vec<u8>>> {
+        // a panic while holding the lock cannot leave a half-written blob behind
+        self.blobs
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[async_trait]
+impl BlobStore for MemoryBlobStore {
+    async fn put(&self, key: &str, content: &[u8]) -> Result<(), BlobError> {
+        self.blobs().insert(key.to_string(), content.to_vec());
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>, BlobError> {
+        self.blobs().get(key).cloned().ok_or(BlobError::Missing)
+    }
+
+    async fn stream(&self, key: &str) -> Result<BlobStream, BlobError> {
+        let content = self.get(key).await?;
+        Ok(Box::pin(ReaderStream::new(Cursor::new(content))))
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), BlobError> {
+        self.blobs()
+            .remove(key)
+            .map(|_| ())
+            .ok_or(BlobError::Missing)
+    }
+
+    async fn rename(&self, from: &str, to: &str) -> Result<(), BlobError> {
+        let mut blobs = self.blobs();
+        let content = blobs.remove(from).ok_or(BlobError::Missing)?;
+        blobs.insert(to.to_string(), content);
+        Ok(())
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>, BlobError> {
+        let directory = match prefix {
+            "" => String::new(),
+            prefix => format!("{}/", prefix.trim_end_matches('/')),
+        };
+
+        Ok(self
+            .blobs()
+            .keys()
+            .filter(|key| key.starts_with(&directory))
+            .cloned()
+            .collect())
+    }
+
+    async fn exists(&self, key: &str) -> Result<bool, BlobError> {
+        Ok(self.blobs().contains_key(key))
+    }
+}
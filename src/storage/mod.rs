@@ -0,0 +1,139 @@
+pub mod codec;
+pub mod encryption;
+mod layout;
+mod local;
+mod memory;
+mod s3;
+
+use std::{fmt::Debug, io, pin::Pin, str::FromStr, sync::Arc, time::Duration};
+
+use axum::{async_trait, body::Bytes};
+use futures::Stream;
+
+use crate::{
+    constants::{
+        FILE_DIR_PATH, S3_ACCESS_KEY_ID, S3_BUCKET, S3_ENDPOINT, S3_PREFIX,
+        S3_PRESIGN_EXPIRY_IN_SECONDS, S3_REGION, S3_SECRET_ACCESS_KEY, STORAGE_BACKEND,
+    },
+    domain::{documents::Document, resources::Resource},
+};
+
+pub use self::{
+    layout::migrate_storage,
+    local::LocalBlobStore,
+    memory::MemoryBlobStore,
+    s3::{S3BlobStore, S3Settings},
+};
+
+#[derive(Debug)]
+pub enum BlobError {
+    Missing,
+    /// The backend ran out of space, the previous content is left intact
+    NoSpace,
+    Unknown,
+}
+
+pub type BlobStream = Pin<Box<dyn Stream<Item = io::Result<Bytes>> + Send>>;
+
+/// Storage for file contents, addressed by `/`-separated keys
+#[async_trait]
+pub trait BlobStore: Debug + Send + Sync {
+    async fn put(&self, key: &str, content: &[u8]) -> Result<(), BlobError>;
+    async fn get(&self, key: &str) -> Result<Vec<u8>, BlobError>;
+    async fn stream(&self, key: &str) -> Result<BlobStream, BlobError>;
+    async fn delete(&self, key: &str) -> Result<(), BlobError>;
+    async fn rename(&self, from: &str, to: &str) -> Result<(), BlobError>;
+    /// Returns every key below the given directory prefix, in no particular order
+    async fn list(&self, prefix: &str) -> Result<Vec<String>, BlobError>;
+    async fn exists(&self, key: &str) -> Result<bool, BlobError>;
+    /// Returns a URL clients can download the blob from directly, if the backend offers one
+    async fn presign(&self, _key: &str) -> Result<Option<String>, BlobError> {
+        Ok(None)
+    }
+}
+
+pub type SharedBlobStore = Arc<dyn BlobStore>;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StorageBackend {
+    Local,
+    Memory,
+    S3,
+}
+
+impl FromStr for StorageBackend {
+    type Err = String;
+
+    fn from_str(backend: &str) -> Result<Self, Self::Err> {
+        match backend {
+            "local" => Ok(Self::Local),
+            "memory" => Ok(Self::Memory),
+            "s3" => Ok(Self::S3),
+            _ => Err(format!("Unknown storage backend {}", backend)),
+        }
+    }
+}
+
+pub fn from_config() -> io::Result<SharedBlobStore> {
+    Ok(match *STORAGE_BACKEND {
+        StorageBackend::Local => {
+            std::fs::create_dir_all(FILE_DIR_PATH.as_path())?;
+            Arc::new(LocalBlobStore::new(FILE_DIR_PATH.clone()))
+        }
+        StorageBackend::Memory => Arc::new(MemoryBlobStore::default()),
+        StorageBackend::S3 => Arc::new(S3BlobStore::new(S3Settings {
+            bucket: S3_BUCKET.clone(),
+            prefix: S3_PREFIX.clone(),
+            endpoint: Some(S3_ENDPOINT.clone()).filter(|endpoint| !endpoint.is_empty()),
+            region: S3_REGION.clone(),
+            access_key_id: S3_ACCESS_KEY_ID.clone(),
+            secret_access_key: S3_SECRET_ACCESS_KEY.clone(),
+            presign_expiry: Some(*S3_PRESIGN_EXPIRY_IN_SECONDS)
+                .filter(|seconds| *seconds > 0)
+                .map(Duration::from_secs),
+        })),
+    })
+}
+
+pub const PROJECTS_PREFIX: &str = "projects";
+
+/// Keys are built from ids only, user-chosen names never reach the storage backend
+pub fn project_prefix(project_id: i32) -> String {
+    format!("{}/{}", PROJECTS_PREFIX, project_id)
+}
+
+pub fn document_key(document: &Document) -> String {
+    format!(
+        "{}/documents/{}",
+        project_prefix(document.project_id),
+        document.document_id
+    )
+}
+
+/// Resources created before content addressing still live under their project directory
+pub fn resource_key(resource: &Resource) -> String {
+    match &resource.content_hash {
+        Some(content_hash) => object_key(content_hash),
+        None => format!(
+            "{}/resources/{}",
+            project_prefix(resource.project_id),
+            resource.resource_id
+        ),
+    }
+}
+
+pub const OBJECTS_PREFIX: &str = "objects";
+
+/// Resource and snapshot contents are stored once per distinct content, named by their SHA-256 hash
+pub fn object_key(content_hash: &str) -> String {
+    format!("{}/{}/{}", OBJECTS_PREFIX, &content_hash[..2], content_hash)
+}
+
+pub const USERS_PREFIX: &str = "users";
+
+pub fn avatar_key(user_id: i32) -> String {
+    format!("{}/{}/avatar", USERS_PREFIX, user_id)
+}
+
+#[cfg(test)]
+mod tests;
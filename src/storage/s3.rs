@@ -0,0 +1,362 @@
+use std::{fmt::Debug, time::Duration};
+
+use aws_sdk_s3::{
+    config::{BehaviorVersion, Credentials, Region},
+    error::{ProvideErrorMetadata, SdkError},
+    presigning::PresigningConfig,
+    primitives::ByteStream,
+    types::{CompletedMultipartUpload, CompletedPart},
+    Client,
+};
+use axum::async_trait;
+use tokio_util::io::ReaderStream;
+use tracing::{error, info};
+
+use super::{BlobError, BlobStore, BlobStream};
+
+/// Uploads larger than this are split into parts of this size, S3 requires at least 5 MiB
+const MULTIPART_PART_SIZE: usize = 8 * 1024 * 1024;
+
+#[derive(Debug, Clone)]
+pub struct S3Settings {
+    pub bucket: String,
+    pub prefix: String,
+    /// Custom endpoint of an S3-compatible service, AWS is used when absent
+    pub endpoint: Option<String>,
+    pub region: String,
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    /// Lifetime of presigned download URLs, downloads go through the service when absent
+    pub presign_expiry: Option<Duration>,
+}
+
+#[derive(Debug, Clone)]
+pub struct S3BlobStore {
+    client: Client,
+    bucket: String,
+    prefix: String,
+    presign_expiry: Option<Duration>,
+}
+
+impl S3BlobStore {
+    pub fn new(settings: S3Settings) -> Self {
+        let credentials = Credentials::new(
+            settings.access_key_id,
+            settings.secret_access_key,
+            None,
+            None,
+            "configuration",
+        );
+
+        let mut config = aws_sdk_s3::Config::builder()
+            .behavior_version(BehaviorVersion::latest())
+            .region(Region::new(settings.region))
+            .credentials_provider(credentials);
+        if let Some(endpoint) = settings.endpoint {
+            // S3-compatible services rarely support virtual-hosted bucket addressing
+            config = config.endpoint_url(endpoint).force_path_style(true);
+        }
+
+        Self {
+            client: Client::from_conf(config.build()),
+            bucket: settings.bucket,
+            prefix: settings.prefix.trim_matches('/').to_string(),
+            presign_expiry: settings.presign_expiry,
+        }
+    }
+
+    fn object_key(&self, key: &str) -> String {
+        match self.prefix.as_str() {
+            "" => key.to_string(),
+            prefix => format!("{}/{}", prefix, key),
+        }
+    }
+
+    fn blob_key<'a>(&self, object_key: &'a str) -> Option<&'a str> {
+        match self.prefix.as_str() {
+            "" => Some(object_key),
+            prefix => object_key
+                .strip_prefix(prefix)
+                .and_then(|key| key.strip_prefix('/')),
+        }
+    }
+
+    async fn put_multipart(&self, object_key: &str, content: &[u8]) -> Result<(), BlobError> {
+        let upload = self
+            .client
+            .create_multipart_upload()
+            .bucket(&self.bucket)
+            .key(object_key)
+            .send()
+            .await
+            .map_err(map_sdk_error)?;
+        let Some(upload_id) = upload.upload_id() else {
+            error!("Multipart upload started without an id");
+            return Err(BlobError::Unknown);
+        };
+
+        match self.upload_parts(object_key, upload_id, content).await {
+            Ok(parts) => self
+                .client
+                .complete_multipart_upload()
+                .bucket(&self.bucket)
+                .key(object_key)
+                .upload_id(upload_id)
+                .multipart_upload(
+                    CompletedMultipartUpload::builder()
+                        .set_parts(Some(parts))
+                        .build(),
+                )
+                .send()
+                .await
+                .map(|_| ())
+                .map_err(map_sdk_error),
+            Err(err) => {
+                // parts of an abandoned upload are billed until aborted
+                let _ = self
+                    .client
+                    .abort_multipart_upload()
+                    .bucket(&self.bucket)
+                    .key(object_key)
+                    .upload_id(upload_id)
+                    .send()
+                    .await;
+                Err(err)
+            }
+        }
+    }
+
+    async fn upload_parts(
+        &self,
+        object_key: &str,
+        upload_id: &str,
+        content: &[u8],
+    ) -> Result<Vec<CompletedPart>, BlobError> {
+        let mut parts = Vec::new();
+        for (index, chunk) in content.chunks(MULTIPART_PART_SIZE).enumerate() {
+            let part_number = index as i32 + 1;
+            let part = self
+                .client
+                .upload_part()
+                .bucket(&self.bucket)
+                .key(object_key)
+                .upload_id(upload_id)
+                .part_number(part_number)
+                .body(ByteStream::from(chunk.to_vec()))
+                .send()
+                .await
+                .map_err(map_sdk_error)?;
+
+            parts.push(
+                CompletedPart::builder()
+                    .part_number(part_number)
+                    .set_e_tag(part.e_tag().map(String::from))
+                    .build(),
+            );
+        }
+
+        info!("Uploaded {} parts", parts.len());
+        Ok(parts)
+    }
+}
+
+fn map_sdk_error<E: ProvideErrorMetadata + Debug>(err: SdkError<E>) -> BlobError {
+    // a HEAD response carries no body, so a missing object only shows up as its status
+    let status = err
+        .raw_response()
+        .map(|response| response.status().as_u16());
+    let code = err.as_service_error().and_then(|err| err.code());
+    if status == Some(404) || matches!(code, Some("NoSuchKey" | "NotFound")) {
+        return BlobError::Missing;
+    }
+
+    error!(?err);
+    BlobError::Unknown
+}
+
+/// Encodes a key for the `x-amz-copy-source` header, keeping the path separators
+pub(super) fn encode_copy_source(bucket: &str, key: &str) -> String {
+    let mut source = format!("{}/", bucket);
+    for byte in key.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                source.push(byte as char)
+            }
+            _ => source.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    source
+}
+
+#[async_trait]
+impl BlobStore for S3BlobStore {
+    #[tracing::instrument(skip(self, content))]
+    async fn put(&self, key: &str, content: &[u8]) -> Result<(), BlobError> {
+        let object_key = self.object_key(key);
+        if content.len() > MULTIPART_PART_SIZE {
+            return self.put_multipart(&object_key, content).await;
+        }
+
+        self.client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(object_key)
+            .body(ByteStream::from(content.to_vec()))
+            .send()
+            .await
+            .map(|_| ())
+            .map_err(map_sdk_error)
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn get(&self, key: &str) -> Result<Vec<u8>, BlobError> {
+        let object = self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(self.object_key(key))
+            .send()
+            .await
+            .map_err(map_sdk_error)?;
+
+        match object.body.collect().await {
+            Ok(content) => Ok(content.into_bytes().to_vec()),
+            Err(err) => {
+                error!(%err);
+                Err(BlobError::Unknown)
+            }
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn stream(&self, key: &str) -> Result<BlobStream, BlobError> {
+        let object = self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(self.object_key(key))
+            .send()
+            .await
+            .map_err(map_sdk_error)?;
+
+        Ok(Box::pin(ReaderStream::new(object.body.into_async_read())))
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn delete(&self, key: &str) -> Result<(), BlobError> {
+        // deleting a missing object succeeds in S3, but callers expect to hear about it
+        if !self.exists(key).await? {
+            return Err(BlobError::Missing);
+        }
+
+        self.client
+            .delete_object()
+            .bucket(&self.bucket)
+            .key(self.object_key(key))
+            .send()
+            .await
+            .map(|_| ())
+            .map_err(map_sdk_error)
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn rename(&self, from: &str, to: &str) -> Result<(), BlobError> {
+        let source = self.object_key(from);
+        self.client
+            .copy_object()
+            .bucket(&self.bucket)
+            .copy_source(encode_copy_source(&self.bucket, &source))
+            .key(self.object_key(to))
+            .send()
+            .await
+            .map_err(map_sdk_error)?;
+
+        self.client
+            .delete_object()
+            .bucket(&self.bucket)
+            .key(source)
+            .send()
+            .await
+            .map(|_| ())
+            .map_err(map_sdk_error)
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn list(&self, prefix: &str) -> Result<Vec<String>, BlobError> {
+        let directory = match prefix {
+            "" => self.object_key(""),
+            prefix => self.object_key(&format!("{}/", prefix.trim_end_matches('/'))),
+        };
+
+        let mut keys = Vec::new();
+        let mut continuation_token = None;
+        loop {
+            let page = self
+                .client
+                .list_objects_v2()
+                .bucket(&self.bucket)
+                .prefix(&directory)
+                .set_continuation_token(continuation_token)
+                .send()
+                .await
+                .map_err(map_sdk_error)?;
+
+            keys.extend(
+                page.contents()
+                    .iter()
+                    .filter_map(|object| object.key())
+                    .filter_map(|key| self.blob_key(key))
+                    .map(String::from),
+            );
+
+            match page.next_continuation_token() {
+                Some(token) => continuation_token = Some(token.to_string()),
+                None => return Ok(keys),
+            }
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn exists(&self, key: &str) -> Result<bool, BlobError> {
+        match self
+            .client
+            .head_object()
+            .bucket(&self.bucket)
+            .key(self.object_key(key))
+            .send()
+            .await
+        {
+            Ok(_) => Ok(true),
+            Err(err) => match map_sdk_error(err) {
+                BlobError::Missing => Ok(false),
+                err => Err(err),
+            },
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn presign(&self, key: &str) -> Result<Option<String>, BlobError> {
+        let Some(expiry) = self.presign_expiry else {
+            return Ok(None);
+        };
+
+        let config = match PresigningConfig::expires_in(expiry) {
+            Ok(config) => config,
+            Err(err) => {
+                error!(%err);
+                return Err(BlobError::Unknown);
+            }
+        };
+
+        let request = self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(self.object_key(key))
+            .presigned(config)
+            .await
+            .map_err(map_sdk_error)?;
+
+        Ok(Some(request.uri().to_string()))
+    }
+}
@@ -0,0 +1,331 @@
+use std::{env, path::PathBuf, sync::Arc};
+
+use aes_gcm::{
+    aead::{KeyInit, OsRng},
+    Aes256Gcm,
+};
+use futures::TryStreamExt;
+use rand::{distributions::Alphanumeric, Rng};
+
+use super::*;
+
+fn random_name() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(16)
+        .map(char::from)
+        .collect()
+}
+
+fn temp_root() -> PathBuf {
+    env::temp_dir().join(format!("blobs-{}", random_name()))
+}
+
+async fn round_trip(store: &dyn BlobStore) {
+    store.put("1/main.tex", b"Hello").await.unwrap();
+    store.put("1/figures/plot.png", b"\x89PNG").await.unwrap();
+    store.put("10/main.tex", b"").await.unwrap();
+
+    assert_eq!(b"Hello".to_vec(), store.get("1/main.tex").await.unwrap());
+    assert!(store.exists("1/main.tex").await.unwrap());
+    assert!(!store.exists("2/main.tex").await.unwrap());
+    assert!(matches!(
+        store.get("2/main.tex").await,
+        Err(BlobError::Missing)
+    ));
+
+    let streamed: Vec<Bytes> = store
+        .stream("1/figures/plot.png")
+        .await
+        .unwrap()
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(b"\x89PNG".to_vec(), streamed.concat());
+
+    let mut keys = store.list("1").await.unwrap();
+    keys.sort();
+    assert_eq!(vec!["1/figures/plot.png", "1/main.tex"], keys);
+
+    store.rename("1/main.tex", "2/main.tex").await.unwrap();
+    assert!(!store.exists("1/main.tex").await.unwrap());
+    assert_eq!(b"Hello".to_vec(), store.get("2/main.tex").await.unwrap());
+
+    store.delete("2/main.tex").await.unwrap();
+    assert!(matches!(
+        store.delete("2/main.tex").await,
+        Err(BlobError::Missing)
+    ));
+}
+
+#[tokio::test]
+async fn memory_store_round_trip() {
+    round_trip(&MemoryBlobStore::default()).await
+}
+
+#[tokio::test]
+async fn local_store_round_trip() {
+    let root = temp_root();
+    round_trip(&LocalBlobStore::new(root.clone())).await;
+    let _ = std::fs::remove_dir_all(root);
+}
+
+#[tokio::test]
+async fn local_store_overwrites_without_leftovers() {
+    let root = temp_root();
+    let store = LocalBlobStore::new(root.clone());
+
+    store.put("1/main.tex", b"first draft").await.unwrap();
+    store.put("1/main.tex", b"second").await.unwrap();
+
+    assert_eq!(b"second".to_vec(), store.get("1/main.tex").await.unwrap());
+    assert_eq!(1, std::fs::read_dir(root.join("1")).unwrap().count());
+    assert_eq!(vec!["1/main.tex"], store.list("1").await.unwrap());
+    let _ = std::fs::remove_dir_all(root);
+}
+
+#[test]
+fn local_store_reports_full_disk() {
+    assert!(matches!(
+        local::map_io_error(io::Error::from(io::ErrorKind::StorageFull)),
+        BlobError::NoSpace
+    ));
+    assert!(matches!(
+        local::map_io_error(io::Error::from(io::ErrorKind::NotFound)),
+        BlobError::Missing
+    ));
+}
+
+#[tokio::test]
+async fn traversal_names_stay_inside_project_directory() {
+    let root = temp_root();
+    let store = LocalBlobStore::new(root.clone());
+    let names = [
+        "..",
+        ".",
+        "../../etc/passwd",
+        "/etc/passwd",
+        "..\\..\\boot.ini",
+    ];
+
+    for (index, name) in names.iter().enumerate() {
+        let document = Document {
+            document_id: index as i32,
+            project_id: 1,
+            name: name.to_string(),
+            folder_id: None,
+        };
+        let resource = Resource {
+            resource_id: index as i32,
+            project_id: 1,
+            name: name.to_string(),
+            folder_id: None,
+            content_hash: None,
+        };
+
+        let document_key = document_key(&document);
+        let resource_key = resource_key(&resource);
+        assert_eq!(format!("projects/1/documents/{}", index), document_key);
+        assert_eq!(format!("projects/1/resources/{}", index), resource_key);
+
+        store.put(&document_key, b"").await.unwrap();
+        store.put(&resource_key, b"").await.unwrap();
+        assert!(root
+            .join(&document_key)
+            .starts_with(root.join("projects/1")));
+    }
+
+    let mut keys = store.list("").await.unwrap();
+    keys.sort();
+    assert_eq!(2 * names.len(), keys.len());
+    assert!(keys.iter().all(|key| key.starts_with("projects/1/")));
+    let _ = std::fs::remove_dir_all(root);
+}
+
+#[tokio::test]
+async fn layout_relocates_name_keyed_files() {
+    let store = MemoryBlobStore::default();
+    let document = Document {
+        document_id: 5,
+        project_id: 1,
+        name: String::from("main.tex"),
+        folder_id: None,
+    };
+    let legacy = layout::legacy_key(document.project_id, &document.name);
+    store.put(&legacy, b"Hello").await.unwrap();
+
+    assert!(layout::relocate(&store, &legacy, &document_key(&document))
+        .await
+        .unwrap());
+    // a second run finds nothing left to move
+    assert!(!layout::relocate(&store, &legacy, &document_key(&document))
+        .await
+        .unwrap());
+    assert!(!store.exists("1/main.tex").await.unwrap());
+    assert_eq!(
+        b"Hello".to_vec(),
+        store.get("projects/1/documents/5").await.unwrap()
+    );
+}
+
+#[tokio::test]
+async fn layout_measures_plain_content() {
+    let store = MemoryBlobStore::default();
+    let content = "\\section{Introduction}\n".repeat(200);
+    let encoded = codec::encode(content.as_bytes(), codec::Compression::Gzip).unwrap();
+    store.put("projects/1/documents/5", &encoded).await.unwrap();
+
+    assert_eq!(
+        Some(content.len() as i64),
+        layout::measure(&store, "projects/1/documents/5", true).await
+    );
+    assert_eq!(
+        None,
+        layout::measure(&store, "projects/1/documents/6", true).await
+    );
+}
+
+#[tokio::test]
+async fn local_store_rejects_escaping_keys() {
+    let store = LocalBlobStore::new(temp_root());
+
+    assert!(store.put("../outside", b"").await.is_err());
+    assert!(store.get("/etc/passwd").await.is_err());
+    assert!(store.list("1/../..").await.is_err());
+}
+
+#[test]
+fn s3_copy_source_escapes_names() {
+    assert_eq!(
+        "bucket/blobs/1/my%20figure%2B1.png",
+        s3::encode_copy_source("bucket", "blobs/1/my figure+1.png")
+    );
+}
+
+// needs an S3-compatible server, e.g. `docker run -p 9000:9000 minio/minio server /data`
+// with a `test` bucket, run with `cargo test -- --ignored`
+#[tokio::test]
+#[ignore]
+async fn s3_store_round_trip() {
+    let store = S3BlobStore::new(S3Settings {
+        bucket: env::var("S3_BUCKET").unwrap_or(String::from("test")),
+        prefix: format!("test-{}", random_name()),
+        endpoint: Some(env::var("S3_ENDPOINT").unwrap_or(String::from("http://localhost:9000"))),
+        region: String::from("us-east-1"),
+        access_key_id: env::var("S3_ACCESS_KEY_ID").unwrap_or(String::from("minioadmin")),
+        secret_access_key: env::var("S3_SECRET_ACCESS_KEY").unwrap_or(String::from("minioadmin")),
+        presign_expiry: None,
+    });
+
+    round_trip(&store).await;
+
+    // large enough to go through a multipart upload
+    let content = vec![7; 9 * 1024 * 1024];
+    store.put("1/scan.pdf", &content).await.unwrap();
+    assert_eq!(content, store.get("1/scan.pdf").await.unwrap());
+    store.delete("1/scan.pdf").await.unwrap();
+}
+
+#[test]
+fn gzip_codec_round_trips() {
+    let content = "\\section{Introduction}\n".repeat(200);
+
+    let encoded = codec::encode(content.as_bytes(), codec::Compression::Gzip).unwrap();
+
+    assert!(encoded.len() < content.len());
+    assert_eq!(content.as_bytes(), codec::decode(encoded).unwrap());
+}
+
+#[test]
+fn codec_reads_uncompressed_blobs() {
+    let content = b"\\documentclass{article}".to_vec();
+
+    assert_eq!(
+        content,
+        codec::encode(&content, codec::Compression::None).unwrap()
+    );
+    assert_eq!(content, codec::decode(content.clone()).unwrap());
+    assert_eq!(Vec::<u8>::new(), codec::decode(Vec::new()).unwrap());
+}
+
+#[test]
+fn encryption_round_trips_with_key_id() {
+    let data_key = Aes256Gcm::generate_key(OsRng);
+    let content = b"\\title{Unpublished proposal}";
+
+    let stored = encryption::encrypt(7, &data_key, content);
+
+    assert_eq!(Some(7), encryption::encrypted_with(&stored));
+    assert!(!stored
+        .windows(content.len())
+        .any(|window| window == content));
+    assert_eq!(
+        content.to_vec(),
+        encryption::decrypt(&data_key, &stored).unwrap()
+    );
+    assert!(encryption::decrypt(&Aes256Gcm::generate_key(OsRng), &stored).is_err());
+}
+
+#[test]
+fn encryption_leaves_plain_blobs_alone() {
+    let compressed = codec::encode(b"\\begin{document}", codec::Compression::Gzip).unwrap();
+
+    assert_eq!(None, encryption::encrypted_with(b"\\begin{document}"));
+    assert_eq!(None, encryption::encrypted_with(&compressed));
+}
+
+#[test]
+fn master_key_wraps_data_keys() {
+    let master_key = encryption::MasterKey::from_hex(&"ab".repeat(32)).unwrap();
+    let other_key = encryption::MasterKey::from_hex(&"cd".repeat(32)).unwrap();
+    let data_key = Aes256Gcm::generate_key(OsRng);
+
+    let wrapped_key = master_key.wrap(&data_key);
+
+    assert_eq!(Some(data_key), master_key.unwrap(&wrapped_key));
+    assert_eq!(None, other_key.unwrap(&wrapped_key));
+    assert!(encryption::MasterKey::from_hex("abcd").is_err());
+}
+
+// needs a PostgreSQL database named by `DATABASE_URL`, like the repository tests
+#[sqlx::test]
+async fn encrypted_store_trusts_recorded_keys_only(pool: sqlx::PgPool) {
+    let master_key = encryption::MasterKey::from_hex(&"ab".repeat(32)).unwrap();
+    let inner = Arc::new(MemoryBlobStore::default());
+    let store = encryption::EncryptedBlobStore::new(inner.clone(), &pool, master_key);
+    let key = |name: &str| object_key(&format!("{}{}", random_name(), name));
+    let (encrypted, renamed, copied, lookalike) = (key("a"), key("b"), key("c"), key("d"));
+    let content = b"\title{Unpublished proposal}".to_vec();
+
+    store.put(&encrypted, &content).await.unwrap();
+    let stored = inner.get(&encrypted).await.unwrap();
+    assert_ne!(content, stored);
+    assert_eq!(content, store.get(&encrypted).await.unwrap());
+
+    // a plain upload may start with a valid header, naming an existing key
+    let mut plain = stored[..codec::MAGIC.len() + 5].to_vec();
+    plain.extend_from_slice(b"not a ciphertext at all");
+    inner.put(&lookalike, &plain).await.unwrap();
+    assert_eq!(plain, store.get(&lookalike).await.unwrap());
+
+    store.rename(&encrypted, &renamed).await.unwrap();
+    assert_eq!(content, store.get(&renamed).await.unwrap());
+
+    // blobs encrypted before their keys were recorded are found by decrypting them
+    inner.put(&copied, &stored).await.unwrap();
+    assert_eq!(stored, store.get(&copied).await.unwrap());
+    store.record_encrypted_blobs().await.unwrap();
+    assert_eq!(content, store.get(&copied).await.unwrap());
+    assert_eq!(plain, store.get(&lookalike).await.unwrap());
+
+    store.delete(&renamed).await.unwrap();
+    inner.put(&renamed, &plain).await.unwrap();
+    assert_eq!(plain, store.get(&renamed).await.unwrap());
+}
+
+#[test]
+fn encryption_key_owner_follows_key_layout() {
+    assert_eq!(Some(3), encryption::key_owner("projects/3/documents/5"));
+    assert_eq!(None, encryption::key_owner("projects/3"));
+    assert_eq!(None, encryption::key_owner(&object_key("ab12")));
+}
@@ -5,7 +5,7 @@ use axum::{
     Json,
 };
 use http::{Request, StatusCode};
-use validator::{Validate, ValidationErrors};
+use validator::{Validate, ValidationError, ValidationErrors};
 
 pub enum ValidatedJsonRejection {
     JsonRejection(JsonRejection),
@@ -46,3 +46,33 @@ where
         Ok(Self(data))
     }
 }
+
+/// Names become path segments in exported archives, so they must not refer to a directory
+pub fn validate_path_segment(name: &str) -> Result<(), ValidationError> {
+    match name.trim() {
+        "." | ".." => Err(ValidationError::new("path_segment")),
+        _ => Ok(()),
+    }
+}
+
+/// Checks the ISO 7064 MOD 11-2 check digit, the format itself is matched by `ORCID_REGEX`
+pub fn validate_orcid_checksum(orcid: &str) -> Result<(), ValidationError> {
+    let digits: Vec<char> = orcid.chars().filter(|c| *c != '-').collect();
+    let Some((check, digits)) = digits.split_last() else {
+        return Err(ValidationError::new("orcid"));
+    };
+
+    let total = digits
+        .iter()
+        .filter_map(|digit| digit.to_digit(10))
+        .fold(0, |total, digit| (total + digit) * 2);
+    let expected = match (12 - total % 11) % 11 {
+        10 => 'X',
+        digit => char::from_digit(digit, 10).unwrap(),
+    };
+
+    match *check == expected {
+        true => Ok(()),
+        false => Err(ValidationError::new("orcid")),
+    }
+}